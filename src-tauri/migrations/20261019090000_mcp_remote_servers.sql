@@ -0,0 +1,2 @@
+-- Remote MCP servers may need an Authorization header; stored encrypted like provider keys
+ALTER TABLE mcp_servers ADD COLUMN auth_header TEXT;
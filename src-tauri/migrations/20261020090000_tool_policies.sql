@@ -0,0 +1,38 @@
+-- Per-server (tool = '*') and per-tool approval policies for model-initiated tool calls
+CREATE TABLE IF NOT EXISTS tool_policies (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    server TEXT NOT NULL,
+    tool TEXT NOT NULL DEFAULT '*',
+    policy TEXT NOT NULL CHECK (policy IN ('allow', 'ask', 'deny')),
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (server, tool)
+);
+
+-- Every tool invocation the model requested, whether or not it ran
+CREATE TABLE IF NOT EXISTS tool_audit (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    conversation_id TEXT NOT NULL,
+    call_id TEXT NOT NULL,
+    server TEXT,
+    tool TEXT NOT NULL,
+    arguments TEXT NOT NULL,
+    decision TEXT NOT NULL,
+    is_error INTEGER NOT NULL DEFAULT 0,
+    result_bytes INTEGER NOT NULL DEFAULT 0,
+    duration_ms INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_tool_audit_created ON tool_audit (created_at);
+
+CREATE TRIGGER IF NOT EXISTS tool_audit_no_update
+BEFORE UPDATE ON tool_audit
+BEGIN
+    SELECT RAISE(ABORT, 'tool_audit is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS tool_audit_no_delete
+BEFORE DELETE ON tool_audit
+BEGIN
+    SELECT RAISE(ABORT, 'tool_audit is append-only');
+END;
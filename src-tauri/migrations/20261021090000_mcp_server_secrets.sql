@@ -0,0 +1,8 @@
+-- Secrets declared by an installed server's manifest, encrypted and injected into its env
+CREATE TABLE IF NOT EXISTS mcp_server_secrets (
+    server_id INTEGER NOT NULL REFERENCES mcp_servers (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (server_id, name)
+);
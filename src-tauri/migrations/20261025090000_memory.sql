@@ -0,0 +1,26 @@
+-- Free-form notes the assistant can recall alongside past conversations
+CREATE TABLE IF NOT EXISTS memory_notes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title TEXT NOT NULL DEFAULT '',
+    content TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- One vector per message or note and embedding model (`provider:model`), stored as
+-- little-endian f32s
+CREATE TABLE IF NOT EXISTS memory_embeddings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    message_id INTEGER REFERENCES messages (id) ON DELETE CASCADE,
+    note_id INTEGER REFERENCES memory_notes (id) ON DELETE CASCADE,
+    model TEXT NOT NULL,
+    dimensions INTEGER NOT NULL,
+    embedding BLOB NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK ((message_id IS NULL) <> (note_id IS NULL))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_memory_embeddings_message
+    ON memory_embeddings (model, message_id) WHERE message_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_memory_embeddings_note
+    ON memory_embeddings (model, note_id) WHERE note_id IS NOT NULL;
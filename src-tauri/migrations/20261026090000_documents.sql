@@ -0,0 +1,35 @@
+-- Named sets of local files that chats can be grounded in
+CREATE TABLE IF NOT EXISTS document_collections (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Files ingested into a collection. `hash` (SHA-256 of the contents) and `model` decide
+-- whether a file has to be chunked and embedded again
+CREATE TABLE IF NOT EXISTS document_files (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    collection_id INTEGER NOT NULL REFERENCES document_collections (id) ON DELETE CASCADE,
+    path TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    model TEXT NOT NULL,
+    chunks INTEGER NOT NULL DEFAULT 0,
+    indexed_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (collection_id, path)
+);
+
+-- Overlapping slices of a file's text with their vector (little-endian f32s)
+CREATE TABLE IF NOT EXISTS document_chunks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    file_id INTEGER NOT NULL REFERENCES document_files (id) ON DELETE CASCADE,
+    collection_id INTEGER NOT NULL REFERENCES document_collections (id) ON DELETE CASCADE,
+    chunk_index INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    dimensions INTEGER NOT NULL,
+    embedding BLOB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_chunks_collection ON document_chunks (collection_id);
+CREATE INDEX IF NOT EXISTS idx_document_chunks_file ON document_chunks (file_id);
@@ -0,0 +1,16 @@
+-- Files sent with chat messages. Contents live under `<base_dir>/attachments/<hash>`, so
+-- the same file attached twice is stored once
+CREATE TABLE IF NOT EXISTS attachments (
+    hash TEXT PRIMARY KEY,
+    mime_type TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS message_attachments (
+    message_id INTEGER NOT NULL REFERENCES messages (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    hash TEXT NOT NULL REFERENCES attachments (hash),
+    name TEXT NOT NULL,
+    PRIMARY KEY (message_id, position)
+);
@@ -0,0 +1,8 @@
+-- Compacted history: the model-written summary of a conversation's messages up to and
+-- including `through_id`, sent in their place once they no longer fit the context window
+CREATE TABLE IF NOT EXISTS conversation_summaries (
+    conversation_id TEXT PRIMARY KEY REFERENCES conversations (id) ON DELETE CASCADE,
+    summary TEXT NOT NULL,
+    through_id INTEGER NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,16 @@
+-- Messages form a tree: editing a prompt or regenerating a reply adds a sibling instead
+-- of replacing anything. `active_leaf_id` is the tip of the branch the user is looking at
+ALTER TABLE messages ADD COLUMN parent_id INTEGER REFERENCES messages (id) ON DELETE CASCADE;
+ALTER TABLE conversations ADD COLUMN active_leaf_id INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_messages_parent ON messages (parent_id);
+
+-- Existing conversations become single-branch chains
+UPDATE messages
+SET parent_id = (
+    SELECT MAX(p.id) FROM messages p
+    WHERE p.conversation_id = messages.conversation_id AND p.id < messages.id
+);
+
+UPDATE conversations
+SET active_leaf_id = (SELECT MAX(m.id) FROM messages m WHERE m.conversation_id = conversations.id);
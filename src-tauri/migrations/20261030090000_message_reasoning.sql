@@ -0,0 +1,3 @@
+-- Thinking that reasoning models return next to their answer. Shown collapsed and never
+-- sent back to the model
+ALTER TABLE messages ADD COLUMN reasoning TEXT;
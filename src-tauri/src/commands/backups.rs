@@ -0,0 +1,35 @@
+use tauri::{AppHandle, State};
+
+use crate::config::{
+    backup::BackupInfo, recovery::SharedRecoveryService, service::SharedConfigService, ConfigError,
+};
+
+/// Served from the recovery service so backups can be listed even when the database failed
+/// to open.
+#[tauri::command]
+pub async fn list_backups(
+    recovery: State<'_, SharedRecoveryService>,
+) -> Result<Vec<BackupInfo>, String> {
+    recovery.list_backups().map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn create_backup(config: State<'_, SharedConfigService>) -> Result<BackupInfo, String> {
+    config.create_backup("manual").await.map_err(to_msg)
+}
+
+/// Stages the backup and restarts so it is swapped in before the database is reopened.
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    file_name: String,
+    config: State<'_, SharedConfigService>,
+) -> Result<(), String> {
+    config.restore_backup(&file_name).await.map_err(to_msg)?;
+    config.pool().close().await;
+    app.restart()
+}
+
+fn to_msg(err: ConfigError) -> String {
+    err.to_string()
+}
@@ -1,27 +1,124 @@
-use tauri::{State, Window};
+use tauri::{AppHandle, State};
+use tracing::warn;
 
 use crate::config::service::SharedConfigService;
+use crate::mcp::manager::SharedMcpManager;
 use crate::services::chat::{
+    edit_and_resend as edit_and_resend_service, regenerate_message as regenerate_message_service,
     send_chat, stream_chat as stream_chat_service, ChatRequest, ChatResponse,
 };
+use crate::services::conversations::{BranchMessage, ConversationStore};
+use crate::services::job_queue::SharedJobQueue;
+use crate::services::memory::schedule_indexing;
+use crate::services::titles::schedule_titling;
+use crate::services::tool_policy::SharedToolPolicyService;
 
 #[tauri::command]
 pub async fn invoke_chat(
+    app: AppHandle,
     request: ChatRequest,
     config: State<'_, SharedConfigService>,
+    mcp: State<'_, SharedMcpManager>,
+    policies: State<'_, SharedToolPolicyService>,
+    queue: State<'_, SharedJobQueue>,
 ) -> Result<ChatResponse, String> {
-    send_chat(&config, request)
+    let response = send_chat(&app, &config, &mcp, &policies, request)
         .await
-        .map_err(|err| err.to_string())
+        .map_err(|err| err.to_string())?;
+    after_reply(&config, &queue, &response.conversation_id).await;
+    Ok(response)
 }
 
 #[tauri::command]
 pub async fn stream_chat(
-    window: Window,
+    app: AppHandle,
     request: ChatRequest,
     config: State<'_, SharedConfigService>,
+    mcp: State<'_, SharedMcpManager>,
+    policies: State<'_, SharedToolPolicyService>,
+    queue: State<'_, SharedJobQueue>,
 ) -> Result<(), String> {
-    stream_chat_service(&window, &config, request)
+    let conversation_id = stream_chat_service(&app, &config, &mcp, &policies, request)
+        .await
+        .map_err(|err| err.to_string())?;
+    after_reply(&config, &queue, &conversation_id).await;
+    Ok(())
+}
+
+/// Streams another reply to the prompt behind `message_id` on a new branch. `request`
+/// carries options such as the model; its prompt is ignored.
+#[tauri::command]
+pub async fn regenerate_message(
+    app: AppHandle,
+    message_id: i64,
+    request: ChatRequest,
+    config: State<'_, SharedConfigService>,
+    mcp: State<'_, SharedMcpManager>,
+    policies: State<'_, SharedToolPolicyService>,
+    queue: State<'_, SharedJobQueue>,
+) -> Result<(), String> {
+    let conversation_id =
+        regenerate_message_service(&app, &config, &mcp, &policies, message_id, request)
+            .await
+            .map_err(|err| err.to_string())?;
+    after_reply(&config, &queue, &conversation_id).await;
+    Ok(())
+}
+
+/// Streams a reply to `request.prompt` as an edit of the prompt `message_id`, keeping the
+/// original on its own branch.
+#[tauri::command]
+pub async fn edit_and_resend(
+    app: AppHandle,
+    message_id: i64,
+    request: ChatRequest,
+    config: State<'_, SharedConfigService>,
+    mcp: State<'_, SharedMcpManager>,
+    policies: State<'_, SharedToolPolicyService>,
+    queue: State<'_, SharedJobQueue>,
+) -> Result<(), String> {
+    let conversation_id =
+        edit_and_resend_service(&app, &config, &mcp, &policies, message_id, request)
+            .await
+            .map_err(|err| err.to_string())?;
+    after_reply(&config, &queue, &conversation_id).await;
+    Ok(())
+}
+
+/// Makes the branch through `message_id` active and returns it.
+#[tauri::command]
+pub async fn switch_branch(
+    message_id: i64,
+    config: State<'_, SharedConfigService>,
+) -> Result<Vec<BranchMessage>, String> {
+    let store = ConversationStore::new(config.pool().clone());
+    let conversation_id = store
+        .switch_branch(message_id)
+        .await
+        .map_err(|err| err.to_string())?;
+    store
+        .branch(&conversation_id)
         .await
         .map_err(|err| err.to_string())
 }
+
+#[tauri::command]
+pub async fn get_conversation_branch(
+    conversation_id: String,
+    config: State<'_, SharedConfigService>,
+) -> Result<Vec<BranchMessage>, String> {
+    ConversationStore::new(config.pool().clone())
+        .branch(&conversation_id)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// The reply is already delivered, so failing to queue follow-up work is only logged.
+async fn after_reply(config: &SharedConfigService, queue: &SharedJobQueue, conversation_id: &str) {
+    if let Err(err) = schedule_indexing(config, queue).await {
+        warn!("unable to queue memory indexing: {err}");
+    }
+    if let Err(err) = schedule_titling(config, queue, conversation_id).await {
+        warn!("unable to queue conversation titling: {err}");
+    }
+}
@@ -0,0 +1,38 @@
+use tauri::State;
+
+use crate::config::service::SharedConfigService;
+use crate::services::conversation_export::{
+    export_conversation as export_conversation_service,
+    import_conversations as import_conversations_service, ExportError, ExportFormat,
+};
+
+/// Writes the conversation to `path` as Markdown, JSON or standalone HTML.
+#[tauri::command]
+pub async fn export_conversation(
+    conversation_id: String,
+    format: ExportFormat,
+    path: String,
+    config: State<'_, SharedConfigService>,
+) -> Result<(), String> {
+    let contents = export_conversation_service(config.pool(), &conversation_id, format)
+        .await
+        .map_err(to_msg)?;
+    std::fs::write(&path, contents).map_err(|err| err.to_string())
+}
+
+/// Imports our JSON export or a ChatGPT `conversations.json`. Returns the new
+/// conversation ids.
+#[tauri::command]
+pub async fn import_conversations(
+    path: String,
+    config: State<'_, SharedConfigService>,
+) -> Result<Vec<String>, String> {
+    let contents = std::fs::read_to_string(&path).map_err(|err| err.to_string())?;
+    import_conversations_service(config.pool(), &contents)
+        .await
+        .map_err(to_msg)
+}
+
+fn to_msg(err: ExportError) -> String {
+    err.to_string()
+}
@@ -0,0 +1,105 @@
+use serde_json::json;
+use tauri::State;
+
+use crate::config::service::SharedConfigService;
+use crate::services::documents::{
+    DocumentCollection, DocumentError, DocumentFile, DocumentHit, DocumentStore, IngestRequest,
+    INGEST_DOCUMENTS_JOB,
+};
+use crate::services::embeddings::EmbeddingClient;
+use crate::services::job_queue::SharedJobQueue;
+
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+#[tauri::command]
+pub async fn list_document_collections(
+    config: State<'_, SharedConfigService>,
+) -> Result<Vec<DocumentCollection>, String> {
+    DocumentStore::new(config.pool().clone())
+        .list_collections()
+        .await
+        .map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn create_document_collection(
+    name: String,
+    config: State<'_, SharedConfigService>,
+) -> Result<DocumentCollection, String> {
+    DocumentStore::new(config.pool().clone())
+        .create_collection(&name)
+        .await
+        .map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn delete_document_collection(
+    id: i64,
+    config: State<'_, SharedConfigService>,
+) -> Result<(), String> {
+    DocumentStore::new(config.pool().clone())
+        .delete_collection(id)
+        .await
+        .map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn list_document_files(
+    collection_id: i64,
+    config: State<'_, SharedConfigService>,
+) -> Result<Vec<DocumentFile>, String> {
+    DocumentStore::new(config.pool().clone())
+        .list_files(collection_id)
+        .await
+        .map_err(to_msg)
+}
+
+/// Queues ingestion of files and folders into a collection. Re-running it on the same
+/// paths only re-embeds what changed. Returns the job id.
+#[tauri::command]
+pub async fn ingest_documents(
+    collection_id: i64,
+    paths: Vec<String>,
+    config: State<'_, SharedConfigService>,
+    queue: State<'_, SharedJobQueue>,
+) -> Result<i64, String> {
+    DocumentStore::new(config.pool().clone())
+        .collection(collection_id)
+        .await
+        .map_err(to_msg)?;
+    queue
+        .enqueue(
+            INGEST_DOCUMENTS_JOB,
+            json!(IngestRequest {
+                collection_id,
+                paths
+            }),
+        )
+        .await
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+pub async fn search_documents(
+    collection_id: i64,
+    query: String,
+    limit: Option<usize>,
+    config: State<'_, SharedConfigService>,
+) -> Result<Vec<DocumentHit>, String> {
+    let client = EmbeddingClient::from_settings(&config)
+        .await
+        .map_err(|err| err.to_string())?;
+    DocumentStore::new(config.pool().clone())
+        .search(
+            &client,
+            collection_id,
+            &query,
+            limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
+        )
+        .await
+        .map_err(to_msg)
+}
+
+fn to_msg(err: DocumentError) -> String {
+    err.to_string()
+}
@@ -0,0 +1,28 @@
+use tauri::State;
+
+use crate::services::job_queue::{JobQueueError, JobStatus, QueuedJob, SharedJobQueue};
+
+const DEFAULT_JOB_LIMIT: i64 = 100;
+
+/// Background jobs, newest first, optionally filtered by status.
+#[tauri::command]
+pub async fn list_jobs(
+    status: Option<JobStatus>,
+    limit: Option<i64>,
+    queue: State<'_, SharedJobQueue>,
+) -> Result<Vec<QueuedJob>, String> {
+    queue
+        .list(status, limit.unwrap_or(DEFAULT_JOB_LIMIT))
+        .await
+        .map_err(to_msg)
+}
+
+/// Requeues a dead-lettered job with a fresh set of attempts.
+#[tauri::command]
+pub async fn retry_job(id: i64, queue: State<'_, SharedJobQueue>) -> Result<QueuedJob, String> {
+    queue.retry(id).await.map_err(to_msg)
+}
+
+fn to_msg(err: JobQueueError) -> String {
+    err.to_string()
+}
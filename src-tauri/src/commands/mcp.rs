@@ -0,0 +1,122 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use tauri::State;
+
+use crate::mcp::{
+    manager::{AddMcpServerPayload, McpPrompt, McpResource, McpServerSummary, SharedMcpManager},
+    protocol::{GetPromptResult, ResourceContents},
+    McpError,
+};
+
+#[tauri::command]
+pub async fn list_mcp_servers(
+    mcp: State<'_, SharedMcpManager>,
+) -> Result<Vec<McpServerSummary>, String> {
+    mcp.list().await.map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn add_mcp_server(
+    payload: AddMcpServerPayload,
+    mcp: State<'_, SharedMcpManager>,
+) -> Result<McpServerSummary, String> {
+    mcp.add(payload).await.map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn remove_mcp_server(
+    slug: String,
+    mcp: State<'_, SharedMcpManager>,
+) -> Result<(), String> {
+    mcp.remove(&slug).await.map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn start_mcp_server(
+    slug: String,
+    mcp: State<'_, SharedMcpManager>,
+) -> Result<McpServerSummary, String> {
+    mcp.start(&slug).await.map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn stop_mcp_server(
+    slug: String,
+    mcp: State<'_, SharedMcpManager>,
+) -> Result<McpServerSummary, String> {
+    mcp.stop(&slug).await.map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn install_mcp_server(
+    manifest_path: PathBuf,
+    secrets: Option<HashMap<String, String>>,
+    auto_start: Option<bool>,
+    mcp: State<'_, SharedMcpManager>,
+) -> Result<McpServerSummary, String> {
+    mcp.install(
+        &manifest_path,
+        secrets.unwrap_or_default(),
+        auto_start.unwrap_or(false),
+    )
+    .await
+    .map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn upgrade_mcp_server(
+    slug: String,
+    manifest_path: PathBuf,
+    secrets: Option<HashMap<String, String>>,
+    mcp: State<'_, SharedMcpManager>,
+) -> Result<McpServerSummary, String> {
+    mcp.upgrade(&slug, &manifest_path, secrets.unwrap_or_default())
+        .await
+        .map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn uninstall_mcp_server(
+    slug: String,
+    mcp: State<'_, SharedMcpManager>,
+) -> Result<(), String> {
+    mcp.uninstall(&slug).await.map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn list_mcp_resources(
+    mcp: State<'_, SharedMcpManager>,
+) -> Result<Vec<McpResource>, String> {
+    Ok(mcp.list_resources().await)
+}
+
+#[tauri::command]
+pub async fn read_mcp_resource(
+    server: String,
+    uri: String,
+    mcp: State<'_, SharedMcpManager>,
+) -> Result<Vec<ResourceContents>, String> {
+    mcp.read_resource(&server, &uri).await.map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn list_mcp_prompts(mcp: State<'_, SharedMcpManager>) -> Result<Vec<McpPrompt>, String> {
+    Ok(mcp.list_prompts().await)
+}
+
+#[tauri::command]
+pub async fn get_mcp_prompt(
+    server: String,
+    name: String,
+    arguments: Option<BTreeMap<String, String>>,
+    mcp: State<'_, SharedMcpManager>,
+) -> Result<GetPromptResult, String> {
+    mcp.get_prompt(&server, &name, &arguments.unwrap_or_default())
+        .await
+        .map_err(to_msg)
+}
+
+fn to_msg(err: McpError) -> String {
+    err.to_string()
+}
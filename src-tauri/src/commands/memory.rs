@@ -0,0 +1,77 @@
+use serde_json::Value;
+use tauri::State;
+
+use crate::config::service::SharedConfigService;
+use crate::services::embeddings::EmbeddingClient;
+use crate::services::job_queue::SharedJobQueue;
+use crate::services::memory::{
+    schedule_indexing, MemoryError, MemoryHit, MemoryNote, MemoryStore, INDEX_MEMORY_JOB,
+};
+
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+#[tauri::command]
+pub async fn list_memory_notes(
+    config: State<'_, SharedConfigService>,
+) -> Result<Vec<MemoryNote>, String> {
+    MemoryStore::new(config.pool().clone())
+        .list_notes()
+        .await
+        .map_err(to_msg)
+}
+
+/// Saves a note and queues it for embedding when memory is enabled.
+#[tauri::command]
+pub async fn add_memory_note(
+    title: Option<String>,
+    content: String,
+    config: State<'_, SharedConfigService>,
+    queue: State<'_, SharedJobQueue>,
+) -> Result<MemoryNote, String> {
+    let note = MemoryStore::new(config.pool().clone())
+        .add_note(title.as_deref().unwrap_or_default(), &content)
+        .await
+        .map_err(to_msg)?;
+    schedule_indexing(&config, &queue).await.map_err(to_msg)?;
+    Ok(note)
+}
+
+#[tauri::command]
+pub async fn delete_memory_note(
+    id: i64,
+    config: State<'_, SharedConfigService>,
+) -> Result<(), String> {
+    MemoryStore::new(config.pool().clone())
+        .delete_note(id)
+        .await
+        .map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn search_memory(
+    query: String,
+    limit: Option<usize>,
+    config: State<'_, SharedConfigService>,
+) -> Result<Vec<MemoryHit>, String> {
+    let client = EmbeddingClient::from_settings(&config)
+        .await
+        .map_err(|err| err.to_string())?;
+    MemoryStore::new(config.pool().clone())
+        .search(&client, &query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT), None)
+        .await
+        .map_err(to_msg)
+}
+
+/// Queues a full catch-up of the index, e.g. after switching embedding models. Returns the
+/// job id.
+#[tauri::command]
+pub async fn index_memory(queue: State<'_, SharedJobQueue>) -> Result<i64, String> {
+    queue
+        .enqueue(INDEX_MEMORY_JOB, Value::Null)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+fn to_msg(err: MemoryError) -> String {
+    err.to_string()
+}
@@ -1,3 +1,15 @@
+pub mod backups;
 pub mod chat;
+pub mod conversation_export;
+pub mod documents;
+pub mod jobs;
+pub mod mcp;
+pub mod memory;
 pub mod preferences;
 pub mod providers;
+pub mod recovery;
+pub mod scheduled_jobs;
+pub mod search;
+pub mod settings;
+pub mod tools;
+pub mod transfer;
@@ -0,0 +1,58 @@
+use tauri::{AppHandle, Manager, State};
+
+use crate::config::{
+    health::HealthReport, recovery::SharedRecoveryService, service::SharedConfigService,
+    ConfigError,
+};
+
+#[tauri::command]
+pub async fn get_health_report(
+    app: AppHandle,
+    recovery: State<'_, SharedRecoveryService>,
+) -> Result<HealthReport, String> {
+    match app.try_state::<SharedConfigService>() {
+        Some(config) => Ok(recovery.refresh(&config).await),
+        None => Ok(recovery.report()),
+    }
+}
+
+#[tauri::command]
+pub async fn repair_database(
+    app: AppHandle,
+    recovery: State<'_, SharedRecoveryService>,
+) -> Result<(), String> {
+    close_config(&app).await;
+    recovery.repair_database().await.map_err(to_msg)?;
+    app.restart()
+}
+
+#[tauri::command]
+pub async fn recover_from_backup(
+    app: AppHandle,
+    file_name: String,
+    recovery: State<'_, SharedRecoveryService>,
+) -> Result<(), String> {
+    close_config(&app).await;
+    recovery.restore_backup(&file_name).map_err(to_msg)?;
+    app.restart()
+}
+
+#[tauri::command]
+pub async fn reset_database(
+    app: AppHandle,
+    recovery: State<'_, SharedRecoveryService>,
+) -> Result<(), String> {
+    close_config(&app).await;
+    recovery.reset_database().map_err(to_msg)?;
+    app.restart()
+}
+
+async fn close_config(app: &AppHandle) {
+    if let Some(config) = app.try_state::<SharedConfigService>() {
+        config.pool().close().await;
+    }
+}
+
+fn to_msg(err: ConfigError) -> String {
+    err.to_string()
+}
@@ -0,0 +1,63 @@
+use tauri::State;
+
+use crate::services::job_queue::QueuedJob;
+use crate::services::scheduled_jobs::{
+    NewScheduledJob, ScheduledJob, ScheduledJobError, ScheduledJobRun, SharedScheduledJobService,
+};
+
+const DEFAULT_HISTORY_LIMIT: i64 = 50;
+
+#[tauri::command]
+pub async fn list_scheduled_jobs(
+    jobs: State<'_, SharedScheduledJobService>,
+) -> Result<Vec<ScheduledJob>, String> {
+    jobs.list().await.map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn create_scheduled_job(
+    job: NewScheduledJob,
+    jobs: State<'_, SharedScheduledJobService>,
+) -> Result<ScheduledJob, String> {
+    jobs.create(job).await.map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn pause_scheduled_job(
+    id: i64,
+    paused: bool,
+    jobs: State<'_, SharedScheduledJobService>,
+) -> Result<ScheduledJob, String> {
+    jobs.set_paused(id, paused).await.map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn delete_scheduled_job(
+    id: i64,
+    jobs: State<'_, SharedScheduledJobService>,
+) -> Result<(), String> {
+    jobs.delete(id).await.map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn run_scheduled_job_now(
+    id: i64,
+    jobs: State<'_, SharedScheduledJobService>,
+) -> Result<QueuedJob, String> {
+    jobs.run_now(id).await.map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn list_scheduled_job_runs(
+    id: i64,
+    limit: Option<i64>,
+    jobs: State<'_, SharedScheduledJobService>,
+) -> Result<Vec<ScheduledJobRun>, String> {
+    jobs.history(id, limit.unwrap_or(DEFAULT_HISTORY_LIMIT))
+        .await
+        .map_err(to_msg)
+}
+
+fn to_msg(err: ScheduledJobError) -> String {
+    err.to_string()
+}
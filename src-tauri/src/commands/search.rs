@@ -0,0 +1,18 @@
+use tauri::State;
+
+use crate::config::service::SharedConfigService;
+use crate::services::search::{
+    search_messages as search_service, MessageHit, MessageSearchQuery, SearchError,
+};
+
+#[tauri::command]
+pub async fn search_messages(
+    query: MessageSearchQuery,
+    config: State<'_, SharedConfigService>,
+) -> Result<Vec<MessageHit>, String> {
+    search_service(config.pool(), &query).await.map_err(to_msg)
+}
+
+fn to_msg(err: SearchError) -> String {
+    err.to_string()
+}
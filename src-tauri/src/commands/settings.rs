@@ -0,0 +1,23 @@
+use tauri::State;
+
+use crate::config::{service::SharedConfigService, settings::SettingEntry, ConfigError};
+
+#[tauri::command]
+pub async fn list_settings(
+    config: State<'_, SharedConfigService>,
+) -> Result<Vec<SettingEntry>, String> {
+    config.list_settings().await.map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn update_setting(
+    key: String,
+    value: serde_json::Value,
+    config: State<'_, SharedConfigService>,
+) -> Result<(), String> {
+    config.update_setting(&key, &value).await.map_err(to_msg)
+}
+
+fn to_msg(err: ConfigError) -> String {
+    err.to_string()
+}
@@ -0,0 +1,68 @@
+use tauri::State;
+
+use crate::services::tool_policy::{
+    AuditRecord, SharedToolPolicyService, ToolPolicy, ToolPolicyError, ToolPolicyRule,
+};
+
+const DEFAULT_AUDIT_LIMIT: i64 = 200;
+
+#[tauri::command]
+pub async fn list_tool_policies(
+    policies: State<'_, SharedToolPolicyService>,
+) -> Result<Vec<ToolPolicyRule>, String> {
+    policies.list_policies().await.map_err(to_msg)
+}
+
+/// Sets the policy for one tool, or for every tool of `server` when `tool` is omitted.
+#[tauri::command]
+pub async fn set_tool_policy(
+    server: String,
+    tool: Option<String>,
+    policy: ToolPolicy,
+    policies: State<'_, SharedToolPolicyService>,
+) -> Result<(), String> {
+    policies
+        .set_policy(&server, tool.as_deref(), policy)
+        .await
+        .map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn clear_tool_policy(
+    server: String,
+    tool: Option<String>,
+    policies: State<'_, SharedToolPolicyService>,
+) -> Result<(), String> {
+    policies
+        .clear_policy(&server, tool.as_deref())
+        .await
+        .map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn approve_tool_call(
+    approval_id: String,
+    approved: bool,
+    remember: Option<bool>,
+    policies: State<'_, SharedToolPolicyService>,
+) -> Result<(), String> {
+    policies
+        .resolve(&approval_id, approved, remember.unwrap_or(false))
+        .await
+        .map_err(to_msg)
+}
+
+#[tauri::command]
+pub async fn list_tool_audit(
+    limit: Option<i64>,
+    policies: State<'_, SharedToolPolicyService>,
+) -> Result<Vec<AuditRecord>, String> {
+    policies
+        .audit_log(limit.unwrap_or(DEFAULT_AUDIT_LIMIT))
+        .await
+        .map_err(to_msg)
+}
+
+fn to_msg(err: ToolPolicyError) -> String {
+    err.to_string()
+}
@@ -0,0 +1,39 @@
+use tauri::State;
+
+use crate::config::{
+    service::SharedConfigService,
+    transfer::{ConflictStrategy, ImportSummary},
+    ConfigError,
+};
+
+#[tauri::command]
+pub async fn export_config(
+    path: String,
+    passphrase: String,
+    config: State<'_, SharedConfigService>,
+) -> Result<(), String> {
+    let data = config.export_config(&passphrase).await.map_err(to_msg)?;
+    tokio::fs::write(&path, data)
+        .await
+        .map_err(|err| to_msg(err.into()))
+}
+
+#[tauri::command]
+pub async fn import_config(
+    path: String,
+    passphrase: String,
+    strategy: Option<ConflictStrategy>,
+    config: State<'_, SharedConfigService>,
+) -> Result<ImportSummary, String> {
+    let data = tokio::fs::read(&path)
+        .await
+        .map_err(|err| to_msg(err.into()))?;
+    config
+        .import_config(&data, &passphrase, strategy.unwrap_or_default())
+        .await
+        .map_err(to_msg)
+}
+
+fn to_msg(err: ConfigError) -> String {
+    err.to_string()
+}
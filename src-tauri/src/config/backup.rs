@@ -0,0 +1,194 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tokio_cron_scheduler::{Job, JobScheduler};
+use tracing::{info, warn};
+
+use super::{error::ConfigError, paths::ConfigPaths, service::SharedConfigService};
+
+/// Every six hours, on the hour.
+pub const BACKUP_SCHEDULE: &str = "0 0 */6 * * *";
+pub const BACKUP_RETENTION: usize = 10;
+/// Pre-migration snapshots rotate on their own, so routine backups never push out the copy
+/// taken before an upgrade.
+pub const PRE_MIGRATION_RETENTION: usize = 3;
+/// Snapshots of the live database taken before a staged restore replaces it.
+pub const PRE_RESTORE_RETENTION: usize = 3;
+/// Repaired copies kept after recovery staged one in place of a damaged database.
+pub const REPAIRED_RETENTION: usize = 3;
+
+const BACKUP_PREFIX: &str = "aethos-";
+const BACKUP_EXTENSION: &str = "db3";
+const PENDING_RESTORE: &str = "pending-restore.db3";
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub reason: String,
+    pub created_at: u64,
+    pub size_bytes: u64,
+}
+
+/// Writes a consistent snapshot of the live database with `VACUUM INTO`, then prunes old
+/// snapshots taken for the same `reason` beyond `keep`.
+pub async fn create_backup(
+    pool: &SqlitePool,
+    paths: &ConfigPaths,
+    reason: &str,
+    keep: usize,
+) -> Result<BackupInfo, ConfigError> {
+    fs::create_dir_all(&paths.backup_dir)?;
+    let mut created_at = now_millis();
+    let mut target = backup_path(paths, created_at, reason);
+    while target.exists() {
+        created_at += 1;
+        target = backup_path(paths, created_at, reason);
+    }
+    let target_str = target
+        .to_str()
+        .ok_or_else(|| ConfigError::InvalidPath("backup path contains invalid UTF-8".into()))?;
+    sqlx::query("VACUUM INTO ?1")
+        .bind(target_str)
+        .execute(pool)
+        .await?;
+    info!("created database backup {}", target.display());
+    prune_backups(paths, reason, keep)?;
+    describe(&target).ok_or_else(|| ConfigError::InvalidPath(target.display().to_string()))
+}
+
+/// Lists snapshots newest first.
+pub fn list_backups(paths: &ConfigPaths) -> Result<Vec<BackupInfo>, ConfigError> {
+    if !paths.backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(&paths.backup_dir)? {
+        if let Some(info) = describe(&entry?.path()) {
+            backups.push(info);
+        }
+    }
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+    Ok(backups)
+}
+
+/// Stages a snapshot to replace the live database on the next launch. The live database is
+/// backed up first so the restore itself can be undone; nothing is staged if that fails.
+pub async fn stage_restore(
+    pool: &SqlitePool,
+    paths: &ConfigPaths,
+    file_name: &str,
+) -> Result<(), ConfigError> {
+    let source = resolve_backup(paths, file_name)?;
+    create_backup(pool, paths, "pre-restore", PRE_RESTORE_RETENTION).await?;
+    stage_file(paths, &source)?;
+    info!("staged restore of {file_name}");
+    Ok(())
+}
+
+pub(super) fn stage_file(paths: &ConfigPaths, source: &Path) -> Result<(), ConfigError> {
+    fs::copy(source, paths.backup_dir.join(PENDING_RESTORE))?;
+    Ok(())
+}
+
+/// Swaps a staged snapshot into place. Must run before the pool is opened.
+pub fn apply_pending_restore(paths: &ConfigPaths) -> Result<bool, ConfigError> {
+    let pending = paths.backup_dir.join(PENDING_RESTORE);
+    if !pending.exists() {
+        return Ok(false);
+    }
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = paths.db_path.clone().into_os_string();
+        sidecar.push(suffix);
+        let sidecar = PathBuf::from(sidecar);
+        if sidecar.exists() {
+            fs::remove_file(sidecar)?;
+        }
+    }
+    fs::rename(&pending, &paths.db_path)?;
+    info!("restored database from staged backup");
+    Ok(true)
+}
+
+/// Takes a backup on [`BACKUP_SCHEDULE`] unless the `backup.enabled` setting is off.
+pub async fn start_backup_scheduler(
+    config: SharedConfigService,
+) -> Result<JobScheduler, ConfigError> {
+    let scheduler = JobScheduler::new().await?;
+    let job = Job::new_async(BACKUP_SCHEDULE, move |_id, _scheduler| {
+        let config = config.clone();
+        Box::pin(async move {
+            match config.get_value::<bool>("backup.enabled").await {
+                Ok(Some(false)) => return,
+                Ok(_) => {}
+                Err(err) => warn!("unable to read backup settings: {err}"),
+            }
+            if let Err(err) = config.create_backup("scheduled").await {
+                warn!("scheduled backup failed: {err}");
+            }
+        })
+    })?;
+    scheduler.add(job).await?;
+    scheduler.start().await?;
+    Ok(scheduler)
+}
+
+fn prune_backups(paths: &ConfigPaths, reason: &str, keep: usize) -> Result<(), ConfigError> {
+    let same_reason = list_backups(paths)?
+        .into_iter()
+        .filter(|backup| backup.reason == reason);
+    for stale in same_reason.skip(keep) {
+        fs::remove_file(paths.backup_dir.join(&stale.file_name))?;
+    }
+    Ok(())
+}
+
+pub(super) fn resolve_backup(paths: &ConfigPaths, file_name: &str) -> Result<PathBuf, ConfigError> {
+    let path = paths.backup_dir.join(file_name);
+    let is_plain_name = Path::new(file_name).file_name() == Some(file_name.as_ref());
+    if !is_plain_name || describe(&path).is_none() {
+        return Err(ConfigError::InvalidPath(format!(
+            "unknown backup `{file_name}`"
+        )));
+    }
+    let mut header = [0u8; SQLITE_HEADER.len()];
+    let read = std::io::Read::read_exact(&mut fs::File::open(&path)?, &mut header);
+    if read.is_err() || header != SQLITE_HEADER {
+        return Err(ConfigError::InvalidPath(format!(
+            "backup `{file_name}` is not a SQLite database"
+        )));
+    }
+    Ok(path)
+}
+
+fn describe(path: &Path) -> Option<BackupInfo> {
+    if path.extension()? != BACKUP_EXTENSION {
+        return None;
+    }
+    let file_name = path.file_name()?.to_str()?.to_string();
+    let stem = path.file_stem()?.to_str()?.strip_prefix(BACKUP_PREFIX)?;
+    let (created_at, reason) = stem.split_once('-')?;
+    Some(BackupInfo {
+        created_at: created_at.parse().ok()?,
+        reason: reason.to_string(),
+        size_bytes: fs::metadata(path).ok()?.len(),
+        file_name,
+    })
+}
+
+fn backup_path(paths: &ConfigPaths, created_at: u64, reason: &str) -> PathBuf {
+    paths.backup_dir.join(format!(
+        "{BACKUP_PREFIX}{created_at}-{reason}.{BACKUP_EXTENSION}"
+    ))
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
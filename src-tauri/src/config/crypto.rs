@@ -10,13 +10,16 @@ use rand::rngs::OsRng;
 use rand::RngCore;
 use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM};
 use ring::rand::SecureRandom;
-use tracing::{debug, warn};
+use tracing::debug;
+#[cfg(target_os = "macos")]
+use tracing::warn;
 
 use super::error::ConfigError;
 use super::paths::ConfigPaths;
 
 const MASTER_KEY_BYTES: usize = 32;
 const NONCE_BYTES: usize = 12;
+const PAYLOAD_VERSION: u8 = 1;
 const KEY_SERVICE: &str = "com.aethos.config";
 const KEY_USER: &str = "encryption-master";
 
@@ -35,43 +38,68 @@ impl CryptoService {
     }
 
     pub fn encrypt(&self, value: &str) -> Result<String, ConfigError> {
+        Ok(BASE64.encode(self.encrypt_bytes(value.as_bytes())?))
+    }
+
+    pub fn decrypt(&self, cipher: &str) -> Result<String, ConfigError> {
+        let payload = BASE64.decode(cipher)?;
+        Ok(String::from_utf8(self.decrypt_bytes(&payload)?)?)
+    }
+
+    /// Seals `plain` into `version || nonce || ciphertext || tag`.
+    pub fn encrypt_bytes(&self, plain: &[u8]) -> Result<Vec<u8>, ConfigError> {
         let mut nonce = [0u8; NONCE_BYTES];
         self.rng
             .fill(&mut nonce)
             .map_err(|_| ConfigError::Encryption("failed to generate nonce".into()))?;
 
-        let mut in_out = value.as_bytes().to_vec();
-        in_out.resize(in_out.len() + AES_256_GCM.tag_len(), 0);
+        let mut in_out = plain.to_vec();
         self.key.seal_in_place_append_tag(
             Nonce::assume_unique_for_key(nonce),
-            Aad::empty(),
+            Aad::from([PAYLOAD_VERSION]),
             &mut in_out,
         )?;
-        let mut payload = Vec::with_capacity(NONCE_BYTES + in_out.len());
+        let mut payload = Vec::with_capacity(1 + NONCE_BYTES + in_out.len());
+        payload.push(PAYLOAD_VERSION);
         payload.extend_from_slice(&nonce);
         payload.extend_from_slice(&in_out);
-        Ok(BASE64.encode(payload))
+        Ok(payload)
     }
 
-    pub fn decrypt(&self, cipher: &str) -> Result<String, ConfigError> {
-        let mut payload = BASE64.decode(cipher)?;
-        if payload.len() <= NONCE_BYTES {
-            return Err(ConfigError::Encryption("payload too small".into()));
+    /// Opens a payload produced by [`encrypt_bytes`](Self::encrypt_bytes), returning the exact
+    /// plaintext. Payloads written before versioning are still accepted.
+    pub fn decrypt_bytes(&self, payload: &[u8]) -> Result<Vec<u8>, ConfigError> {
+        if payload.first() == Some(&PAYLOAD_VERSION) {
+            if let Some(plain) = self.open(&payload[1..], Aad::from([PAYLOAD_VERSION])) {
+                return Ok(plain);
+            }
         }
-        let nonce_bytes: [u8; NONCE_BYTES] = payload[..NONCE_BYTES]
-            .try_into()
-            .map_err(|_| ConfigError::Encryption("invalid nonce length".into()))?;
-        let nonce = Nonce::assume_unique_for_key(nonce_bytes);
-        let mut buffer = payload.split_off(NONCE_BYTES);
-        let plaintext = self
-            .key
-            .open_in_place(nonce, Aad::empty(), &mut buffer)
-            .map_err(|_| ConfigError::Encryption("failed to decrypt payload".into()))?;
-        let mut plain = plaintext.to_vec();
-        while plain.last() == Some(&0) {
-            plain.pop();
+        self.decrypt_legacy(payload)
+    }
+
+    /// Legacy payloads are `nonce || ciphertext || tag` where the sealed plaintext carried
+    /// `tag_len` zero bytes of padding.
+    fn decrypt_legacy(&self, payload: &[u8]) -> Result<Vec<u8>, ConfigError> {
+        let mut plain = self
+            .open(payload, Aad::empty())
+            .ok_or_else(|| ConfigError::Encryption("failed to decrypt payload".into()))?;
+        let padding = AES_256_GCM.tag_len();
+        if plain.len() >= padding && plain[plain.len() - padding..].iter().all(|b| *b == 0) {
+            plain.truncate(plain.len() - padding);
+        }
+        Ok(plain)
+    }
+
+    fn open<A: AsRef<[u8]>>(&self, sealed: &[u8], aad: Aad<A>) -> Option<Vec<u8>> {
+        if sealed.len() < NONCE_BYTES + AES_256_GCM.tag_len() {
+            return None;
         }
-        Ok(String::from_utf8(plain)?)
+        let nonce_bytes: [u8; NONCE_BYTES] = sealed[..NONCE_BYTES].try_into().ok()?;
+        let nonce = Nonce::assume_unique_for_key(nonce_bytes);
+        let mut buffer = sealed[NONCE_BYTES..].to_vec();
+        let plain_len = self.key.open_in_place(nonce, aad, &mut buffer).ok()?.len();
+        buffer.truncate(plain_len);
+        Some(buffer)
     }
 }
 
@@ -99,6 +127,7 @@ impl KeyManager {
         Ok(generated)
     }
 
+    #[allow(clippy::wrong_self_convention)]
     fn from_keychain(&self) -> Result<Option<[u8; MASTER_KEY_BYTES]>, ConfigError> {
         #[cfg(target_os = "macos")]
         {
@@ -137,6 +166,7 @@ impl KeyManager {
         }
     }
 
+    #[allow(clippy::wrong_self_convention)]
     fn from_disk(&self) -> Result<Option<[u8; MASTER_KEY_BYTES]>, ConfigError> {
         if !self.key_path.exists() {
             return Ok(None);
@@ -203,3 +233,72 @@ impl KeyManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn service() -> CryptoService {
+        CryptoService::new([7u8; MASTER_KEY_BYTES]).unwrap()
+    }
+
+    #[test]
+    fn preserves_trailing_nul_bytes() {
+        let crypto = service();
+        let sealed = crypto.encrypt_bytes(b"token\0\0").unwrap();
+        assert_eq!(crypto.decrypt_bytes(&sealed).unwrap(), b"token\0\0");
+
+        let cipher = crypto.encrypt("ends-with-nul\0").unwrap();
+        assert_eq!(crypto.decrypt(&cipher).unwrap(), "ends-with-nul\0");
+    }
+
+    #[test]
+    fn decrypts_legacy_padded_payloads() {
+        let crypto = service();
+        let nonce = [3u8; NONCE_BYTES];
+        let mut in_out = b"sk-legacy".to_vec();
+        in_out.resize(in_out.len() + AES_256_GCM.tag_len(), 0);
+        crypto
+            .key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut in_out,
+            )
+            .unwrap();
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&in_out);
+
+        assert_eq!(
+            crypto.decrypt(&BASE64.encode(payload)).unwrap(),
+            "sk-legacy"
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_payload() {
+        let crypto = service();
+        let mut sealed = crypto.encrypt_bytes(b"secret").unwrap();
+        let last = sealed.len() - 1;
+        sealed[last] ^= 0x01;
+        assert!(crypto.decrypt_bytes(&sealed).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn bytes_roundtrip(plain in proptest::collection::vec(any::<u8>(), 0..512)) {
+            let crypto = service();
+            let sealed = crypto.encrypt_bytes(&plain).unwrap();
+            prop_assert_eq!(crypto.decrypt_bytes(&sealed).unwrap(), plain);
+        }
+
+        #[test]
+        fn string_roundtrip(plain in ".*") {
+            let crypto = service();
+            let cipher = crypto.encrypt(&plain).unwrap();
+            prop_assert_eq!(crypto.decrypt(&cipher).unwrap(), plain);
+        }
+    }
+}
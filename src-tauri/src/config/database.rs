@@ -1,17 +1,22 @@
 use std::str::FromStr;
 
 use sqlx::{
+    migrate::Migrator,
     sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous},
     SqlitePool,
 };
 use tracing::info;
 
-use super::{error::ConfigError, paths::ConfigPaths};
+use super::{backup, error::ConfigError, paths::ConfigPaths};
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 
 pub async fn create_pool(paths: &ConfigPaths) -> Result<SqlitePool, ConfigError> {
     if let Some(parent) = paths.db_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
+    backup::apply_pending_restore(paths)?;
+    let existed = paths.db_path.exists();
     let path_str = paths
         .db_path
         .to_str()
@@ -23,10 +28,57 @@ pub async fn create_pool(paths: &ConfigPaths) -> Result<SqlitePool, ConfigError>
         .synchronous(SqliteSynchronous::Normal);
 
     let pool = SqlitePool::connect_with(options).await?;
-    sqlx::migrate!("./migrations").run(&pool).await?;
+    if existed && !migration_state(&pool).await?.pending.is_empty() {
+        backup::create_backup(
+            &pool,
+            paths,
+            "pre-migration",
+            backup::PRE_MIGRATION_RETENTION,
+        )
+        .await?;
+    }
+    MIGRATOR.run(&pool).await?;
     info!(
         "SQLite config database ready at {}",
         paths.db_path.display()
     );
     Ok(pool)
 }
+
+/// Migration versions known to this build that have not been applied, and applied versions
+/// that were left in a failed state.
+#[derive(Debug, Default)]
+pub(super) struct MigrationState {
+    pub pending: Vec<i64>,
+    pub failed: Vec<i64>,
+}
+
+pub(super) async fn migration_state(pool: &SqlitePool) -> Result<MigrationState, ConfigError> {
+    let tracked: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+    )
+    .fetch_one(pool)
+    .await?;
+    if tracked == 0 {
+        return Ok(MigrationState {
+            pending: MIGRATOR.iter().map(|migration| migration.version).collect(),
+            failed: Vec::new(),
+        });
+    }
+    let applied: Vec<(i64, bool)> =
+        sqlx::query_as("SELECT version, success FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(pool)
+            .await?;
+    Ok(MigrationState {
+        pending: MIGRATOR
+            .iter()
+            .map(|migration| migration.version)
+            .filter(|version| !applied.iter().any(|(v, ok)| v == version && *ok))
+            .collect(),
+        failed: applied
+            .iter()
+            .filter(|(_, ok)| !ok)
+            .map(|(version, _)| *version)
+            .collect(),
+    })
+}
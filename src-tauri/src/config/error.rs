@@ -6,6 +6,8 @@ pub enum ConfigError {
     MissingProjectDir,
     #[error("no default provider configured")]
     MissingDefaultProvider,
+    #[error("provider `{0}` is not configured")]
+    MissingProvider(String),
     #[error("i/o error: {0}")]
     Io(#[from] std::io::Error),
     #[error("database error: {0}")]
@@ -22,6 +24,16 @@ pub enum ConfigError {
     Utf8(#[from] std::string::FromUtf8Error),
     #[error("task join error: {0}")]
     Join(#[from] tokio::task::JoinError),
+    #[error("serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("invalid setting: {0}")]
+    InvalidSetting(String),
+    #[error("transfer error: {0}")]
+    Transfer(String),
+    #[error("recovery error: {0}")]
+    Recovery(String),
+    #[error("scheduler error: {0}")]
+    Scheduler(#[from] tokio_cron_scheduler::JobSchedulerError),
 }
 
 impl From<ring::error::Unspecified> for ConfigError {
@@ -0,0 +1,25 @@
+use serde::Serialize;
+
+use super::service::ProviderSummary;
+
+/// Buffered changes per subscriber before slow receivers start lagging.
+pub const CHANGE_CHANNEL_CAPACITY: usize = 64;
+
+/// Published by [`ConfigService`](super::service::ConfigService) after a write commits.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum ConfigChange {
+    /// A setting was written. Values are omitted for secret settings.
+    #[serde(rename_all = "camelCase")]
+    Setting {
+        key: String,
+        old_value: Option<String>,
+        new_value: Option<String>,
+        secret: bool,
+    },
+    /// A provider was added, updated or made default; carries the full new list.
+    #[serde(rename_all = "camelCase")]
+    Providers { providers: Vec<ProviderSummary> },
+    /// Many keys changed at once (e.g. an import); listeners should reload everything.
+    Reloaded,
+}
@@ -0,0 +1,139 @@
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+
+use super::{crypto::CryptoService, database::migration_state, error::ConfigError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthStatus {
+    Healthy,
+    /// The app started but at least one check failed.
+    Degraded,
+    /// The config service could not start; only recovery actions are available.
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    pub fn failed(err: &ConfigError) -> Self {
+        Self {
+            status: HealthStatus::Failed,
+            checks: vec![HealthCheck::fail("startup", err.to_string())],
+        }
+    }
+
+    fn from_checks(checks: Vec<HealthCheck>) -> Self {
+        let status = if checks.iter().all(|check| check.passed) {
+            HealthStatus::Healthy
+        } else {
+            HealthStatus::Degraded
+        };
+        Self { status, checks }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.status == HealthStatus::Healthy
+    }
+}
+
+impl HealthCheck {
+    fn pass(name: &str, detail: Option<String>) -> Self {
+        Self {
+            name: name.into(),
+            passed: true,
+            detail,
+        }
+    }
+
+    fn fail(name: &str, detail: String) -> Self {
+        Self {
+            name: name.into(),
+            passed: false,
+            detail: Some(detail),
+        }
+    }
+
+    fn from_result(name: &str, result: Result<Option<String>, String>) -> Self {
+        match result {
+            Ok(detail) => Self::pass(name, detail),
+            Err(detail) => Self::fail(name, detail),
+        }
+    }
+}
+
+pub async fn run_health_check(pool: &SqlitePool, crypto: &CryptoService) -> HealthReport {
+    HealthReport::from_checks(vec![
+        HealthCheck::from_result("integrity", check_integrity(pool).await),
+        HealthCheck::from_result("migrations", check_migrations(pool).await),
+        HealthCheck::from_result("secrets", check_secrets(pool, crypto).await),
+    ])
+}
+
+pub(super) async fn integrity_errors(pool: &SqlitePool) -> Result<Vec<String>, ConfigError> {
+    let rows: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().filter(|row| row != "ok").collect())
+}
+
+async fn check_integrity(pool: &SqlitePool) -> Result<Option<String>, String> {
+    let errors = integrity_errors(pool)
+        .await
+        .map_err(|err| err.to_string())?;
+    if errors.is_empty() {
+        Ok(None)
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+async fn check_migrations(pool: &SqlitePool) -> Result<Option<String>, String> {
+    let state = migration_state(pool).await.map_err(|err| err.to_string())?;
+    if !state.failed.is_empty() {
+        return Err(format!("failed migrations: {:?}", state.failed));
+    }
+    if !state.pending.is_empty() {
+        return Err(format!("pending migrations: {:?}", state.pending));
+    }
+    Ok(None)
+}
+
+/// Decrypts one stored secret to confirm the master key matches the database.
+async fn check_secrets(
+    pool: &SqlitePool,
+    crypto: &CryptoService,
+) -> Result<Option<String>, String> {
+    let row = sqlx::query(
+        r#"
+        SELECT api_key AS value FROM providers WHERE LENGTH(api_key) > 0
+        UNION ALL
+        SELECT value FROM settings WHERE is_secret = 1
+        LIMIT 1
+      "#,
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|err| err.to_string())?;
+    let Some(row) = row else {
+        return Ok(Some("no secrets stored".into()));
+    };
+    let cipher: String = row.try_get("value").map_err(|err| err.to_string())?;
+    crypto
+        .decrypt(&cipher)
+        .map(|_| None)
+        .map_err(|_| "stored secrets cannot be decrypted with the current master key".into())
+}
@@ -1,7 +1,13 @@
+pub mod backup;
 pub mod crypto;
 pub mod database;
 pub mod error;
+pub mod events;
+pub mod health;
 pub mod paths;
+pub mod recovery;
 pub mod service;
+pub mod settings;
+pub mod transfer;
 
 pub use error::ConfigError;
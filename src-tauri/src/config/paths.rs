@@ -11,6 +11,11 @@ pub struct ConfigPaths {
     pub base_dir: PathBuf,
     pub db_path: PathBuf,
     pub key_path: PathBuf,
+    pub backup_dir: PathBuf,
+    /// Installed MCP servers, one directory per slug.
+    pub mcp_dir: PathBuf,
+    /// Chat attachments, one file per content hash.
+    pub attachments_dir: PathBuf,
 }
 
 impl ConfigPaths {
@@ -20,6 +25,9 @@ impl ConfigPaths {
         Ok(Self {
             key_path: base_dir.join("master.key"),
             db_path: base_dir.join("aethos.db3"),
+            backup_dir: base_dir.join("backups"),
+            mcp_dir: base_dir.join("mcp"),
+            attachments_dir: base_dir.join("attachments"),
             base_dir,
         })
     }
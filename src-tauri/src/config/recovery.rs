@@ -0,0 +1,148 @@
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::SqlitePool;
+use tracing::{error, info, warn};
+
+use super::{
+    backup::{self, BackupInfo},
+    error::ConfigError,
+    health::{integrity_errors, HealthReport},
+    paths::ConfigPaths,
+    service::{ConfigService, SharedConfigService},
+};
+
+pub type SharedRecoveryService = Arc<RecoveryService>;
+
+/// Startup health and the repair, restore and reset actions offered when it is not healthy.
+/// Works from paths alone so it stays usable when [`ConfigService`] failed to start.
+///
+/// Every action that replaces the database expects the live pool to be closed and the app to
+/// be restarted afterwards.
+pub struct RecoveryService {
+    paths: ConfigPaths,
+    report: RwLock<HealthReport>,
+}
+
+impl RecoveryService {
+    pub async fn startup(
+        paths: ConfigPaths,
+    ) -> (Option<SharedConfigService>, SharedRecoveryService) {
+        match ConfigService::with_paths(paths.clone()).await {
+            Ok(service) => {
+                let report = service.health_check().await;
+                if !report.is_healthy() {
+                    warn!(?report, "config database is degraded");
+                }
+                (Some(service), Arc::new(Self::new(paths, report)))
+            }
+            Err(err) => {
+                error!("config service failed to start: {err}");
+                (None, Arc::new(Self::new(paths, HealthReport::failed(&err))))
+            }
+        }
+    }
+
+    pub fn new(paths: ConfigPaths, report: HealthReport) -> Self {
+        Self {
+            paths,
+            report: RwLock::new(report),
+        }
+    }
+
+    pub fn report(&self) -> HealthReport {
+        self.report
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub async fn refresh(&self, config: &ConfigService) -> HealthReport {
+        let report = config.health_check().await;
+        *self
+            .report
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = report.clone();
+        report
+    }
+
+    pub fn list_backups(&self) -> Result<Vec<BackupInfo>, ConfigError> {
+        backup::list_backups(&self.paths)
+    }
+
+    /// Rebuilds indexes and copies the readable contents into a fresh file with
+    /// `VACUUM INTO`, then stages that copy in place of the damaged database.
+    pub async fn repair_database(&self) -> Result<BackupInfo, ConfigError> {
+        let path_str = self.paths.db_path.to_str().ok_or_else(|| {
+            ConfigError::InvalidPath("database path contains invalid UTF-8".into())
+        })?;
+        let options = SqliteConnectOptions::from_str(path_str)?.create_if_missing(false);
+        let pool = SqlitePool::connect_with(options).await?;
+        if let Err(err) = sqlx::query("REINDEX").execute(&pool).await {
+            warn!("reindex during repair failed: {err}");
+        }
+        let repaired =
+            backup::create_backup(&pool, &self.paths, "repaired", backup::REPAIRED_RETENTION).await;
+        pool.close().await;
+        let repaired = repaired?;
+
+        let copy_path = self.paths.backup_dir.join(&repaired.file_name);
+        let copy_str = copy_path
+            .to_str()
+            .ok_or_else(|| ConfigError::InvalidPath("backup path contains invalid UTF-8".into()))?;
+        let copy = SqlitePool::connect_with(SqliteConnectOptions::from_str(copy_str)?).await?;
+        let errors = integrity_errors(&copy).await;
+        copy.close().await;
+        let errors = errors?;
+        if !errors.is_empty() {
+            return Err(ConfigError::Recovery(format!(
+                "repaired copy is still damaged: {}",
+                errors.join("; ")
+            )));
+        }
+
+        self.quarantine()?;
+        backup::stage_file(&self.paths, &copy_path)?;
+        info!("staged repaired database {}", repaired.file_name);
+        Ok(repaired)
+    }
+
+    /// Moves the damaged database aside and stages `file_name` for the next launch.
+    pub fn restore_backup(&self, file_name: &str) -> Result<(), ConfigError> {
+        let source = backup::resolve_backup(&self.paths, file_name)?;
+        self.quarantine()?;
+        backup::stage_file(&self.paths, &source)
+    }
+
+    /// Moves the database aside so the next launch starts from an empty one.
+    pub fn reset_database(&self) -> Result<PathBuf, ConfigError> {
+        self.quarantine()
+    }
+
+    /// Moves the database and its WAL sidecars into a timestamped folder under the backup
+    /// directory rather than deleting them.
+    fn quarantine(&self) -> Result<PathBuf, ConfigError> {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis())
+            .unwrap_or_default();
+        let target = self.paths.backup_dir.join(format!("quarantine-{stamp}"));
+        fs::create_dir_all(&target)?;
+        for suffix in ["", "-wal", "-shm"] {
+            let mut source = self.paths.db_path.clone().into_os_string();
+            source.push(suffix);
+            let source = PathBuf::from(source);
+            if let Some(name) = source.file_name() {
+                if source.exists() {
+                    fs::rename(&source, target.join(name))?;
+                }
+            }
+        }
+        info!("quarantined database to {}", target.display());
+        Ok(target)
+    }
+}
@@ -3,14 +3,24 @@
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
 use sqlx::{FromRow, Row};
-use tracing::instrument;
+use tokio::sync::broadcast;
+use tracing::{debug, instrument, warn};
 
 use super::{
+    backup::{self, BackupInfo},
     crypto::{CryptoService, KeyManager},
     database::create_pool,
     error::ConfigError,
+    events::{ConfigChange, CHANGE_CHANNEL_CAPACITY},
+    health::{run_health_check, HealthReport},
     paths::ConfigPaths,
+    settings::{self, SettingEntry, SettingValue, SETTINGS},
+    transfer::{
+        open_bundle, seal_bundle, ConfigBundle, ConflictStrategy, ExportedProvider,
+        ExportedSetting, ImportSummary,
+    },
 };
 
 pub type SharedConfigService = Arc<ConfigService>;
@@ -81,6 +91,7 @@ pub struct PreferencesUpdate {
 pub struct ConfigService {
     pool: sqlx::SqlitePool,
     crypto: CryptoService,
+    changes: broadcast::Sender<ConfigChange>,
     pub paths: ConfigPaths,
 }
 
@@ -92,17 +103,17 @@ impl ConfigService {
 
     pub async fn get_preferences(&self) -> Result<UserPreferences, ConfigError> {
         Ok(UserPreferences {
-            language: self.get_setting("ui.language").await?,
-            theme: self.get_setting("ui.theme").await?,
+            language: self.get_value("ui.language").await?,
+            theme: self.get_value("ui.theme").await?,
         })
     }
 
     pub async fn save_preferences(&self, prefs: PreferencesUpdate) -> Result<(), ConfigError> {
         if let Some(language) = prefs.language {
-            self.set_setting("ui.language", &language, false).await?;
+            self.set_value("ui.language", &language).await?;
         }
         if let Some(theme) = prefs.theme {
-            self.set_setting("ui.theme", &theme, false).await?;
+            self.set_value("ui.theme", &theme).await?;
         }
         Ok(())
     }
@@ -113,9 +124,11 @@ impl ConfigService {
         let master_key =
             tokio::task::spawn_blocking(move || key_manager.resolve_master_key()).await??;
         let crypto = CryptoService::new(master_key)?;
+        let (changes, _) = broadcast::channel(CHANGE_CHANNEL_CAPACITY);
         Ok(Arc::new(Self {
             pool,
             crypto,
+            changes,
             paths,
         }))
     }
@@ -124,19 +137,54 @@ impl ConfigService {
         &self.pool
     }
 
+    pub fn crypto(&self) -> &CryptoService {
+        &self.crypto
+    }
+
+    /// Receives a [`ConfigChange`] for every committed write.
+    pub fn subscribe(&self) -> broadcast::Receiver<ConfigChange> {
+        self.changes.subscribe()
+    }
+
+    fn publish(&self, change: ConfigChange) {
+        if self.changes.send(change).is_err() {
+            debug!("config change dropped: no subscribers");
+        }
+    }
+
+    /// Runs after the write has committed, so a failure to re-list is only logged.
+    async fn publish_providers(&self) {
+        match self.list_providers().await {
+            Ok(providers) => self.publish(ConfigChange::Providers { providers }),
+            Err(err) => warn!("unable to publish provider change: {err}"),
+        }
+    }
+
+    pub async fn health_check(&self) -> HealthReport {
+        run_health_check(&self.pool, &self.crypto).await
+    }
+
+    /// Raw write behind [`set_value`](Self::set_value); callers outside this module go
+    /// through the registry.
     #[instrument(skip(self, value))]
-    pub async fn set_setting(
-        &self,
-        key: &str,
-        value: &str,
-        secret: bool,
-    ) -> Result<(), ConfigError> {
+    async fn set_setting(&self, key: &str, value: &str, secret: bool) -> Result<(), ConfigError> {
         let maybe_value = if secret {
             Some(self.crypto.encrypt(value)?)
         } else {
             None
         };
         let stored = maybe_value.unwrap_or_else(|| value.to_string());
+        // Read the old value under the write lock so the reported change cannot race
+        // another writer.
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        let old_value = if secret {
+            None
+        } else {
+            sqlx::query_scalar("SELECT value FROM settings WHERE key = ?1 AND is_secret = 0")
+                .bind(key)
+                .fetch_optional(&mut *tx)
+                .await?
+        };
         sqlx::query(
             r#"
         INSERT INTO settings (key, value, is_secret, updated_at)
@@ -147,12 +195,19 @@ impl ConfigService {
         .bind(key)
         .bind(stored)
         .bind(if secret { 1 } else { 0 })
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
+        tx.commit().await?;
+        self.publish(ConfigChange::Setting {
+            key: key.to_string(),
+            old_value,
+            new_value: (!secret).then(|| value.to_string()),
+            secret,
+        });
         Ok(())
     }
 
-    pub async fn get_setting(&self, key: &str) -> Result<Option<String>, ConfigError> {
+    async fn get_setting(&self, key: &str) -> Result<Option<String>, ConfigError> {
         let row = sqlx::query("SELECT value, is_secret FROM settings WHERE key = ?1")
             .bind(key)
             .fetch_optional(&self.pool)
@@ -168,6 +223,61 @@ impl ConfigService {
         Ok(None)
     }
 
+    /// Reads a registered setting, falling back to its declared default.
+    pub async fn get_value<T: SettingValue>(&self, key: &str) -> Result<Option<T>, ConfigError> {
+        let definition = settings::definition(key)?;
+        let raw = match self.get_setting(key).await? {
+            Some(raw) => Some(raw),
+            None => definition.default.map(str::to_string),
+        };
+        raw.as_deref().map(T::from_raw).transpose()
+    }
+
+    /// Validates and stores a registered setting, encrypting it when the registry marks it
+    /// secret.
+    pub async fn set_value<T: SettingValue>(
+        &self,
+        key: &str,
+        value: &T,
+    ) -> Result<(), ConfigError> {
+        let definition = settings::definition(key)?;
+        let raw = value.to_raw();
+        definition.validate(&raw)?;
+        self.set_setting(key, &raw, definition.secret).await
+    }
+
+    pub async fn update_setting(
+        &self,
+        key: &str,
+        value: &serde_json::Value,
+    ) -> Result<(), ConfigError> {
+        let definition = settings::definition(key)?;
+        let raw = definition.raw_from_json(value)?;
+        self.set_setting(key, &raw, definition.secret).await
+    }
+
+    pub async fn list_settings(&self) -> Result<Vec<SettingEntry>, ConfigError> {
+        let mut entries = Vec::with_capacity(SETTINGS.len());
+        for definition in SETTINGS {
+            let stored = self.get_setting(definition.key).await?;
+            let has_value = stored.is_some();
+            let value = if definition.secret {
+                None
+            } else {
+                stored
+                    .as_deref()
+                    .or(definition.default)
+                    .map(|raw| definition.raw_to_json(raw))
+            };
+            entries.push(SettingEntry {
+                definition: definition.clone(),
+                value,
+                has_value,
+            });
+        }
+        Ok(entries)
+    }
+
     pub async fn list_providers(&self) -> Result<Vec<ProviderSummary>, ConfigError> {
         let rows = sqlx::query_as::<_, ProviderRow>(
             r#"
@@ -216,6 +326,31 @@ impl ConfigService {
             .ok_or(ConfigError::MissingDefaultProvider)?
         };
 
+        self.credential_from_row(row)
+    }
+
+    /// Credentials for a specific provider rather than the default one.
+    pub async fn provider_credentials(
+        &self,
+        provider: &str,
+    ) -> Result<ProviderCredential, ConfigError> {
+        let row = sqlx::query(
+            r#"
+        SELECT provider, display_name, default_model, api_key
+        FROM providers
+        WHERE provider = ?1
+        ORDER BY id ASC
+        LIMIT 1
+      "#,
+        )
+        .bind(provider)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| ConfigError::MissingProvider(provider.to_string()))?;
+        self.credential_from_row(row)
+    }
+
+    fn credential_from_row(&self, row: SqliteRow) -> Result<ProviderCredential, ConfigError> {
         let encrypted_key: String = row.try_get("api_key")?;
         let api_key = self.crypto.decrypt(&encrypted_key)?;
 
@@ -265,6 +400,7 @@ impl ConfigService {
         .execute(&mut *tx)
         .await?;
         tx.commit().await?;
+        self.publish_providers().await;
         self.get_provider_by_slug(&payload.provider)
             .await?
             .ok_or_else(|| ConfigError::Database(sqlx::Error::RowNotFound))
@@ -277,6 +413,7 @@ impl ConfigService {
             .execute(&mut *tx)
             .await?;
         tx.commit().await?;
+        self.publish_providers().await;
         Ok(())
     }
 
@@ -287,6 +424,223 @@ impl ConfigService {
         Ok(count > 0)
     }
 
+    /// Snapshots the database and prunes down to the `backup.retention` setting.
+    pub async fn create_backup(&self, reason: &str) -> Result<BackupInfo, ConfigError> {
+        let keep = self
+            .get_value::<i64>("backup.retention")
+            .await?
+            .map_or(backup::BACKUP_RETENTION, |keep| keep as usize);
+        backup::create_backup(&self.pool, &self.paths, reason, keep).await
+    }
+
+    pub fn list_backups(&self) -> Result<Vec<BackupInfo>, ConfigError> {
+        backup::list_backups(&self.paths)
+    }
+
+    /// Stages `file_name` to replace the database; takes effect on the next launch.
+    pub async fn restore_backup(&self, file_name: &str) -> Result<(), ConfigError> {
+        backup::stage_restore(&self.pool, &self.paths, file_name).await
+    }
+
+    /// Serializes every provider and setting into a passphrase-protected export.
+    pub async fn export_config(&self, passphrase: &str) -> Result<Vec<u8>, ConfigError> {
+        let provider_rows = sqlx::query(
+            r#"
+        SELECT provider, display_name, api_key, default_model, is_default
+        FROM providers
+        ORDER BY id ASC
+      "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mut providers = Vec::with_capacity(provider_rows.len());
+        for row in provider_rows {
+            let encrypted_key: String = row.try_get("api_key")?;
+            let is_default: i64 = row.try_get("is_default")?;
+            providers.push(ExportedProvider {
+                provider: row.try_get("provider")?,
+                display_name: row.try_get("display_name")?,
+                api_key: self.crypto.decrypt(&encrypted_key)?,
+                default_model: row.try_get("default_model")?,
+                is_default: is_default == 1,
+            });
+        }
+
+        let setting_rows = sqlx::query("SELECT key, value, is_secret FROM settings ORDER BY key")
+            .fetch_all(&self.pool)
+            .await?;
+        let mut settings = Vec::with_capacity(setting_rows.len());
+        for row in setting_rows {
+            // Only registry settings can be imported again; the rest is internal state.
+            let key: String = row.try_get("key")?;
+            if settings::definition(&key).is_err() {
+                continue;
+            }
+            let value: String = row.try_get("value")?;
+            let is_secret = row.try_get::<i64, _>("is_secret")? == 1;
+            settings.push(ExportedSetting {
+                key,
+                value: if is_secret {
+                    self.crypto.decrypt(&value)?
+                } else {
+                    value
+                },
+                is_secret,
+            });
+        }
+
+        // Key derivation is deliberately slow, so keep it off the async workers.
+        let bundle = ConfigBundle {
+            providers,
+            settings,
+        };
+        let passphrase = passphrase.to_owned();
+        tokio::task::spawn_blocking(move || seal_bundle(&bundle, &passphrase)).await?
+    }
+
+    /// Applies an export produced by [`export_config`](Self::export_config) in one transaction.
+    pub async fn import_config(
+        &self,
+        data: &[u8],
+        passphrase: &str,
+        strategy: ConflictStrategy,
+    ) -> Result<ImportSummary, ConfigError> {
+        let (data, passphrase) = (data.to_vec(), passphrase.to_owned());
+        let bundle = tokio::task::spawn_blocking(move || open_bundle(&data, &passphrase)).await??;
+        let mut summary = ImportSummary::default();
+        let mut imported_default = None;
+        let mut tx = self.pool.begin().await?;
+
+        for provider in bundle.providers {
+            let exists: i64 =
+                sqlx::query_scalar("SELECT COUNT(*) FROM providers WHERE provider = ?1")
+                    .bind(&provider.provider)
+                    .fetch_one(&mut *tx)
+                    .await?;
+            let (slug, display_name) = match (exists > 0, strategy) {
+                (false, _) => {
+                    summary.providers_added += 1;
+                    (provider.provider, provider.display_name)
+                }
+                (true, ConflictStrategy::Skip) => {
+                    summary.providers_skipped += 1;
+                    continue;
+                }
+                (true, ConflictStrategy::Overwrite) => {
+                    summary.providers_overwritten += 1;
+                    (provider.provider, provider.display_name)
+                }
+                (true, ConflictStrategy::Rename) => {
+                    let mut candidate = format!("{}-imported", provider.provider);
+                    let mut suffix = 2;
+                    loop {
+                        let taken: i64 = sqlx::query_scalar(
+                            "SELECT COUNT(*) FROM providers WHERE provider = ?1",
+                        )
+                        .bind(&candidate)
+                        .fetch_one(&mut *tx)
+                        .await?;
+                        if taken == 0 {
+                            break;
+                        }
+                        candidate = format!("{}-imported-{suffix}", provider.provider);
+                        suffix += 1;
+                    }
+                    summary.providers_renamed += 1;
+                    (candidate, format!("{} (imported)", provider.display_name))
+                }
+            };
+            if provider.is_default {
+                imported_default = Some(slug.clone());
+            }
+            sqlx::query(
+                r#"
+            INSERT INTO providers (provider, display_name, api_key, default_model, is_default)
+            VALUES (?1, ?2, ?3, ?4, 0)
+            ON CONFLICT(provider) DO UPDATE SET
+              display_name = excluded.display_name,
+              api_key = excluded.api_key,
+              default_model = excluded.default_model,
+              updated_at = CURRENT_TIMESTAMP
+          "#,
+            )
+            .bind(&slug)
+            .bind(&display_name)
+            .bind(self.crypto.encrypt(&provider.api_key)?)
+            .bind(&provider.default_model)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        if let Some(slug) = imported_default {
+            let has_default: i64 =
+                sqlx::query_scalar("SELECT COUNT(*) FROM providers WHERE is_default = 1")
+                    .fetch_one(&mut *tx)
+                    .await?;
+            if has_default == 0 || strategy == ConflictStrategy::Overwrite {
+                sqlx::query(
+                    "UPDATE providers SET is_default = CASE WHEN provider = ?1 THEN 1 ELSE 0 END",
+                )
+                .bind(&slug)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        // Settings go through the registry like any other write. Its keys are fixed, so a
+        // conflicting setting cannot be renamed; it keeps its local value and is counted
+        // as not renamed so the caller can tell.
+        for setting in bundle.settings {
+            let definition = match settings::definition(&setting.key)
+                .and_then(|definition| definition.validate(&setting.value).map(|_| definition))
+            {
+                Ok(definition) => definition,
+                Err(err) => {
+                    warn!("skipping imported setting: {err}");
+                    summary.settings_skipped += 1;
+                    continue;
+                }
+            };
+            let exists: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM settings WHERE key = ?1")
+                .bind(definition.key)
+                .fetch_one(&mut *tx)
+                .await?;
+            match (exists > 0, strategy) {
+                (false, _) => summary.settings_added += 1,
+                (true, ConflictStrategy::Overwrite) => summary.settings_overwritten += 1,
+                (true, ConflictStrategy::Skip) => {
+                    summary.settings_skipped += 1;
+                    continue;
+                }
+                (true, ConflictStrategy::Rename) => {
+                    summary.settings_not_renamed += 1;
+                    continue;
+                }
+            }
+            let stored = if definition.secret {
+                self.crypto.encrypt(&setting.value)?
+            } else {
+                setting.value
+            };
+            sqlx::query(
+                r#"
+            INSERT INTO settings (key, value, is_secret, updated_at)
+            VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)
+            ON CONFLICT(key) DO UPDATE SET value=excluded.value, is_secret=excluded.is_secret, updated_at=CURRENT_TIMESTAMP
+          "#,
+            )
+            .bind(definition.key)
+            .bind(stored)
+            .bind(if definition.secret { 1 } else { 0 })
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        self.publish(ConfigChange::Reloaded);
+        Ok(summary)
+    }
+
     async fn get_provider_by_slug(
         &self,
         provider: &str,
@@ -313,9 +667,12 @@ impl ConfigService {
 
 #[cfg(test)]
 mod tests {
+    use base64::engine::general_purpose::STANDARD as BASE64;
+    use base64::Engine;
     use tempfile::tempdir;
 
     use super::*;
+    use crate::config::{health::HealthStatus, recovery::RecoveryService};
 
     #[tokio::test]
     async fn secret_roundtrip() {
@@ -416,6 +773,293 @@ mod tests {
         assert_eq!(prefs.theme.as_deref(), Some("dark"));
     }
 
+    #[tokio::test]
+    async fn config_export_import_roundtrip() {
+        let source_dir = tempdir().unwrap();
+        let source =
+            ConfigService::with_paths(ConfigPaths::from_base_dir(source_dir.path()).unwrap())
+                .await
+                .unwrap();
+        source
+            .upsert_provider(ProviderUpsertPayload {
+                provider: "openai".into(),
+                display_name: "OpenAI".into(),
+                api_key: "sk-export".into(),
+                default_model: Some("gpt-4o-mini".into()),
+                make_default: true,
+            })
+            .await
+            .unwrap();
+        source.set_setting("ui.theme", "dark", false).await.unwrap();
+        source
+            .set_setting("chat.title_model", "gpt-4o-mini", false)
+            .await
+            .unwrap();
+        // Rows the registry no longer knows stay out of the export.
+        source
+            .set_setting("legacy.token", "abc", true)
+            .await
+            .unwrap();
+        let exported = source.export_config("passphrase").await.unwrap();
+
+        let target_dir = tempdir().unwrap();
+        let target =
+            ConfigService::with_paths(ConfigPaths::from_base_dir(target_dir.path()).unwrap())
+                .await
+                .unwrap();
+        let summary = target
+            .import_config(&exported, "passphrase", ConflictStrategy::Skip)
+            .await
+            .unwrap();
+        assert_eq!(summary.providers_added, 1);
+        assert_eq!(summary.settings_added, 2);
+        assert_eq!(summary.settings_skipped, 0);
+
+        let creds = target.default_provider_credentials().await.unwrap();
+        assert_eq!(creds.api_key, "sk-export");
+        assert_eq!(
+            target
+                .get_value::<String>("chat.title_model")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("gpt-4o-mini")
+        );
+        assert_eq!(target.get_setting("legacy.token").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn config_import_applies_conflict_strategy() {
+        let temp_dir = tempdir().unwrap();
+        let paths = ConfigPaths::from_base_dir(temp_dir.path()).unwrap();
+        let service = ConfigService::with_paths(paths).await.unwrap();
+
+        service
+            .upsert_provider(ProviderUpsertPayload {
+                provider: "openai".into(),
+                display_name: "OpenAI".into(),
+                api_key: "sk-original".into(),
+                default_model: None,
+                make_default: true,
+            })
+            .await
+            .unwrap();
+        service
+            .set_setting("ui.theme", "dark", false)
+            .await
+            .unwrap();
+        let exported = service.export_config("passphrase").await.unwrap();
+
+        let skipped = service
+            .import_config(&exported, "passphrase", ConflictStrategy::Skip)
+            .await
+            .unwrap();
+        assert_eq!(skipped.providers_skipped, 1);
+        assert_eq!(skipped.settings_skipped, 1);
+
+        let renamed = service
+            .import_config(&exported, "passphrase", ConflictStrategy::Rename)
+            .await
+            .unwrap();
+        assert_eq!(renamed.providers_renamed, 1);
+        let providers = service.list_providers().await.unwrap();
+        assert!(providers.iter().any(|p| p.provider == "openai-imported"));
+        assert_eq!(providers.iter().filter(|p| p.is_default).count(), 1);
+        assert_eq!(renamed.settings_not_renamed, 1);
+        assert_eq!(renamed.settings_skipped, 0);
+    }
+
+    #[tokio::test]
+    async fn backups_rotate_and_restore_on_reopen() {
+        let temp_dir = tempdir().unwrap();
+        let paths = ConfigPaths::from_base_dir(temp_dir.path()).unwrap();
+        let service = ConfigService::with_paths(paths.clone()).await.unwrap();
+
+        service
+            .set_setting("ui.theme", "dark", false)
+            .await
+            .unwrap();
+        let upgrade = backup::create_backup(
+            service.pool(),
+            &paths,
+            "pre-migration",
+            backup::PRE_MIGRATION_RETENTION,
+        )
+        .await
+        .unwrap();
+        let snapshot = service.create_backup("manual").await.unwrap();
+        for _ in 0..backup::BACKUP_RETENTION {
+            service.create_backup("manual").await.unwrap();
+        }
+        let backups = service.list_backups().unwrap();
+        assert_eq!(backups.len(), backup::BACKUP_RETENTION + 1);
+        assert!(backups.iter().all(|b| b.file_name != snapshot.file_name));
+        // Routine rotation leaves the pre-migration snapshot alone.
+        assert_eq!(backups.last().unwrap().file_name, upgrade.file_name);
+
+        let restore_point = backups[backups.len() - 2].file_name.clone();
+        service
+            .set_setting("ui.theme", "light", false)
+            .await
+            .unwrap();
+        for _ in 0..=backup::PRE_RESTORE_RETENTION {
+            service.restore_backup(&restore_point).await.unwrap();
+        }
+        let safety_copies = service
+            .list_backups()
+            .unwrap()
+            .into_iter()
+            .filter(|backup| backup.reason == "pre-restore")
+            .count();
+        assert_eq!(safety_copies, backup::PRE_RESTORE_RETENTION);
+        assert!(service.restore_backup("../aethos.db3").await.is_err());
+        service.pool().close().await;
+        drop(service);
+
+        let reopened = ConfigService::with_paths(paths).await.unwrap();
+        assert_eq!(
+            reopened.get_setting("ui.theme").await.unwrap().as_deref(),
+            Some("dark")
+        );
+    }
+
+    #[tokio::test]
+    async fn health_check_passes_on_fresh_database() {
+        let temp_dir = tempdir().unwrap();
+        let paths = ConfigPaths::from_base_dir(temp_dir.path()).unwrap();
+        let (service, recovery) = RecoveryService::startup(paths).await;
+
+        assert!(service.is_some());
+        assert_eq!(recovery.report().status, HealthStatus::Healthy);
+    }
+
+    #[tokio::test]
+    async fn health_check_flags_master_key_mismatch() {
+        let temp_dir = tempdir().unwrap();
+        let paths = ConfigPaths::from_base_dir(temp_dir.path()).unwrap();
+        let service = ConfigService::with_paths(paths.clone()).await.unwrap();
+        service.set_setting("token", "abc", true).await.unwrap();
+        service.pool().close().await;
+        drop(service);
+
+        std::fs::write(&paths.key_path, BASE64.encode([9u8; 32])).unwrap();
+        let (service, recovery) = RecoveryService::startup(paths).await;
+        assert!(service.is_some());
+        let report = recovery.report();
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert!(report
+            .checks
+            .iter()
+            .any(|check| check.name == "secrets" && !check.passed));
+    }
+
+    #[tokio::test]
+    async fn corrupt_database_starts_in_recovery_and_resets() {
+        let temp_dir = tempdir().unwrap();
+        let paths = ConfigPaths::from_base_dir(temp_dir.path()).unwrap();
+        std::fs::write(&paths.db_path, b"definitely not sqlite").unwrap();
+
+        let (service, recovery) = RecoveryService::startup(paths.clone()).await;
+        assert!(service.is_none());
+        assert_eq!(recovery.report().status, HealthStatus::Failed);
+
+        let quarantined = recovery.reset_database().unwrap();
+        assert!(quarantined.join("aethos.db3").exists());
+        let (service, recovery) = RecoveryService::startup(paths).await;
+        assert!(service.is_some());
+        assert!(recovery.report().is_healthy());
+    }
+
+    #[tokio::test]
+    async fn typed_settings_reject_unknown_keys_and_invalid_values() {
+        let temp_dir = tempdir().unwrap();
+        let paths = ConfigPaths::from_base_dir(temp_dir.path()).unwrap();
+        let service = ConfigService::with_paths(paths).await.unwrap();
+
+        assert!(matches!(
+            service.set_value("ui.unknown", &"x".to_string()).await,
+            Err(ConfigError::InvalidSetting(_))
+        ));
+        assert!(matches!(
+            service
+                .save_preferences(PreferencesUpdate {
+                    language: None,
+                    theme: Some("purple".into()),
+                })
+                .await,
+            Err(ConfigError::InvalidSetting(_))
+        ));
+        assert!(service
+            .update_setting("ui.theme", &serde_json::json!(true))
+            .await
+            .is_err());
+
+        service
+            .update_setting("ui.theme", &serde_json::json!("dark"))
+            .await
+            .unwrap();
+        let entries = service.list_settings().await.unwrap();
+        let theme = entries
+            .iter()
+            .find(|entry| entry.definition.key == "ui.theme")
+            .unwrap();
+        assert_eq!(theme.value, Some(serde_json::json!("dark")));
+    }
+
+    #[tokio::test]
+    async fn committed_writes_publish_changes() {
+        let temp_dir = tempdir().unwrap();
+        let paths = ConfigPaths::from_base_dir(temp_dir.path()).unwrap();
+        let service = ConfigService::with_paths(paths).await.unwrap();
+        let mut changes = service.subscribe();
+
+        service
+            .set_setting("ui.theme", "dark", false)
+            .await
+            .unwrap();
+        service
+            .set_setting("ui.theme", "light", false)
+            .await
+            .unwrap();
+        service.set_setting("token", "abc", true).await.unwrap();
+        service
+            .upsert_provider(ProviderUpsertPayload {
+                provider: "openai".into(),
+                display_name: "OpenAI".into(),
+                api_key: "sk-test".into(),
+                default_model: None,
+                make_default: true,
+            })
+            .await
+            .unwrap();
+
+        changes.recv().await.unwrap();
+        match changes.recv().await.unwrap() {
+            ConfigChange::Setting {
+                old_value,
+                new_value,
+                ..
+            } => {
+                assert_eq!(old_value.as_deref(), Some("dark"));
+                assert_eq!(new_value.as_deref(), Some("light"));
+            }
+            other => panic!("unexpected change {other:?}"),
+        }
+        match changes.recv().await.unwrap() {
+            ConfigChange::Setting {
+                new_value, secret, ..
+            } => {
+                assert!(secret);
+                assert!(new_value.is_none());
+            }
+            other => panic!("unexpected change {other:?}"),
+        }
+        match changes.recv().await.unwrap() {
+            ConfigChange::Providers { providers } => assert_eq!(providers.len(), 1),
+            other => panic!("unexpected change {other:?}"),
+        }
+    }
+
     #[tokio::test]
     async fn can_fetch_default_provider_credentials() {
         let temp_dir = tempdir().unwrap();
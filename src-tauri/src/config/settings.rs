@@ -0,0 +1,329 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use super::error::ConfigError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SettingScope {
+    Ui,
+    Chat,
+    System,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum SettingKind {
+    Text,
+    Bool,
+    Integer {
+        min: i64,
+        max: i64,
+    },
+    Choice {
+        options: &'static [&'static str],
+    },
+    /// A list of strings, stored as a JSON array.
+    List,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingDefinition {
+    pub key: &'static str,
+    pub kind: SettingKind,
+    pub default: Option<&'static str>,
+    pub secret: bool,
+    pub scope: SettingScope,
+    pub description: &'static str,
+}
+
+/// Every setting key the app reads or writes through the typed API.
+pub static SETTINGS: &[SettingDefinition] = &[
+    SettingDefinition {
+        key: "ui.language",
+        kind: SettingKind::Choice {
+            options: &["en", "zh-CN"],
+        },
+        default: None,
+        secret: false,
+        scope: SettingScope::Ui,
+        description: "Interface language",
+    },
+    SettingDefinition {
+        key: "ui.theme",
+        kind: SettingKind::Choice {
+            options: &["light", "dark"],
+        },
+        default: None,
+        secret: false,
+        scope: SettingScope::Ui,
+        description: "Colour theme",
+    },
+    SettingDefinition {
+        key: "backup.enabled",
+        kind: SettingKind::Bool,
+        default: Some("true"),
+        secret: false,
+        scope: SettingScope::System,
+        description: "Take scheduled database backups",
+    },
+    SettingDefinition {
+        key: "backup.retention",
+        kind: SettingKind::Integer { min: 1, max: 100 },
+        default: Some("10"),
+        secret: false,
+        scope: SettingScope::System,
+        description: "Number of scheduled and manual database backups to keep, each",
+    },
+    SettingDefinition {
+        key: "tools.fs.roots",
+        kind: SettingKind::List,
+        default: Some("[]"),
+        secret: false,
+        scope: SettingScope::Chat,
+        description: "Directories the built-in file tools may read",
+    },
+    SettingDefinition {
+        key: "tools.shell.allowlist",
+        kind: SettingKind::List,
+        default: Some("[]"),
+        secret: false,
+        scope: SettingScope::Chat,
+        description: "Programs the built-in command tool may run",
+    },
+    SettingDefinition {
+        key: "tools.shell.timeout_secs",
+        kind: SettingKind::Integer { min: 1, max: 600 },
+        default: Some("30"),
+        secret: false,
+        scope: SettingScope::Chat,
+        description: "Seconds before a built-in command is killed",
+    },
+    SettingDefinition {
+        key: "chat.context_tokens",
+        kind: SettingKind::Integer {
+            min: 0,
+            max: 2_000_000,
+        },
+        default: Some("0"),
+        secret: false,
+        scope: SettingScope::Chat,
+        description: "Context window in tokens; 0 picks it from the model name",
+    },
+    SettingDefinition {
+        key: "chat.reply_tokens",
+        kind: SettingKind::Integer {
+            min: 256,
+            max: 65_536,
+        },
+        default: Some("4096"),
+        secret: false,
+        scope: SettingScope::Chat,
+        description: "Tokens of the context window kept free for the reply",
+    },
+    SettingDefinition {
+        key: "chat.context_strategy",
+        kind: SettingKind::Choice {
+            options: &["truncate", "summarize"],
+        },
+        default: Some("truncate"),
+        secret: false,
+        scope: SettingScope::Chat,
+        description: "Drop the oldest turns of long chats, or compact them into a summary",
+    },
+    SettingDefinition {
+        key: "chat.auto_title",
+        kind: SettingKind::Bool,
+        default: Some("true"),
+        secret: false,
+        scope: SettingScope::Chat,
+        description: "Name new conversations after their first exchange",
+    },
+    SettingDefinition {
+        key: "chat.title_model",
+        kind: SettingKind::Text,
+        default: None,
+        secret: false,
+        scope: SettingScope::Chat,
+        description: "Model that writes conversation titles; empty for the provider's default",
+    },
+    SettingDefinition {
+        key: "memory.enabled",
+        kind: SettingKind::Bool,
+        default: Some("false"),
+        secret: false,
+        scope: SettingScope::Chat,
+        description: "Index conversations and recall relevant ones in new chats",
+    },
+    SettingDefinition {
+        key: "memory.provider",
+        kind: SettingKind::Choice {
+            options: &["openai", "google", "ollama"],
+        },
+        default: Some("openai"),
+        secret: false,
+        scope: SettingScope::Chat,
+        description: "Provider used to embed memories",
+    },
+    SettingDefinition {
+        key: "memory.model",
+        kind: SettingKind::Text,
+        default: None,
+        secret: false,
+        scope: SettingScope::Chat,
+        description: "Embedding model; empty for the provider's default",
+    },
+    SettingDefinition {
+        key: "memory.ollama_url",
+        kind: SettingKind::Text,
+        default: Some("http://localhost:11434"),
+        secret: false,
+        scope: SettingScope::Chat,
+        description: "Base URL of the local Ollama server",
+    },
+    SettingDefinition {
+        key: "memory.top_k",
+        kind: SettingKind::Integer { min: 1, max: 20 },
+        default: Some("5"),
+        secret: false,
+        scope: SettingScope::Chat,
+        description: "Memories recalled per chat turn",
+    },
+    SettingDefinition {
+        key: "documents.top_k",
+        kind: SettingKind::Integer { min: 1, max: 20 },
+        default: Some("6"),
+        secret: false,
+        scope: SettingScope::Chat,
+        description: "Document excerpts added to a chat grounded in a collection",
+    },
+];
+
+pub fn definition(key: &str) -> Result<&'static SettingDefinition, ConfigError> {
+    SETTINGS
+        .iter()
+        .find(|definition| definition.key == key)
+        .ok_or_else(|| ConfigError::InvalidSetting(format!("unknown setting `{key}`")))
+}
+
+impl SettingDefinition {
+    /// Checks a stored (string) representation against the kind's constraints.
+    pub fn validate(&self, raw: &str) -> Result<(), ConfigError> {
+        let invalid = |reason: String| {
+            Err(ConfigError::InvalidSetting(format!(
+                "`{}`: {reason}",
+                self.key
+            )))
+        };
+        match self.kind {
+            SettingKind::Text => Ok(()),
+            SettingKind::Bool => match raw {
+                "true" | "false" => Ok(()),
+                _ => invalid(format!("expected true or false, got `{raw}`")),
+            },
+            SettingKind::Integer { min, max } => match raw.parse::<i64>() {
+                Ok(value) if (min..=max).contains(&value) => Ok(()),
+                Ok(value) => invalid(format!("{value} is outside {min}..={max}")),
+                Err(_) => invalid(format!("expected an integer, got `{raw}`")),
+            },
+            SettingKind::Choice { options } if options.contains(&raw) => Ok(()),
+            SettingKind::Choice { options } => {
+                invalid(format!("`{raw}` is not one of {}", options.join(", ")))
+            }
+            SettingKind::List => match serde_json::from_str::<Vec<String>>(raw) {
+                Ok(_) => Ok(()),
+                Err(_) => invalid(format!("expected a list of strings, got `{raw}`")),
+            },
+        }
+    }
+
+    /// Converts a JSON value from the frontend into the stored representation.
+    pub fn raw_from_json(&self, value: &Value) -> Result<String, ConfigError> {
+        let raw = match (self.kind, value) {
+            (SettingKind::Bool, Value::Bool(flag)) => flag.to_string(),
+            (SettingKind::Integer { .. }, Value::Number(number)) => number.to_string(),
+            (SettingKind::Text | SettingKind::Choice { .. }, Value::String(text)) => text.clone(),
+            (SettingKind::List, Value::Array(_)) => value.to_string(),
+            _ => {
+                return Err(ConfigError::InvalidSetting(format!(
+                    "`{}`: unexpected value {value}",
+                    self.key
+                )))
+            }
+        };
+        self.validate(&raw)?;
+        Ok(raw)
+    }
+
+    pub fn raw_to_json(&self, raw: &str) -> Value {
+        match self.kind {
+            SettingKind::Bool => Value::Bool(raw == "true"),
+            SettingKind::Integer { .. } => {
+                raw.parse::<i64>().map(Value::from).unwrap_or(Value::Null)
+            }
+            SettingKind::Text | SettingKind::Choice { .. } => Value::String(raw.to_string()),
+            SettingKind::List => serde_json::from_str(raw).unwrap_or(Value::Null),
+        }
+    }
+}
+
+/// Rust types that can be stored in a registered setting.
+pub trait SettingValue: Sized {
+    fn from_raw(raw: &str) -> Result<Self, ConfigError>;
+    fn to_raw(&self) -> String;
+}
+
+impl SettingValue for String {
+    fn from_raw(raw: &str) -> Result<Self, ConfigError> {
+        Ok(raw.to_string())
+    }
+
+    fn to_raw(&self) -> String {
+        self.clone()
+    }
+}
+
+impl SettingValue for bool {
+    fn from_raw(raw: &str) -> Result<Self, ConfigError> {
+        raw.parse()
+            .map_err(|_| ConfigError::InvalidSetting(format!("`{raw}` is not a boolean")))
+    }
+
+    fn to_raw(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SettingValue for i64 {
+    fn from_raw(raw: &str) -> Result<Self, ConfigError> {
+        raw.parse()
+            .map_err(|_| ConfigError::InvalidSetting(format!("`{raw}` is not an integer")))
+    }
+
+    fn to_raw(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SettingValue for Vec<String> {
+    fn from_raw(raw: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str(raw)
+            .map_err(|_| ConfigError::InvalidSetting(format!("`{raw}` is not a list of strings")))
+    }
+
+    fn to_raw(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| "[]".into())
+    }
+}
+
+/// A registered setting together with its current value, for the settings UI. Secret values
+/// are never returned; `has_value` reports whether one is stored.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingEntry {
+    #[serde(flatten)]
+    pub definition: SettingDefinition,
+    pub value: Option<Value>,
+    pub has_value: bool,
+}
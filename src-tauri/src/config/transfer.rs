@@ -0,0 +1,196 @@
+use std::num::NonZeroU32;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use ring::pbkdf2;
+use serde::{Deserialize, Serialize};
+
+use super::crypto::CryptoService;
+use super::error::ConfigError;
+
+const EXPORT_FORMAT: &str = "aethos-config";
+const EXPORT_VERSION: u32 = 1;
+const KDF_NAME: &str = "pbkdf2-hmac-sha256";
+const KDF_ITERATIONS: u32 = 600_000;
+const SALT_BYTES: usize = 16;
+
+/// Plaintext contents of an export. Secrets are decrypted so the bundle can be re-encrypted
+/// with the importing machine's master key.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigBundle {
+    pub providers: Vec<ExportedProvider>,
+    pub settings: Vec<ExportedSetting>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedProvider {
+    pub provider: String,
+    pub display_name: String,
+    pub api_key: String,
+    pub default_model: Option<String>,
+    pub is_default: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedSetting {
+    pub key: String,
+    pub value: String,
+    pub is_secret: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictStrategy {
+    #[default]
+    Skip,
+    Overwrite,
+    Rename,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub providers_added: usize,
+    pub providers_overwritten: usize,
+    pub providers_renamed: usize,
+    pub providers_skipped: usize,
+    pub settings_added: usize,
+    pub settings_overwritten: usize,
+    pub settings_skipped: usize,
+    /// Conflicting settings left at their local value under [`ConflictStrategy::Rename`],
+    /// since setting keys are fixed.
+    pub settings_not_renamed: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    format: String,
+    version: u32,
+    kdf: String,
+    iterations: u32,
+    salt: String,
+    payload: String,
+}
+
+pub fn seal_bundle(bundle: &ConfigBundle, passphrase: &str) -> Result<Vec<u8>, ConfigError> {
+    ensure_passphrase(passphrase)?;
+    let mut salt = [0u8; SALT_BYTES];
+    OsRng.fill_bytes(&mut salt);
+    let crypto = CryptoService::new(derive_key(passphrase, &salt, KDF_ITERATIONS)?)?;
+    let plain = serde_json::to_vec(bundle)?;
+    let envelope = Envelope {
+        format: EXPORT_FORMAT.into(),
+        version: EXPORT_VERSION,
+        kdf: KDF_NAME.into(),
+        iterations: KDF_ITERATIONS,
+        salt: BASE64.encode(salt),
+        payload: BASE64.encode(crypto.encrypt_bytes(&plain)?),
+    };
+    Ok(serde_json::to_vec_pretty(&envelope)?)
+}
+
+pub fn open_bundle(data: &[u8], passphrase: &str) -> Result<ConfigBundle, ConfigError> {
+    ensure_passphrase(passphrase)?;
+    let envelope: Envelope = serde_json::from_slice(data)
+        .map_err(|err| ConfigError::Transfer(format!("not an aethos export: {err}")))?;
+    if envelope.format != EXPORT_FORMAT {
+        return Err(ConfigError::Transfer(format!(
+            "unexpected export format `{}`",
+            envelope.format
+        )));
+    }
+    if envelope.version > EXPORT_VERSION {
+        return Err(ConfigError::Transfer(format!(
+            "export version {} is newer than supported version {EXPORT_VERSION}",
+            envelope.version
+        )));
+    }
+    if envelope.kdf != KDF_NAME {
+        return Err(ConfigError::Transfer(format!(
+            "unsupported key derivation `{}`",
+            envelope.kdf
+        )));
+    }
+    // Exports always use KDF_ITERATIONS; honouring another count would let a crafted file
+    // stall the import.
+    if envelope.iterations != KDF_ITERATIONS {
+        return Err(ConfigError::Transfer(format!(
+            "unsupported key derivation cost {}",
+            envelope.iterations
+        )));
+    }
+    let salt = BASE64.decode(&envelope.salt)?;
+    let crypto = CryptoService::new(derive_key(passphrase, &salt, KDF_ITERATIONS)?)?;
+    let plain = crypto
+        .decrypt_bytes(&BASE64.decode(&envelope.payload)?)
+        .map_err(|_| ConfigError::Transfer("incorrect passphrase or corrupted export".into()))?;
+    Ok(serde_json::from_slice(&plain)?)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> Result<[u8; 32], ConfigError> {
+    let iterations = NonZeroU32::new(iterations)
+        .ok_or_else(|| ConfigError::Transfer("invalid key derivation parameters".into()))?;
+    let mut key = [0u8; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    Ok(key)
+}
+
+fn ensure_passphrase(passphrase: &str) -> Result<(), ConfigError> {
+    if passphrase.is_empty() {
+        return Err(ConfigError::Transfer("passphrase must not be empty".into()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> ConfigBundle {
+        ConfigBundle {
+            providers: vec![ExportedProvider {
+                provider: "openai".into(),
+                display_name: "OpenAI".into(),
+                api_key: "sk-test".into(),
+                default_model: None,
+                is_default: true,
+            }],
+            settings: vec![],
+        }
+    }
+
+    #[test]
+    fn bundle_roundtrip() {
+        let sealed = seal_bundle(&sample(), "correct horse").unwrap();
+        let opened = open_bundle(&sealed, "correct horse").unwrap();
+        assert_eq!(opened.providers[0].api_key, "sk-test");
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let sealed = seal_bundle(&sample(), "correct horse").unwrap();
+        let err = open_bundle(&sealed, "battery staple").unwrap_err();
+        assert!(matches!(err, ConfigError::Transfer(_)));
+    }
+
+    #[test]
+    fn foreign_iteration_counts_are_rejected() {
+        let sealed = seal_bundle(&sample(), "correct horse").unwrap();
+        let mut envelope: Envelope = serde_json::from_slice(&sealed).unwrap();
+        envelope.iterations = u32::MAX;
+        let tampered = serde_json::to_vec(&envelope).unwrap();
+        let err = open_bundle(&tampered, "correct horse").unwrap_err();
+        assert!(err.to_string().contains("key derivation cost"));
+    }
+}
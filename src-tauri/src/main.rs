@@ -2,21 +2,106 @@
 
 mod commands;
 mod config;
+mod mcp;
 mod services;
 
 use commands::{
-    chat::{invoke_chat, stream_chat},
+    backups::{create_backup, list_backups, restore_backup},
+    chat::{
+        edit_and_resend, get_conversation_branch, invoke_chat, regenerate_message, stream_chat,
+        switch_branch,
+    },
+    conversation_export::{export_conversation, import_conversations},
+    documents::{
+        create_document_collection, delete_document_collection, ingest_documents,
+        list_document_collections, list_document_files, search_documents,
+    },
+    jobs::{list_jobs, retry_job},
+    mcp::{
+        add_mcp_server, get_mcp_prompt, install_mcp_server, list_mcp_prompts, list_mcp_resources,
+        list_mcp_servers, read_mcp_resource, remove_mcp_server, start_mcp_server, stop_mcp_server,
+        uninstall_mcp_server, upgrade_mcp_server,
+    },
+    memory::{add_memory_note, delete_memory_note, index_memory, list_memory_notes, search_memory},
     preferences::{get_preferences, save_preferences},
     providers::{has_any_provider, list_providers, set_default_provider, upsert_provider},
+    recovery::{get_health_report, recover_from_backup, repair_database, reset_database},
+    scheduled_jobs::{
+        create_scheduled_job, delete_scheduled_job, list_scheduled_job_runs, list_scheduled_jobs,
+        pause_scheduled_job, run_scheduled_job_now,
+    },
+    search::search_messages,
+    settings::{list_settings, update_setting},
+    tools::{
+        approve_tool_call, clear_tool_policy, list_tool_audit, list_tool_policies, set_tool_policy,
+    },
+    transfer::{export_config, import_config},
 };
+use config::{backup::start_backup_scheduler, paths::ConfigPaths, recovery::RecoveryService};
+use mcp::manager::McpManager;
+use services::{
+    config_events::forward_config_changes,
+    documents::register_document_jobs,
+    job_queue::{forward_job_updates, JobQueue, DEFAULT_WORKERS},
+    memory::register_memory_jobs,
+    scheduled_jobs::ScheduledJobService,
+    titles::register_title_jobs,
+    tool_policy::ToolPolicyService,
+};
+use tauri::Manager;
+use tracing::warn;
 
 fn main() {
-    let config_service =
-        tauri::async_runtime::block_on(config::service::ConfigService::initialize())
-            .expect("failed to initialize config service");
+    let paths = ConfigPaths::default().expect("failed to resolve config directory");
+    // A config service that fails to start leaves the app in recovery mode instead of
+    // panicking; the frontend reads `get_health_report` to decide what to show.
+    let (config_service, recovery) =
+        tauri::async_runtime::block_on(RecoveryService::startup(paths));
+
+    let mut builder = tauri::Builder::default().manage(recovery);
+    if let Some(config_service) = config_service {
+        match tauri::async_runtime::block_on(start_backup_scheduler(config_service.clone())) {
+            Ok(scheduler) => builder = builder.manage(scheduler),
+            Err(err) => warn!("backup scheduler unavailable: {err}"),
+        }
+        // Chat commands depend on both, so they are always managed, even if their startup
+        // housekeeping failed.
+        let mcp_manager = tauri::async_runtime::block_on(McpManager::new(config_service.clone()));
+        builder = builder.manage(mcp_manager.clone());
+        let policies = ToolPolicyService::new(config_service.pool().clone());
+        builder = builder.manage(policies.clone());
+        let queue = tauri::async_runtime::block_on(JobQueue::new(config_service.pool().clone()));
+        register_memory_jobs(&queue, config_service.clone());
+        register_document_jobs(&queue, config_service.clone());
+        builder = builder.manage(queue.clone());
+        let events_source = config_service.clone();
+        builder = builder.manage(config_service).setup(move |app| {
+            forward_config_changes(app.handle().clone(), &events_source);
+            forward_job_updates(app.handle().clone(), &queue);
+            // Titles are announced as events, so their handler needs the app handle.
+            register_title_jobs(&queue, events_source.clone(), app.handle().clone());
+            // Scheduled jobs need an app handle for their events, so they start here. If they
+            // cannot, their queued runs wait for a later launch.
+            match tauri::async_runtime::block_on(ScheduledJobService::start(
+                app.handle().clone(),
+                events_source,
+                mcp_manager.clone(),
+                policies,
+                queue.clone(),
+            )) {
+                Ok(jobs) => {
+                    app.manage(jobs);
+                }
+                Err(err) => warn!("job scheduler unavailable: {err}"),
+            }
+            tauri::async_runtime::spawn(async move { mcp_manager.start_auto_servers().await });
+            // Handlers are registered above; start draining only once they are in place.
+            queue.start(DEFAULT_WORKERS);
+            Ok(())
+        });
+    }
 
-    tauri::Builder::default()
-        .manage(config_service)
+    builder
         .invoke_handler(tauri::generate_handler![
             get_preferences,
             save_preferences,
@@ -25,7 +110,61 @@ fn main() {
             list_providers,
             upsert_provider,
             set_default_provider,
-            has_any_provider
+            has_any_provider,
+            export_config,
+            import_config,
+            list_backups,
+            create_backup,
+            restore_backup,
+            get_health_report,
+            repair_database,
+            recover_from_backup,
+            reset_database,
+            list_settings,
+            update_setting,
+            list_mcp_servers,
+            add_mcp_server,
+            remove_mcp_server,
+            start_mcp_server,
+            stop_mcp_server,
+            install_mcp_server,
+            upgrade_mcp_server,
+            uninstall_mcp_server,
+            list_mcp_resources,
+            read_mcp_resource,
+            list_mcp_prompts,
+            get_mcp_prompt,
+            list_tool_policies,
+            set_tool_policy,
+            clear_tool_policy,
+            approve_tool_call,
+            list_tool_audit,
+            list_scheduled_jobs,
+            create_scheduled_job,
+            pause_scheduled_job,
+            delete_scheduled_job,
+            run_scheduled_job_now,
+            list_scheduled_job_runs,
+            list_jobs,
+            retry_job,
+            search_messages,
+            list_memory_notes,
+            add_memory_note,
+            delete_memory_note,
+            search_memory,
+            index_memory,
+            list_document_collections,
+            create_document_collection,
+            delete_document_collection,
+            list_document_files,
+            ingest_documents,
+            search_documents,
+            regenerate_message,
+            edit_and_resend,
+            switch_branch,
+            get_conversation_branch,
+            export_conversation,
+            import_conversations
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,350 @@
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, oneshot, watch};
+use tracing::{debug, warn};
+
+use super::error::McpError;
+use super::protocol::{
+    CallToolResult, GetPromptResult, InitializeResult, JsonRpcError, JsonRpcMessage,
+    JsonRpcRequest, Prompt, ReadResourceResult, Resource, Tool, JSONRPC_VERSION, METHOD_NOT_FOUND,
+    PROTOCOL_VERSION,
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, McpError>>>>>;
+
+/// A JSON-RPC session with one MCP server. The client only deals in messages; a transport
+/// task moves them between the outgoing channel and the server and feeds replies back
+/// through [`Inbound`].
+pub struct McpClient {
+    outgoing: mpsc::UnboundedSender<Value>,
+    pending: Pending,
+    next_id: AtomicU64,
+    closed: watch::Receiver<bool>,
+    server: OnceLock<InitializeResult>,
+}
+
+/// Transport-side half of a client: routes messages read from the server. Holds only a weak
+/// handle on the outgoing channel, so the channel closes once the client is dropped.
+pub struct Inbound {
+    pending: Pending,
+    replies: mpsc::WeakUnboundedSender<Value>,
+    closed: watch::Sender<bool>,
+}
+
+impl McpClient {
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<Value>, Inbound) {
+        let (outgoing, outgoing_rx) = mpsc::unbounded_channel();
+        let (closed_tx, closed) = watch::channel(false);
+        let pending: Pending = Arc::default();
+        let inbound = Inbound {
+            pending: pending.clone(),
+            replies: outgoing.downgrade(),
+            closed: closed_tx,
+        };
+        let client = Self {
+            outgoing,
+            pending,
+            next_id: AtomicU64::new(1),
+            closed,
+            server: OnceLock::new(),
+        };
+        (client, outgoing_rx, inbound)
+    }
+
+    /// Performs the `initialize` handshake and sends `notifications/initialized`.
+    pub async fn initialize(&self) -> Result<InitializeResult, McpError> {
+        let result = self
+            .request(
+                "initialize",
+                Some(json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {
+                        "name": "aethos",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                })),
+            )
+            .await?;
+        let result: InitializeResult = serde_json::from_value(result)?;
+        self.notify("notifications/initialized", None)?;
+        let _ = self.server.set(result.clone());
+        Ok(result)
+    }
+
+    pub fn server_info(&self) -> Option<&InitializeResult> {
+        self.server.get()
+    }
+
+    pub async fn list_tools(&self) -> Result<Vec<Tool>, McpError> {
+        self.list_all("tools/list", "tools").await
+    }
+
+    pub async fn call_tool(
+        &self,
+        name: &str,
+        arguments: Value,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .request(
+                "tools/call",
+                Some(json!({ "name": name, "arguments": arguments })),
+            )
+            .await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub async fn list_resources(&self) -> Result<Vec<Resource>, McpError> {
+        self.list_all("resources/list", "resources").await
+    }
+
+    pub async fn read_resource(&self, uri: &str) -> Result<ReadResourceResult, McpError> {
+        let result = self
+            .request("resources/read", Some(json!({ "uri": uri })))
+            .await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    pub async fn list_prompts(&self) -> Result<Vec<Prompt>, McpError> {
+        self.list_all("prompts/list", "prompts").await
+    }
+
+    pub async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: &BTreeMap<String, String>,
+    ) -> Result<GetPromptResult, McpError> {
+        let result = self
+            .request(
+                "prompts/get",
+                Some(json!({ "name": name, "arguments": arguments })),
+            )
+            .await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Fetches every page of a paginated `*/list` method and collects `field` from each.
+    async fn list_all<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        field: &str,
+    ) -> Result<Vec<T>, McpError> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = cursor.as_ref().map(|cursor| json!({ "cursor": cursor }));
+            let mut page = self.request(method, params).await?;
+            let listed = page.get_mut(field).map(Value::take).unwrap_or_default();
+            let listed: Vec<T> = match listed {
+                Value::Null => Vec::new(),
+                listed => serde_json::from_value(listed)?,
+            };
+            items.extend(listed);
+            match page["nextCursor"].as_str() {
+                Some(next) if !next.is_empty() => cursor = Some(next.to_string()),
+                _ => return Ok(items),
+            }
+        }
+    }
+
+    pub async fn request(&self, method: &str, params: Option<Value>) -> Result<Value, McpError> {
+        if self.is_closed() {
+            return Err(McpError::Disconnected);
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        lock(&self.pending).insert(id, tx);
+        let message = serde_json::to_value(JsonRpcRequest {
+            jsonrpc: JSONRPC_VERSION,
+            id: Some(id),
+            method,
+            params,
+        })?;
+        if self.outgoing.send(message).is_err() {
+            lock(&self.pending).remove(&id);
+            return Err(McpError::Disconnected);
+        }
+        match tokio::time::timeout(REQUEST_TIMEOUT, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(McpError::Disconnected),
+            Err(_) => {
+                lock(&self.pending).remove(&id);
+                Err(McpError::Timeout(method.to_string()))
+            }
+        }
+    }
+
+    pub fn notify(&self, method: &str, params: Option<Value>) -> Result<(), McpError> {
+        let message = serde_json::to_value(JsonRpcRequest {
+            jsonrpc: JSONRPC_VERSION,
+            id: None,
+            method,
+            params,
+        })?;
+        self.outgoing
+            .send(message)
+            .map_err(|_| McpError::Disconnected)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        *self.closed.borrow()
+    }
+
+    /// Resolves once the transport has shut down. The future does not borrow the client.
+    pub fn closed(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut closed = self.closed.clone();
+        async move {
+            let _ = closed.wait_for(|closed| *closed).await;
+        }
+    }
+}
+
+impl Inbound {
+    pub fn dispatch(&self, raw: &str) {
+        match serde_json::from_str(raw) {
+            Ok(message) => self.route(message),
+            Err(err) => warn!("ignoring malformed MCP message: {err}"),
+        }
+    }
+
+    pub fn dispatch_value(&self, value: Value) {
+        match serde_json::from_value(value) {
+            Ok(message) => self.route(message),
+            Err(err) => warn!("ignoring malformed MCP message: {err}"),
+        }
+    }
+
+    /// Fails the request behind an outgoing `message` when the transport could not deliver
+    /// it. Does nothing for notifications and replies.
+    pub fn fail(&self, message: &Value, reason: String) {
+        if message.get("method").is_none() {
+            return;
+        }
+        let Some(waiter) = message
+            .get("id")
+            .and_then(Value::as_u64)
+            .and_then(|id| lock(&self.pending).remove(&id))
+        else {
+            return;
+        };
+        let _ = waiter.send(Err(McpError::Transport(reason)));
+    }
+
+    fn route(&self, message: JsonRpcMessage) {
+        match (message.id, message.method) {
+            (Some(id), Some(method)) => self.answer_server_request(id, &method),
+            (None, Some(method)) => debug!(method, "MCP notification"),
+            (Some(id), None) => {
+                let Some(waiter) = id.as_u64().and_then(|id| lock(&self.pending).remove(&id))
+                else {
+                    debug!(%id, "response for unknown MCP request");
+                    return;
+                };
+                let outcome = match message.error {
+                    Some(error) => Err(McpError::Rpc {
+                        code: error.code,
+                        message: error.message,
+                    }),
+                    None => Ok(message.result.unwrap_or(Value::Null)),
+                };
+                let _ = waiter.send(outcome);
+            }
+            (None, None) => warn!("ignoring MCP message without id or method"),
+        }
+    }
+
+    /// Marks the session closed and fails every in-flight request.
+    pub fn close(&self) {
+        let _ = self.closed.send(true);
+        lock(&self.pending).clear();
+    }
+
+    fn answer_server_request(&self, id: Value, method: &str) {
+        let reply = if method == "ping" {
+            json!({ "jsonrpc": JSONRPC_VERSION, "id": id, "result": {} })
+        } else {
+            json!({
+                "jsonrpc": JSONRPC_VERSION,
+                "id": id,
+                "error": JsonRpcError {
+                    code: METHOD_NOT_FOUND,
+                    message: format!("method `{method}` is not supported"),
+                    data: None,
+                },
+            })
+        };
+        if let Some(replies) = self.replies.upgrade() {
+            let _ = replies.send(reply);
+        }
+    }
+}
+
+/// Launches `command` and speaks newline-delimited JSON-RPC over its stdin/stdout.
+pub fn spawn_stdio(
+    command: &str,
+    args: &[String],
+    env: &BTreeMap<String, String>,
+    cwd: Option<&std::path::Path>,
+) -> Result<(McpClient, Child), McpError> {
+    let mut process = Command::new(command);
+    process
+        .args(args)
+        .envs(env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(cwd) = cwd {
+        process.current_dir(cwd);
+    }
+    let mut child = process.spawn()?;
+    let mut stdin = child.stdin.take().ok_or(McpError::Disconnected)?;
+    let stdout = child.stdout.take().ok_or(McpError::Disconnected)?;
+    let stderr = child.stderr.take().ok_or(McpError::Disconnected)?;
+    let (client, mut outgoing, inbound) = McpClient::new();
+
+    tokio::spawn(async move {
+        while let Some(message) = outgoing.recv().await {
+            let mut line = message.to_string();
+            line.push('\n');
+            if stdin.write_all(line.as_bytes()).await.is_err() || stdin.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if !line.trim().is_empty() {
+                inbound.dispatch(&line);
+            }
+        }
+        inbound.close();
+    });
+    let label = command.to_string();
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            debug!(server = label.as_str(), "{line}");
+        }
+    });
+
+    Ok((client, child))
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -0,0 +1,31 @@
+use thiserror::Error;
+
+use crate::config::ConfigError;
+
+#[derive(Debug, Error)]
+pub enum McpError {
+    #[error("{0}")]
+    Config(#[from] ConfigError),
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("i/o error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("unknown MCP server `{0}`")]
+    UnknownServer(String),
+    #[error("MCP server `{0}` already exists")]
+    DuplicateServer(String),
+    #[error("MCP server `{0}` is not running")]
+    NotRunning(String),
+    #[error("invalid MCP server definition: {0}")]
+    InvalidDefinition(String),
+    #[error("MCP server error {code}: {message}")]
+    Rpc { code: i64, message: String },
+    #[error("MCP request `{0}` timed out")]
+    Timeout(String),
+    #[error("MCP transport error: {0}")]
+    Transport(String),
+    #[error("MCP connection closed")]
+    Disconnected,
+}
@@ -0,0 +1,283 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::{Client, RequestBuilder, Response, StatusCode, Url};
+use serde_json::Value;
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+
+use super::client::{Inbound, McpClient};
+use super::error::McpError;
+
+const SESSION_HEADER: &str = "mcp-session-id";
+const EVENT_STREAM: &str = "text/event-stream";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Attempts per POST when the server cannot be reached; delays double from `RETRY_DELAY`.
+const POST_ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Connects using the Streamable HTTP transport: every message is POSTed to `url` and the
+/// reply comes back as JSON or as an event stream. The session closes when the server
+/// forgets the session id or stays unreachable after retries.
+pub fn connect_streamable_http(url: Url, auth: Option<&str>) -> Result<McpClient, McpError> {
+    let http = http_client(auth)?;
+    let (client, mut outgoing, inbound) = McpClient::new();
+    let inbound = Arc::new(inbound);
+
+    tokio::spawn(async move {
+        let mut session: Option<HeaderValue> = None;
+        while let Some(message) = outgoing.recv().await {
+            let mut request = http
+                .post(url.clone())
+                .header(ACCEPT, format!("application/json, {EVENT_STREAM}"))
+                .json(&message);
+            if let Some(session) = &session {
+                request = request.header(SESSION_HEADER, session.clone());
+            }
+            let response = match send_with_retry(request).await {
+                Ok(response) => response,
+                Err(err) => {
+                    warn!(url = url.as_str(), "MCP server unreachable: {err}");
+                    inbound.fail(&message, err.to_string());
+                    break;
+                }
+            };
+            if let Some(id) = response.headers().get(SESSION_HEADER) {
+                session = Some(id.clone());
+            }
+            let status = response.status();
+            if status == StatusCode::NOT_FOUND && session.is_some() {
+                warn!(url = url.as_str(), "MCP session expired");
+                inbound.fail(&message, "session expired".into());
+                break;
+            }
+            if status == StatusCode::ACCEPTED {
+                continue;
+            }
+            if !status.is_success() {
+                inbound.fail(&message, format!("HTTP {status}"));
+                continue;
+            }
+            tokio::spawn(read_reply(response, inbound.clone()));
+        }
+
+        if let Some(session) = session {
+            let _ = http
+                .delete(url.clone())
+                .header(SESSION_HEADER, session)
+                .send()
+                .await;
+        }
+        inbound.close();
+    });
+
+    Ok(client)
+}
+
+/// Connects using the legacy HTTP+SSE transport: server messages arrive on a long-lived GET
+/// stream, whose first `endpoint` event names the URL to POST client messages to.
+pub fn connect_sse(url: Url, auth: Option<&str>) -> Result<McpClient, McpError> {
+    let http = http_client(auth)?;
+    let (client, mut outgoing, inbound) = McpClient::new();
+    let inbound = Arc::new(inbound);
+    let (endpoint_tx, endpoint_rx) = oneshot::channel::<Url>();
+
+    let reader = tokio::spawn({
+        let http = http.clone();
+        let inbound = inbound.clone();
+        async move {
+            let response = http
+                .get(url.clone())
+                .header(ACCEPT, EVENT_STREAM)
+                .send()
+                .await
+                .and_then(Response::error_for_status);
+            match response {
+                Ok(response) => {
+                    let mut endpoint_tx = Some(endpoint_tx);
+                    read_events(response, |event| match event.name.as_deref() {
+                        Some("endpoint") => match url.join(event.data.trim()) {
+                            Ok(endpoint) => {
+                                if let Some(tx) = endpoint_tx.take() {
+                                    let _ = tx.send(endpoint);
+                                }
+                            }
+                            Err(err) => warn!("invalid MCP endpoint event: {err}"),
+                        },
+                        None | Some("message") => inbound.dispatch(&event.data),
+                        Some(other) => debug!(event = other, "ignoring SSE event"),
+                    })
+                    .await;
+                }
+                Err(err) => warn!(url = url.as_str(), "MCP event stream failed: {err}"),
+            }
+            inbound.close();
+        }
+    });
+
+    tokio::spawn(async move {
+        if let Ok(endpoint) = endpoint_rx.await {
+            while let Some(message) = outgoing.recv().await {
+                let request = http.post(endpoint.clone()).json(&message);
+                match send_with_retry(request).await {
+                    Ok(response) if response.status().is_success() => {}
+                    Ok(response) => inbound.fail(&message, format!("HTTP {}", response.status())),
+                    Err(err) => inbound.fail(&message, err.to_string()),
+                }
+            }
+        }
+        reader.abort();
+        inbound.close();
+    });
+
+    Ok(client)
+}
+
+fn http_client(auth: Option<&str>) -> Result<Client, McpError> {
+    let mut headers = HeaderMap::new();
+    if let Some(auth) = auth {
+        let mut value = HeaderValue::from_str(auth).map_err(|_| {
+            McpError::InvalidDefinition("auth header is not a valid header value".into())
+        })?;
+        value.set_sensitive(true);
+        headers.insert(AUTHORIZATION, value);
+    }
+    Client::builder()
+        .default_headers(headers)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .map_err(|err| McpError::Transport(err.to_string()))
+}
+
+async fn send_with_retry(request: RequestBuilder) -> Result<Response, reqwest::Error> {
+    let mut delay = RETRY_DELAY;
+    for _ in 1..POST_ATTEMPTS {
+        let Some(attempt) = request.try_clone() else {
+            break;
+        };
+        match attempt.send().await {
+            Err(err) if err.is_connect() || err.is_timeout() => {
+                debug!("retrying MCP request: {err}");
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    request.send().await
+}
+
+/// Routes the body of a Streamable HTTP reply, which is either JSON (one message or a batch)
+/// or an event stream carrying messages.
+async fn read_reply(response: Response, inbound: Arc<Inbound>) {
+    let is_stream = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with(EVENT_STREAM));
+    if is_stream {
+        read_events(response, |event| {
+            if matches!(event.name.as_deref(), None | Some("message")) {
+                inbound.dispatch(&event.data);
+            }
+        })
+        .await;
+        return;
+    }
+    match response.json::<Value>().await {
+        Ok(Value::Array(batch)) => batch
+            .into_iter()
+            .for_each(|message| inbound.dispatch_value(message)),
+        Ok(message) => inbound.dispatch_value(message),
+        Err(err) => warn!("unreadable MCP reply: {err}"),
+    }
+}
+
+async fn read_events(response: Response, mut on_event: impl FnMut(SseEvent)) {
+    let mut parser = SseParser::default();
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        match chunk {
+            Ok(chunk) => parser.push(&chunk).into_iter().for_each(&mut on_event),
+            Err(err) => {
+                debug!("MCP event stream ended: {err}");
+                return;
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct SseEvent {
+    name: Option<String>,
+    data: String,
+}
+
+/// Incremental `text/event-stream` decoder.
+#[derive(Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer
+            .extend(chunk.iter().filter(|byte| **byte != b'\r'));
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|pair| pair == b"\n\n") {
+            let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            if let Some(event) = parse_event(&String::from_utf8_lossy(&block)) {
+                events.push(event);
+            }
+        }
+        events
+    }
+}
+
+fn parse_event(block: &str) -> Option<SseEvent> {
+    let mut name = None;
+    let mut data: Vec<&str> = Vec::new();
+    for line in block.lines() {
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => name = Some(value.to_string()),
+            "data" => data.push(value),
+            _ => {}
+        }
+    }
+    if data.is_empty() {
+        return None;
+    }
+    Some(SseEvent {
+        name,
+        data: data.join("\n"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_events_split_across_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b"event: endpoint\r\ndata: /mess").is_empty());
+        let events = parser.push(b"ages?s=1\r\n\r\n: keep-alive\n\ndata: {\"a\":\ndata: 1}\n\n");
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    name: Some("endpoint".into()),
+                    data: "/messages?s=1".into(),
+                },
+                SseEvent {
+                    name: None,
+                    data: "{\"a\":\n1}".into(),
+                },
+            ]
+        );
+    }
+}
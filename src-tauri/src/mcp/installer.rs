@@ -0,0 +1,533 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::error::McpError;
+use super::manager::{summarize, validate_slug, McpManager, McpServerSummary};
+use super::store::{NewServer, ServerLaunch};
+
+/// Largest package an install copies, so a manifest in a crowded directory such as
+/// Downloads cannot pull all of it into the app data directory.
+const MAX_PACKAGE_BYTES: u64 = 256 * 1024 * 1024;
+const MAX_PACKAGE_FILES: usize = 10_000;
+
+/// Describes an installable stdio server. The manifest sits at the root of the package
+/// directory; it and the `files` it lists are copied to `ConfigPaths::mcp_dir/<slug>` on
+/// install.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerManifest {
+    pub slug: String,
+    pub name: String,
+    pub version: String,
+    /// Program to run. Paths starting with `./` are relative to the package directory.
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Files and directories the server needs, relative to the package directory.
+    #[serde(default)]
+    pub files: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Environment variables the user must supply; stored encrypted.
+    #[serde(default)]
+    pub secrets: Vec<ManifestSecret>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestSecret {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+impl ServerManifest {
+    pub fn load(path: &Path) -> Result<Self, McpError> {
+        let manifest: Self = serde_json::from_slice(&std::fs::read(path)?)?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<(), McpError> {
+        validate_slug(&self.slug)?;
+        if self.name.trim().is_empty() {
+            return Err(invalid("manifest needs a name"));
+        }
+        parse_version(&self.version)?;
+        if self.command.trim().is_empty() {
+            return Err(invalid("manifest needs a command"));
+        }
+        for file in &self.files {
+            let relative = Path::new(file);
+            let inside = relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+            if file.is_empty() || !inside {
+                return Err(invalid(format!(
+                    "package file `{file}` must be a path inside the package directory"
+                )));
+            }
+        }
+        for secret in &self.secrets {
+            if !is_env_name(&secret.name) {
+                return Err(invalid(format!(
+                    "secret `{}` is not a valid environment variable name",
+                    secret.name
+                )));
+            }
+            if self.env.contains_key(&secret.name) {
+                return Err(invalid(format!(
+                    "`{}` is declared both as env and as a secret",
+                    secret.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn launch(&self, install_dir: &Path) -> ServerLaunch {
+        let command = match self.command.strip_prefix("./") {
+            Some(relative) => install_dir.join(relative).to_string_lossy().into_owned(),
+            None => self.command.clone(),
+        };
+        ServerLaunch::Stdio {
+            command,
+            args: self.args.clone(),
+            env: self.env.clone(),
+        }
+    }
+
+    /// Encrypts the declared secrets found in `provided`, falling back to `existing` (already
+    /// encrypted) for ones not provided. Undeclared secrets are dropped.
+    fn seal_secrets(
+        &self,
+        manager: &McpManager,
+        provided: &HashMap<String, String>,
+        existing: &[(String, String)],
+    ) -> Result<Vec<(String, String)>, McpError> {
+        let mut sealed = Vec::new();
+        for secret in &self.secrets {
+            let value = match provided.get(&secret.name).filter(|value| !value.is_empty()) {
+                Some(value) => Some(manager.config.crypto().encrypt(value)?),
+                None => existing
+                    .iter()
+                    .find(|(name, _)| *name == secret.name)
+                    .map(|(_, value)| value.clone()),
+            };
+            match value {
+                Some(value) => sealed.push((secret.name.clone(), value)),
+                None if secret.required => {
+                    return Err(invalid(format!(
+                        "missing required secret `{}`",
+                        secret.name
+                    )))
+                }
+                None => {}
+            }
+        }
+        Ok(sealed)
+    }
+}
+
+impl McpManager {
+    /// Installs the package whose manifest is at `manifest_path`.
+    pub async fn install(
+        &self,
+        manifest_path: &Path,
+        secrets: HashMap<String, String>,
+        auto_start: bool,
+    ) -> Result<McpServerSummary, McpError> {
+        let manifest = ServerManifest::load(manifest_path)?;
+        match self.store.get(&manifest.slug).await {
+            Ok(_) => return Err(McpError::DuplicateServer(manifest.slug)),
+            Err(McpError::UnknownServer(_)) => {}
+            Err(err) => return Err(err),
+        }
+        let sealed = manifest.seal_secrets(self, &secrets, &[])?;
+
+        let install_dir = self.install_dir(&manifest.slug);
+        materialize(manifest_path, &manifest, install_dir.clone()).await?;
+        let record = self
+            .store
+            .insert(NewServer {
+                slug: manifest.slug.clone(),
+                name: manifest.name.clone(),
+                launch: manifest.launch(&install_dir),
+                auto_start,
+                auth_header: None,
+                version: Some(manifest.version.clone()),
+                install_path: Some(install_dir.to_string_lossy().into_owned()),
+            })
+            .await;
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                let _ = std::fs::remove_dir_all(&install_dir);
+                return Err(err);
+            }
+        };
+        self.store.replace_secrets(record.id, &sealed).await?;
+        info!(
+            server = manifest.slug.as_str(),
+            version = manifest.version.as_str(),
+            "MCP server installed"
+        );
+        Ok(summarize(record, None))
+    }
+
+    /// Replaces an installed server with a newer version of its package. Secrets carry over
+    /// unless `secrets` overrides them; a server that was running is restarted.
+    pub async fn upgrade(
+        self: &Arc<Self>,
+        slug: &str,
+        manifest_path: &Path,
+        secrets: HashMap<String, String>,
+    ) -> Result<McpServerSummary, McpError> {
+        let record = self.store.get(slug).await?;
+        let manifest = ServerManifest::load(manifest_path)?;
+        if manifest.slug != slug {
+            return Err(invalid(format!(
+                "manifest is for `{}`, not `{slug}`",
+                manifest.slug
+            )));
+        }
+        if record.install_path.is_none() {
+            return Err(invalid(format!(
+                "`{slug}` was not installed from a manifest"
+            )));
+        }
+        let current = record.version.as_deref().unwrap_or("0");
+        if parse_version(&manifest.version)? <= parse_version(current)? {
+            return Err(invalid(format!(
+                "`{slug}` {current} is not older than {}",
+                manifest.version
+            )));
+        }
+        let existing = self.store.secrets(record.id).await?;
+        let sealed = manifest.seal_secrets(self, &secrets, &existing)?;
+
+        let was_running = self.is_running(slug).await;
+        if was_running {
+            self.stop(slug).await?;
+        }
+        let install_dir = self.install_dir(slug);
+        materialize(manifest_path, &manifest, install_dir.clone()).await?;
+        self.store
+            .update_install(
+                slug,
+                &manifest.name,
+                &manifest.launch(&install_dir),
+                &manifest.version,
+                &install_dir.to_string_lossy(),
+            )
+            .await?;
+        self.store.replace_secrets(record.id, &sealed).await?;
+        info!(
+            server = slug,
+            from = current,
+            to = manifest.version.as_str(),
+            "MCP server upgraded"
+        );
+        if was_running {
+            return self.start(slug).await;
+        }
+        Ok(summarize(self.store.get(slug).await?, None))
+    }
+
+    /// Stops and unregisters the server and deletes its installed files.
+    pub async fn uninstall(&self, slug: &str) -> Result<(), McpError> {
+        let record = self.store.get(slug).await?;
+        self.remove(slug).await?;
+        if let Some(install_path) = record.install_path {
+            if let Err(err) = tokio::fs::remove_dir_all(&install_path).await {
+                warn!(server = slug, "failed to delete installed files: {err}");
+            }
+        }
+        info!(server = slug, "MCP server uninstalled");
+        Ok(())
+    }
+
+    fn install_dir(&self, slug: &str) -> PathBuf {
+        self.config.paths.mcp_dir.join(slug)
+    }
+}
+
+/// Copies the manifest and its listed files to `target` through a staging directory so a
+/// failed copy never leaves a half-written install behind.
+async fn materialize(
+    manifest_path: &Path,
+    manifest: &ServerManifest,
+    target: PathBuf,
+) -> Result<(), McpError> {
+    let manifest_path = manifest_path.to_path_buf();
+    let files = manifest.files.clone();
+    tokio::task::spawn_blocking(move || {
+        let parent = target
+            .parent()
+            .ok_or_else(|| invalid("invalid install path"))?;
+        std::fs::create_dir_all(parent)?;
+        let name = target.file_name().unwrap_or_default().to_string_lossy();
+        let staging = parent.join(format!(".{name}.staging"));
+        let previous = parent.join(format!(".{name}.previous"));
+        for stale in [&staging, &previous] {
+            if stale.exists() {
+                std::fs::remove_dir_all(stale)?;
+            }
+        }
+        let mut budget = CopyBudget {
+            files: MAX_PACKAGE_FILES,
+            bytes: MAX_PACKAGE_BYTES,
+        };
+        if let Err(err) = copy_package(&manifest_path, &files, &staging, &mut budget) {
+            let _ = std::fs::remove_dir_all(&staging);
+            return Err(err);
+        }
+        if target.exists() {
+            std::fs::rename(&target, &previous)?;
+        }
+        std::fs::rename(&staging, &target)?;
+        if previous.exists() {
+            std::fs::remove_dir_all(&previous)?;
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|err| McpError::Io(std::io::Error::other(err)))?
+}
+
+/// What a package may still add to an install.
+struct CopyBudget {
+    files: usize,
+    bytes: u64,
+}
+
+fn copy_package(
+    manifest_path: &Path,
+    files: &[String],
+    target: &Path,
+    budget: &mut CopyBudget,
+) -> Result<(), McpError> {
+    let source = manifest_path.parent().unwrap_or(Path::new(""));
+    std::fs::create_dir_all(target)?;
+    let manifest_name = manifest_path.file_name().unwrap_or_default();
+    copy_entry(manifest_path, &target.join(manifest_name), budget)?;
+    for file in files {
+        let path = source.join(file);
+        if !path.exists() {
+            return Err(invalid(format!("package file `{file}` does not exist")));
+        }
+        let destination = target.join(file);
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        copy_entry(&path, &destination, budget)?;
+    }
+    Ok(())
+}
+
+/// Recursive copy of regular files and directories; symlinks are skipped so a package
+/// cannot pull in files from outside its directory.
+fn copy_entry(source: &Path, target: &Path, budget: &mut CopyBudget) -> Result<(), McpError> {
+    let file_type = std::fs::symlink_metadata(source)?.file_type();
+    if file_type.is_dir() {
+        std::fs::create_dir_all(target)?;
+        for entry in std::fs::read_dir(source)? {
+            let entry = entry?;
+            copy_entry(&entry.path(), &target.join(entry.file_name()), budget)?;
+        }
+    } else if file_type.is_file() {
+        let size = std::fs::metadata(source)?.len();
+        if budget.files == 0 || size > budget.bytes {
+            return Err(invalid(format!(
+                "package is larger than {MAX_PACKAGE_FILES} files or {} MiB",
+                MAX_PACKAGE_BYTES / (1024 * 1024)
+            )));
+        }
+        budget.files -= 1;
+        budget.bytes -= size;
+        std::fs::copy(source, target)?;
+    }
+    Ok(())
+}
+
+/// Dotted numeric versions (`1.2.10`), compared component by component. Pre-release and
+/// build suffixes after `-` or `+` are ignored.
+fn parse_version(version: &str) -> Result<Vec<u64>, McpError> {
+    let core = version
+        .trim()
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default();
+    let mut parts = core
+        .split('.')
+        .map(|part| part.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid(format!("invalid version `{version}`")))?;
+    while parts.len() > 1 && parts.last() == Some(&0) {
+        parts.pop();
+    }
+    Ok(parts)
+}
+
+fn is_env_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn invalid(message: impl Into<String>) -> McpError {
+    McpError::InvalidDefinition(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::config::{paths::ConfigPaths, service::ConfigService};
+    use crate::mcp::store::ServerStatus;
+
+    fn write_package(dir: &Path, version: &str) -> PathBuf {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::copy(
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/fake_mcp_server.py"
+            ),
+            dir.join("server.py"),
+        )
+        .unwrap();
+        let manifest = dir.join("manifest.json");
+        std::fs::write(
+            &manifest,
+            serde_json::to_vec(&json!({
+                "slug": "packaged",
+                "name": "Packaged",
+                "version": version,
+                "command": "python3",
+                "args": ["server.py"],
+                "files": ["server.py"],
+                "secrets": [{ "name": "FAKE_MCP_INSTRUCTIONS" }],
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+        manifest
+    }
+
+    #[tokio::test]
+    async fn installs_upgrades_and_uninstalls() {
+        let temp_dir = tempdir().unwrap();
+        let config = ConfigService::with_paths(
+            ConfigPaths::from_base_dir(temp_dir.path().join("config")).unwrap(),
+        )
+        .await
+        .unwrap();
+        let manager = McpManager::new(config.clone()).await;
+        let manifest = write_package(&temp_dir.path().join("v1"), "1.0.0");
+        std::fs::write(temp_dir.path().join("v1/unrelated.txt"), "not part of it").unwrap();
+
+        let err = manager
+            .install(&manifest, HashMap::new(), false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, McpError::InvalidDefinition(_)));
+
+        let secrets = HashMap::from([("FAKE_MCP_INSTRUCTIONS".to_string(), "s3cret".to_string())]);
+        let installed = manager.install(&manifest, secrets, false).await.unwrap();
+        let install_dir = config.paths.mcp_dir.join("packaged");
+        assert_eq!(installed.version.as_deref(), Some("1.0.0"));
+        assert!(install_dir.join("server.py").exists());
+        assert!(install_dir.join("manifest.json").exists());
+        assert!(!install_dir.join("unrelated.txt").exists());
+        let record = manager.store.get("packaged").await.unwrap();
+        let stored = manager.store.secrets(record.id).await.unwrap();
+        assert_ne!(stored[0].1, "s3cret");
+
+        manager.start("packaged").await.unwrap();
+        let client = manager.client("packaged").await.unwrap();
+        let info = client.server_info().unwrap();
+        assert_eq!(info.instructions.as_deref(), Some("s3cret"));
+
+        let older = write_package(&temp_dir.path().join("v0"), "0.9");
+        assert!(manager
+            .upgrade("packaged", &older, HashMap::new())
+            .await
+            .is_err());
+        let newer = write_package(&temp_dir.path().join("v2"), "1.10.0");
+        let upgraded = manager
+            .upgrade("packaged", &newer, HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(upgraded.version.as_deref(), Some("1.10.0"));
+        assert_eq!(upgraded.status, ServerStatus::Running);
+
+        manager.uninstall("packaged").await.unwrap();
+        assert!(!install_dir.exists());
+        assert!(manager.list().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn copies_only_listed_files_within_budget() {
+        let temp_dir = tempdir().unwrap();
+        let manifest_path = write_package(&temp_dir.path().join("pkg"), "1.0.0");
+        let mut manifest = ServerManifest::load(&manifest_path).unwrap();
+        manifest.files = vec!["../escape".into()];
+        assert!(manifest.validate().is_err());
+
+        std::fs::create_dir_all(temp_dir.path().join("pkg/lib")).unwrap();
+        std::fs::write(temp_dir.path().join("pkg/lib/a.py"), "a").unwrap();
+        std::fs::write(temp_dir.path().join("pkg/lib/b.py"), "b").unwrap();
+        let files = vec!["server.py".to_string(), "lib".to_string()];
+        let target = temp_dir.path().join("installed");
+        let mut budget = CopyBudget {
+            files: 4,
+            bytes: u64::MAX,
+        };
+        copy_package(&manifest_path, &files, &target, &mut budget).unwrap();
+        assert!(target.join("lib/b.py").exists());
+        assert_eq!(budget.files, 0);
+
+        let mut budget = CopyBudget {
+            files: 3,
+            bytes: u64::MAX,
+        };
+        let target = temp_dir.path().join("too-many");
+        assert!(copy_package(&manifest_path, &files, &target, &mut budget).is_err());
+        let mut budget = CopyBudget {
+            files: MAX_PACKAGE_FILES,
+            bytes: 4,
+        };
+        let target = temp_dir.path().join("too-big");
+        assert!(copy_package(&manifest_path, &files, &target, &mut budget).is_err());
+        let mut budget = CopyBudget {
+            files: MAX_PACKAGE_FILES,
+            bytes: MAX_PACKAGE_BYTES,
+        };
+        let missing = vec!["absent.py".to_string()];
+        let target = temp_dir.path().join("missing");
+        assert!(copy_package(&manifest_path, &missing, &target, &mut budget).is_err());
+    }
+
+    #[test]
+    fn compares_versions_numerically() {
+        assert!(parse_version("1.10.0").unwrap() > parse_version("1.9.3").unwrap());
+        assert_eq!(
+            parse_version("2.0").unwrap(),
+            parse_version("v2.0.0").unwrap()
+        );
+        assert!(parse_version("one").is_err());
+    }
+}
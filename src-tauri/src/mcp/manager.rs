@@ -0,0 +1,658 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Child;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use super::client::{spawn_stdio, McpClient};
+use super::error::McpError;
+use super::http::{connect_sse, connect_streamable_http};
+use super::protocol::{GetPromptResult, Implementation, Prompt, Resource, ResourceContents, Tool};
+use super::store::{parse_url, McpStore, NewServer, ServerLaunch, ServerRecord, ServerStatus};
+use crate::config::service::SharedConfigService;
+use crate::services::native_tools::NATIVE_SERVER;
+
+/// Reconnect attempts after a remote server drops, waiting `RECONNECT_DELAY`, then twice as
+/// long, and so on.
+const RECONNECT_ATTEMPTS: u32 = 4;
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+
+pub type SharedMcpManager = Arc<McpManager>;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerSummary {
+    pub id: i64,
+    pub slug: String,
+    pub name: String,
+    pub version: Option<String>,
+    pub status: ServerStatus,
+    pub auto_start: bool,
+    pub launch: ServerLaunch,
+    pub install_path: Option<String>,
+    pub has_auth_header: bool,
+    pub server_info: Option<Implementation>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddMcpServerPayload {
+    pub slug: String,
+    pub name: String,
+    pub launch: ServerLaunch,
+    #[serde(default)]
+    pub auto_start: bool,
+    /// `Authorization` header value for remote servers, encrypted before it is stored.
+    #[serde(default)]
+    pub auth_header: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResource {
+    pub server: String,
+    #[serde(flatten)]
+    pub resource: Resource,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpPrompt {
+    pub server: String,
+    #[serde(flatten)]
+    pub prompt: Prompt,
+}
+
+pub(super) struct RunningServer {
+    client: Arc<McpClient>,
+    child: Option<Child>,
+}
+
+/// Owns the connections to MCP servers registered in `mcp_servers` and keeps each row's
+/// `status` in step with the process behind it.
+pub struct McpManager {
+    pub(super) config: SharedConfigService,
+    pub(super) store: McpStore,
+    running: Mutex<HashMap<String, RunningServer>>,
+}
+
+impl McpManager {
+    /// Never fails so chat keeps working without MCP; stale statuses are only cosmetic.
+    pub async fn new(config: SharedConfigService) -> SharedMcpManager {
+        let store = McpStore::new(config.pool().clone());
+        if let Err(err) = store.reset_statuses().await {
+            warn!("unable to reset MCP server statuses: {err}");
+        }
+        Arc::new(Self {
+            config,
+            store,
+            running: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub async fn list(&self) -> Result<Vec<McpServerSummary>, McpError> {
+        let records = self.store.list().await?;
+        let running = self.running.lock().await;
+        Ok(records
+            .into_iter()
+            .map(|record| {
+                let server = running.get(&record.slug);
+                summarize(record, server)
+            })
+            .collect())
+    }
+
+    pub async fn add(&self, payload: AddMcpServerPayload) -> Result<McpServerSummary, McpError> {
+        validate_slug(&payload.slug)?;
+        payload.launch.validate()?;
+        if payload.auth_header.is_some() && !payload.launch.is_remote() {
+            return Err(McpError::InvalidDefinition(
+                "auth headers only apply to remote servers".into(),
+            ));
+        }
+        let auth_header = payload
+            .auth_header
+            .filter(|header| !header.trim().is_empty())
+            .map(|header| self.config.crypto().encrypt(&header))
+            .transpose()?;
+        let record = self
+            .store
+            .insert(NewServer {
+                slug: payload.slug,
+                name: payload.name,
+                launch: payload.launch,
+                auto_start: payload.auto_start,
+                auth_header,
+                version: None,
+                install_path: None,
+            })
+            .await?;
+        Ok(summarize(record, None))
+    }
+
+    pub async fn remove(&self, slug: &str) -> Result<(), McpError> {
+        self.shutdown(slug).await;
+        self.store.delete(slug).await
+    }
+
+    /// Spawns the server and completes the `initialize` handshake. Starting a server that is
+    /// already running is a no-op. The handshake runs unlocked so a slow server does not
+    /// hold up the others.
+    pub async fn start(self: &Arc<Self>, slug: &str) -> Result<McpServerSummary, McpError> {
+        let record = self.store.get(slug).await?;
+        if let Some(server) = self.running.lock().await.get(slug) {
+            return Ok(summarize(record, Some(server)));
+        }
+        self.store.set_status(slug, ServerStatus::Starting).await?;
+        let server = match self.connect(&record).await {
+            Ok(server) => server,
+            Err(err) => {
+                warn!(server = slug, "MCP server failed to start: {err}");
+                if !self.is_running(slug).await {
+                    self.store.set_status(slug, ServerStatus::Error).await?;
+                }
+                return Err(err);
+            }
+        };
+        let mut running = self.running.lock().await;
+        if let Some(existing) = running.get(slug) {
+            // A concurrent start got there first; keep its connection.
+            let summary = summarize(self.store.get(slug).await?, Some(existing));
+            drop(running);
+            discard(slug, server).await;
+            return Ok(summary);
+        }
+        self.store.set_status(slug, ServerStatus::Running).await?;
+        self.watch_exit(slug.to_string(), &server.client);
+        info!(server = slug, "MCP server running");
+        let summary = summarize(self.store.get(slug).await?, Some(&server));
+        running.insert(slug.to_string(), server);
+        Ok(summary)
+    }
+
+    pub async fn stop(&self, slug: &str) -> Result<McpServerSummary, McpError> {
+        self.shutdown(slug).await;
+        self.store.set_status(slug, ServerStatus::Inactive).await?;
+        Ok(summarize(self.store.get(slug).await?, None))
+    }
+
+    /// Starts every server flagged `auto_start`, logging rather than propagating failures so
+    /// one broken server does not block the others.
+    pub async fn start_auto_servers(self: &Arc<Self>) {
+        let records = match self.store.list().await {
+            Ok(records) => records,
+            Err(err) => {
+                warn!("unable to load MCP servers: {err}");
+                return;
+            }
+        };
+        for record in records.into_iter().filter(|record| record.auto_start) {
+            if let Err(err) = self.start(&record.slug).await {
+                warn!(server = record.slug.as_str(), "auto-start failed: {err}");
+            }
+        }
+    }
+
+    pub async fn is_running(&self, slug: &str) -> bool {
+        self.running.lock().await.contains_key(slug)
+    }
+
+    pub async fn client(&self, slug: &str) -> Result<Arc<McpClient>, McpError> {
+        self.running
+            .lock()
+            .await
+            .get(slug)
+            .map(|server| server.client.clone())
+            .ok_or_else(|| McpError::NotRunning(slug.to_string()))
+    }
+
+    /// Tools offered by every running server that advertises the `tools` capability.
+    pub async fn list_tools(&self) -> Vec<(String, Tool)> {
+        let mut tools = Vec::new();
+        for (slug, client) in self.clients_with("tools").await {
+            match client.list_tools().await {
+                Ok(listed) => tools.extend(listed.into_iter().map(|tool| (slug.clone(), tool))),
+                Err(err) => warn!(server = slug.as_str(), "tools/list failed: {err}"),
+            }
+        }
+        tools
+    }
+
+    pub async fn list_resources(&self) -> Vec<McpResource> {
+        let mut resources = Vec::new();
+        for (slug, client) in self.clients_with("resources").await {
+            match client.list_resources().await {
+                Ok(listed) => resources.extend(listed.into_iter().map(|resource| McpResource {
+                    server: slug.clone(),
+                    resource,
+                })),
+                Err(err) => warn!(server = slug.as_str(), "resources/list failed: {err}"),
+            }
+        }
+        resources
+    }
+
+    pub async fn read_resource(
+        &self,
+        slug: &str,
+        uri: &str,
+    ) -> Result<Vec<ResourceContents>, McpError> {
+        Ok(self.client(slug).await?.read_resource(uri).await?.contents)
+    }
+
+    pub async fn list_prompts(&self) -> Vec<McpPrompt> {
+        let mut prompts = Vec::new();
+        for (slug, client) in self.clients_with("prompts").await {
+            match client.list_prompts().await {
+                Ok(listed) => prompts.extend(listed.into_iter().map(|prompt| McpPrompt {
+                    server: slug.clone(),
+                    prompt,
+                })),
+                Err(err) => warn!(server = slug.as_str(), "prompts/list failed: {err}"),
+            }
+        }
+        prompts
+    }
+
+    pub async fn get_prompt(
+        &self,
+        slug: &str,
+        name: &str,
+        arguments: &BTreeMap<String, String>,
+    ) -> Result<GetPromptResult, McpError> {
+        self.client(slug).await?.get_prompt(name, arguments).await
+    }
+
+    /// Running servers that advertised `capability` during initialization, ordered by slug.
+    async fn clients_with(&self, capability: &str) -> Vec<(String, Arc<McpClient>)> {
+        let mut clients: Vec<(String, Arc<McpClient>)> = self
+            .running
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, server)| {
+                server
+                    .client
+                    .server_info()
+                    .is_some_and(|info| info.capabilities.get(capability).is_some())
+            })
+            .map(|(slug, server)| (slug.clone(), server.client.clone()))
+            .collect();
+        clients.sort_by(|a, b| a.0.cmp(&b.0));
+        clients
+    }
+
+    async fn connect(&self, record: &ServerRecord) -> Result<RunningServer, McpError> {
+        let auth = record
+            .auth_header
+            .as_deref()
+            .map(|cipher| self.config.crypto().decrypt(cipher))
+            .transpose()?;
+        let (client, child) = match &record.launch {
+            ServerLaunch::Stdio { command, args, env } => {
+                let mut env = env.clone();
+                for (name, cipher) in self.store.secrets(record.id).await? {
+                    env.insert(name, self.config.crypto().decrypt(&cipher)?);
+                }
+                let cwd = record.install_path.as_deref().map(std::path::Path::new);
+                let (client, child) = spawn_stdio(command, args, &env, cwd)?;
+                (client, Some(child))
+            }
+            ServerLaunch::Http { url } => (
+                connect_streamable_http(parse_url(url)?, auth.as_deref())?,
+                None,
+            ),
+            ServerLaunch::Sse { url } => (connect_sse(parse_url(url)?, auth.as_deref())?, None),
+        };
+        let mut server = RunningServer {
+            client: Arc::new(client),
+            child,
+        };
+        if let Err(err) = server.client.initialize().await {
+            if let Some(child) = server.child.as_mut() {
+                let _ = child.kill().await;
+            }
+            return Err(err);
+        }
+        Ok(server)
+    }
+
+    async fn shutdown(&self, slug: &str) {
+        let Some(server) = self.running.lock().await.remove(slug) else {
+            return;
+        };
+        discard(slug, server).await;
+    }
+
+    /// Reacts to a connection dropping without [`stop`](Self::stop): remote servers are
+    /// reconnected with backoff, anything else (or a server that stays unreachable) is
+    /// flagged as errored.
+    fn watch_exit(self: &Arc<Self>, slug: String, client: &Arc<McpClient>) {
+        let manager = Arc::downgrade(self);
+        let closed = client.closed();
+        let client = Arc::downgrade(client);
+        tokio::spawn(async move {
+            closed.await;
+            let Some(manager) = manager.upgrade() else {
+                return;
+            };
+            let mut running = manager.running.lock().await;
+            let is_current = running
+                .get(&slug)
+                .is_some_and(|server| Weak::ptr_eq(&Arc::downgrade(&server.client), &client));
+            if !is_current {
+                return;
+            }
+            running.remove(&slug);
+            drop(running);
+            warn!(server = slug.as_str(), "MCP server connection lost");
+            if let Err(err) = manager.reconnect(&slug).await {
+                warn!(server = slug.as_str(), "MCP server unavailable: {err}");
+                if let Err(err) = manager.store.set_status(&slug, ServerStatus::Error).await {
+                    warn!("failed to record MCP server exit: {err}");
+                }
+            }
+        });
+    }
+
+    async fn reconnect(self: &Arc<Self>, slug: &str) -> Result<(), McpError> {
+        let record = self.store.get(slug).await?;
+        if !record.launch.is_remote() {
+            return Err(McpError::Disconnected);
+        }
+        self.store.set_status(slug, ServerStatus::Starting).await?;
+        let mut delay = RECONNECT_DELAY;
+        let mut last_error = McpError::Disconnected;
+        for attempt in 1..=RECONNECT_ATTEMPTS {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            let server = match self.connect(&record).await {
+                Ok(server) => server,
+                Err(err) => {
+                    warn!(server = slug, attempt, "MCP reconnect failed: {err}");
+                    last_error = err;
+                    continue;
+                }
+            };
+            let mut running = self.running.lock().await;
+            // A stop or restart while we were reconnecting wins.
+            if running.contains_key(slug)
+                || self.store.get(slug).await?.status != ServerStatus::Starting
+            {
+                drop(running);
+                discard(slug, server).await;
+                return Ok(());
+            }
+            self.store.set_status(slug, ServerStatus::Running).await?;
+            self.watch_exit(slug.to_string(), &server.client);
+            running.insert(slug.to_string(), server);
+            info!(server = slug, "MCP server reconnected");
+            return Ok(());
+        }
+        Err(last_error)
+    }
+}
+
+/// Stops the process behind a connection that is not (or no longer) kept.
+async fn discard(slug: &str, mut server: RunningServer) {
+    if let Some(child) = server.child.as_mut() {
+        if let Err(err) = child.kill().await {
+            warn!(server = slug, "failed to stop MCP server: {err}");
+        }
+    }
+}
+
+pub(super) fn summarize(record: ServerRecord, running: Option<&RunningServer>) -> McpServerSummary {
+    McpServerSummary {
+        id: record.id,
+        slug: record.slug,
+        name: record.name,
+        version: record.version,
+        status: record.status,
+        auto_start: record.auto_start,
+        launch: record.launch,
+        install_path: record.install_path,
+        has_auth_header: record.auth_header.is_some(),
+        server_info: running
+            .and_then(|server| server.client.server_info())
+            .map(|info| info.server_info.clone()),
+    }
+}
+
+pub(super) fn validate_slug(slug: &str) -> Result<(), McpError> {
+    let valid = !slug.is_empty()
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if slug == NATIVE_SERVER {
+        Err(McpError::InvalidDefinition(format!(
+            "slug `{slug}` is reserved for built-in tools"
+        )))
+    } else if valid {
+        Ok(())
+    } else {
+        Err(McpError::InvalidDefinition(format!(
+            "slug `{slug}` must be lowercase letters, digits, `-` or `_`"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use tokio::process::Command;
+
+    use super::*;
+    use crate::config::{paths::ConfigPaths, service::ConfigService};
+
+    fn fake_server() -> ServerLaunch {
+        ServerLaunch::Stdio {
+            command: "python3".into(),
+            args: vec![concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/fake_mcp_server.py"
+            )
+            .into()],
+            env: Default::default(),
+        }
+    }
+
+    /// Launches the HTTP mock server and returns it with its base URL.
+    async fn http_server(args: &[&str]) -> (Child, String) {
+        let mut child = Command::new("python3")
+            .arg(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/fake_mcp_http_server.py"
+            ))
+            .args(args)
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let port = BufReader::new(stdout)
+            .lines()
+            .next_line()
+            .await
+            .unwrap()
+            .unwrap();
+        (child, format!("http://127.0.0.1:{port}"))
+    }
+
+    async fn manager(dir: &std::path::Path) -> SharedMcpManager {
+        let config = ConfigService::with_paths(ConfigPaths::from_base_dir(dir).unwrap())
+            .await
+            .unwrap();
+        McpManager::new(config).await
+    }
+
+    #[tokio::test]
+    async fn starts_and_stops_stdio_server() {
+        let temp_dir = tempdir().unwrap();
+        let manager = manager(temp_dir.path()).await;
+        manager
+            .add(AddMcpServerPayload {
+                slug: "fake".into(),
+                name: "Fake".into(),
+                launch: fake_server(),
+                auto_start: false,
+                auth_header: None,
+            })
+            .await
+            .unwrap();
+
+        let started = manager.start("fake").await.unwrap();
+        assert_eq!(started.status, ServerStatus::Running);
+        assert_eq!(started.server_info.unwrap().name, "fake-mcp");
+
+        let stopped = manager.stop("fake").await.unwrap();
+        assert_eq!(stopped.status, ServerStatus::Inactive);
+        assert!(manager.client("fake").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn auto_starts_flagged_servers_and_detects_exit() {
+        let temp_dir = tempdir().unwrap();
+        let manager = manager(temp_dir.path()).await;
+        manager
+            .add(AddMcpServerPayload {
+                slug: "fake".into(),
+                name: "Fake".into(),
+                launch: fake_server(),
+                auto_start: true,
+                auth_header: None,
+            })
+            .await
+            .unwrap();
+        manager.start_auto_servers().await;
+        let client = manager.client("fake").await.unwrap();
+
+        let _ = client.request("exit", None).await;
+        client.closed().await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let servers = manager.list().await.unwrap();
+        assert_eq!(servers[0].status, ServerStatus::Error);
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_definitions() {
+        let temp_dir = tempdir().unwrap();
+        let manager = manager(temp_dir.path()).await;
+        let err = manager
+            .add(AddMcpServerPayload {
+                slug: "Bad Slug".into(),
+                name: "Bad".into(),
+                launch: fake_server(),
+                auto_start: false,
+                auth_header: None,
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, McpError::InvalidDefinition(_)));
+    }
+
+    #[tokio::test]
+    async fn connects_over_streamable_http_with_auth_header() {
+        let temp_dir = tempdir().unwrap();
+        let manager = manager(temp_dir.path()).await;
+        let (_server, base) = http_server(&["--token", "s3cret"]).await;
+        let summary = manager
+            .add(AddMcpServerPayload {
+                slug: "remote".into(),
+                name: "Remote".into(),
+                launch: ServerLaunch::Http {
+                    url: format!("{base}/mcp"),
+                },
+                auto_start: false,
+                auth_header: Some("Bearer s3cret".into()),
+            })
+            .await
+            .unwrap();
+        assert!(summary.has_auth_header);
+        let stored = manager.store.get("remote").await.unwrap();
+        assert_ne!(stored.auth_header.as_deref(), Some("Bearer s3cret"));
+
+        manager.start("remote").await.unwrap();
+        let client = manager.client("remote").await.unwrap();
+        let result = client
+            .call_tool("echo", serde_json::json!({ "text": "over http" }))
+            .await
+            .unwrap();
+        assert_eq!(result.to_text(), "echo: over http");
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_auth_header() {
+        let temp_dir = tempdir().unwrap();
+        let manager = manager(temp_dir.path()).await;
+        let (_server, base) = http_server(&["--token", "s3cret"]).await;
+        manager
+            .add(AddMcpServerPayload {
+                slug: "remote".into(),
+                name: "Remote".into(),
+                launch: ServerLaunch::Http {
+                    url: format!("{base}/mcp"),
+                },
+                auto_start: false,
+                auth_header: None,
+            })
+            .await
+            .unwrap();
+        let err = manager.start("remote").await.unwrap_err();
+        assert!(matches!(err, McpError::Transport(_)));
+    }
+
+    #[tokio::test]
+    async fn reconnects_after_session_loss() {
+        let temp_dir = tempdir().unwrap();
+        let manager = manager(temp_dir.path()).await;
+        let (_server, base) = http_server(&[]).await;
+        for (slug, launch) in [
+            (
+                "streamable",
+                ServerLaunch::Http {
+                    url: format!("{base}/mcp"),
+                },
+            ),
+            (
+                "legacy",
+                ServerLaunch::Sse {
+                    url: format!("{base}/sse"),
+                },
+            ),
+        ] {
+            manager
+                .add(AddMcpServerPayload {
+                    slug: slug.into(),
+                    name: slug.into(),
+                    launch,
+                    auto_start: false,
+                    auth_header: None,
+                })
+                .await
+                .unwrap();
+            manager.start(slug).await.unwrap();
+            let client = manager.client(slug).await.unwrap();
+            client.request("reset", None).await.unwrap();
+            // The next request finds the session gone and closes the connection.
+            let _ = client.request("ping", None).await;
+            client.closed().await;
+
+            let mut reconnected = None;
+            for _ in 0..50 {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                if let Ok(fresh) = manager.client(slug).await {
+                    reconnected = Some(fresh);
+                    break;
+                }
+            }
+            let fresh = reconnected.expect("server did not reconnect");
+            assert!(!Arc::ptr_eq(&fresh, &client));
+            fresh.request("ping", None).await.unwrap();
+        }
+    }
+}
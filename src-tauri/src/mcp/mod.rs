@@ -0,0 +1,9 @@
+pub mod client;
+pub mod error;
+pub mod http;
+pub mod installer;
+pub mod manager;
+pub mod protocol;
+pub mod store;
+
+pub use error::McpError;
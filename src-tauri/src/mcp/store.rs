@@ -0,0 +1,295 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+
+use super::error::McpError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ServerStatus {
+    Inactive,
+    Starting,
+    Running,
+    Error,
+}
+
+impl ServerStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Inactive => "inactive",
+            Self::Starting => "starting",
+            Self::Running => "running",
+            Self::Error => "error",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "starting" => Self::Starting,
+            "running" => Self::Running,
+            "error" => Self::Error,
+            _ => Self::Inactive,
+        }
+    }
+}
+
+/// How to reach a server, stored as JSON in `mcp_servers.manifest`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "transport")]
+pub enum ServerLaunch {
+    #[serde(rename_all = "camelCase")]
+    Stdio {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        env: BTreeMap<String, String>,
+    },
+    /// Streamable HTTP: one endpoint for every message.
+    Http { url: String },
+    /// Legacy HTTP+SSE: an event stream plus a POST endpoint it announces.
+    Sse { url: String },
+}
+
+impl ServerLaunch {
+    pub fn validate(&self) -> Result<(), McpError> {
+        match self {
+            Self::Stdio { command, .. } if command.trim().is_empty() => Err(
+                McpError::InvalidDefinition("stdio servers need a command".into()),
+            ),
+            Self::Stdio { .. } => Ok(()),
+            Self::Http { url } | Self::Sse { url } => parse_url(url).map(drop),
+        }
+    }
+
+    pub fn is_remote(&self) -> bool {
+        !matches!(self, Self::Stdio { .. })
+    }
+}
+
+pub fn parse_url(url: &str) -> Result<reqwest::Url, McpError> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|err| McpError::InvalidDefinition(format!("invalid server url `{url}`: {err}")))?;
+    if matches!(parsed.scheme(), "http" | "https") {
+        Ok(parsed)
+    } else {
+        Err(McpError::InvalidDefinition(format!(
+            "server url `{url}` must use http or https"
+        )))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerRecord {
+    pub id: i64,
+    pub slug: String,
+    pub name: String,
+    pub version: Option<String>,
+    pub status: ServerStatus,
+    pub launch: ServerLaunch,
+    pub install_path: Option<String>,
+    pub auto_start: bool,
+    /// Encrypted `Authorization` header value for remote servers.
+    pub auth_header: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+struct ServerRow {
+    id: i64,
+    slug: String,
+    name: String,
+    version: Option<String>,
+    status: String,
+    manifest: Option<String>,
+    install_path: Option<String>,
+    auto_start: i64,
+    auth_header: Option<String>,
+}
+
+impl TryFrom<ServerRow> for ServerRecord {
+    type Error = McpError;
+
+    fn try_from(row: ServerRow) -> Result<Self, McpError> {
+        let manifest = row.manifest.ok_or_else(|| {
+            McpError::InvalidDefinition(format!("server `{}` has no manifest", row.slug))
+        })?;
+        Ok(Self {
+            id: row.id,
+            launch: serde_json::from_str(&manifest)?,
+            slug: row.slug,
+            name: row.name,
+            version: row.version,
+            status: ServerStatus::parse(&row.status),
+            install_path: row.install_path,
+            auto_start: row.auto_start == 1,
+            auth_header: row.auth_header,
+        })
+    }
+}
+
+pub struct NewServer {
+    pub slug: String,
+    pub name: String,
+    pub launch: ServerLaunch,
+    pub auto_start: bool,
+    pub auth_header: Option<String>,
+    pub version: Option<String>,
+    pub install_path: Option<String>,
+}
+
+/// Data access for the `mcp_servers` table.
+#[derive(Clone)]
+pub struct McpStore {
+    pool: SqlitePool,
+}
+
+impl McpStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self) -> Result<Vec<ServerRecord>, McpError> {
+        let rows = sqlx::query_as::<_, ServerRow>(
+            r#"
+        SELECT id, slug, name, version, status, manifest, install_path, auto_start, auth_header
+        FROM mcp_servers
+        ORDER BY name
+      "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(TryInto::try_into).collect()
+    }
+
+    pub async fn get(&self, slug: &str) -> Result<ServerRecord, McpError> {
+        sqlx::query_as::<_, ServerRow>(
+            r#"
+        SELECT id, slug, name, version, status, manifest, install_path, auto_start, auth_header
+        FROM mcp_servers
+        WHERE slug = ?1
+      "#,
+        )
+        .bind(slug)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| McpError::UnknownServer(slug.to_string()))?
+        .try_into()
+    }
+
+    pub async fn insert(&self, server: NewServer) -> Result<ServerRecord, McpError> {
+        let manifest = serde_json::to_string(&server.launch)?;
+        let inserted = sqlx::query(
+            r#"
+        INSERT INTO mcp_servers (slug, name, manifest, auto_start, auth_header, version, install_path)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        ON CONFLICT(slug) DO NOTHING
+      "#,
+        )
+        .bind(&server.slug)
+        .bind(&server.name)
+        .bind(manifest)
+        .bind(if server.auto_start { 1 } else { 0 })
+        .bind(&server.auth_header)
+        .bind(&server.version)
+        .bind(&server.install_path)
+        .execute(&self.pool)
+        .await?;
+        if inserted.rows_affected() == 0 {
+            return Err(McpError::DuplicateServer(server.slug));
+        }
+        self.get(&server.slug).await
+    }
+
+    pub async fn delete(&self, slug: &str) -> Result<(), McpError> {
+        let deleted = sqlx::query("DELETE FROM mcp_servers WHERE slug = ?1")
+            .bind(slug)
+            .execute(&self.pool)
+            .await?;
+        if deleted.rows_affected() == 0 {
+            return Err(McpError::UnknownServer(slug.to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn set_status(&self, slug: &str, status: ServerStatus) -> Result<(), McpError> {
+        sqlx::query(
+            "UPDATE mcp_servers SET status = ?2, updated_at = CURRENT_TIMESTAMP WHERE slug = ?1",
+        )
+        .bind(slug)
+        .bind(status.as_str())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Records a new version of an installed server.
+    pub async fn update_install(
+        &self,
+        slug: &str,
+        name: &str,
+        launch: &ServerLaunch,
+        version: &str,
+        install_path: &str,
+    ) -> Result<(), McpError> {
+        sqlx::query(
+            r#"
+        UPDATE mcp_servers
+        SET name = ?2, manifest = ?3, version = ?4, install_path = ?5,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE slug = ?1
+      "#,
+        )
+        .bind(slug)
+        .bind(name)
+        .bind(serde_json::to_string(launch)?)
+        .bind(version)
+        .bind(install_path)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Encrypted secrets as `(name, value)` pairs.
+    pub async fn secrets(&self, server_id: i64) -> Result<Vec<(String, String)>, McpError> {
+        Ok(sqlx::query_as(
+            "SELECT name, value FROM mcp_server_secrets WHERE server_id = ?1 ORDER BY name",
+        )
+        .bind(server_id)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Replaces every stored secret of the server with `secrets` (already encrypted).
+    pub async fn replace_secrets(
+        &self,
+        server_id: i64,
+        secrets: &[(String, String)],
+    ) -> Result<(), McpError> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM mcp_server_secrets WHERE server_id = ?1")
+            .bind(server_id)
+            .execute(&mut *tx)
+            .await?;
+        for (name, value) in secrets {
+            sqlx::query(
+                "INSERT INTO mcp_server_secrets (server_id, name, value) VALUES (?1, ?2, ?3)",
+            )
+            .bind(server_id)
+            .bind(name)
+            .bind(value)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Statuses left over from a previous run are stale once the app restarts.
+    pub async fn reset_statuses(&self) -> Result<(), McpError> {
+        sqlx::query("UPDATE mcp_servers SET status = 'inactive' WHERE status != 'inactive'")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
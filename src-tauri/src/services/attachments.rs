@@ -0,0 +1,532 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{FromRow, SqlitePool};
+use thiserror::Error;
+
+use crate::config::service::ConfigService;
+use crate::services::documents::hex_digest;
+
+const MAX_ATTACHMENT_BYTES: usize = 20 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum AttachmentError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("attachment data is not valid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("attachment needs a path, data or the hash of a stored attachment")]
+    MissingSource,
+    #[error("no stored attachment with hash `{0}`")]
+    NotFound(String),
+    #[error("attachment `{0}` is larger than {max} MiB", max = MAX_ATTACHMENT_BYTES / (1024 * 1024))]
+    TooLarge(String),
+    #[error("attachment `{name}` ({mime_type}) is neither an image, a PDF nor text")]
+    Unsupported { name: String, mime_type: String },
+}
+
+/// An attachment as sent with a [`ChatRequest`](crate::services::chat::ChatRequest):
+/// a local path, base64 `data` (a `data:` URL also works) with a name, or the `hash` of
+/// one already stored.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentInput {
+    pub path: Option<String>,
+    pub data: Option<String>,
+    pub hash: Option<String>,
+    pub name: Option<String>,
+    /// Guessed from the name when missing.
+    pub mime_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    /// Hex SHA-256 of the contents.
+    pub hash: String,
+    pub name: String,
+    pub mime_type: String,
+    pub size: i64,
+}
+
+impl Attachment {
+    pub fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+}
+
+/// One piece of a message's content. Binary parts carry base64 data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentPart {
+    Text(String),
+    Image {
+        mime_type: String,
+        data: String,
+    },
+    File {
+        name: String,
+        mime_type: String,
+        data: String,
+    },
+}
+
+/// The provider wire formats content parts can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartFormat {
+    /// Chat completions, also spoken by OpenRouter.
+    OpenAi,
+    Anthropic,
+    Gemini,
+}
+
+impl PartFormat {
+    pub fn for_provider(provider: &str) -> Self {
+        match provider {
+            "anthropic" => Self::Anthropic,
+            "google" | "gemini" => Self::Gemini,
+            _ => Self::OpenAi,
+        }
+    }
+}
+
+impl ContentPart {
+    pub fn to_json(&self, format: PartFormat) -> Value {
+        match (self, format) {
+            (Self::Text(text), PartFormat::Gemini) => json!({ "text": text }),
+            (Self::Text(text), _) => json!({ "type": "text", "text": text }),
+            (Self::Image { mime_type, data }, PartFormat::OpenAi) => json!({
+                "type": "image_url",
+                "image_url": { "url": format!("data:{mime_type};base64,{data}") },
+            }),
+            (Self::Image { mime_type, data }, PartFormat::Anthropic) => json!({
+                "type": "image",
+                "source": { "type": "base64", "media_type": mime_type, "data": data },
+            }),
+            (
+                Self::File {
+                    name,
+                    mime_type,
+                    data,
+                },
+                PartFormat::OpenAi,
+            ) => json!({
+                "type": "file",
+                "file": {
+                    "filename": name,
+                    "file_data": format!("data:{mime_type};base64,{data}"),
+                },
+            }),
+            (
+                Self::File {
+                    name,
+                    mime_type,
+                    data,
+                },
+                PartFormat::Anthropic,
+            ) => json!({
+                "type": "document",
+                "title": name,
+                "source": { "type": "base64", "media_type": mime_type, "data": data },
+            }),
+            (
+                Self::Image { mime_type, data }
+                | Self::File {
+                    mime_type, data, ..
+                },
+                PartFormat::Gemini,
+            ) => json!({ "inline_data": { "mime_type": mime_type, "data": data } }),
+        }
+    }
+}
+
+/// Content in `format`. A lone text part stays a plain string, which every provider
+/// accepts and tool/system messages require.
+pub fn content_json(parts: &[ContentPart], format: PartFormat) -> Value {
+    match parts {
+        [] => Value::Null,
+        [ContentPart::Text(text)] if format != PartFormat::Gemini => Value::String(text.clone()),
+        parts => Value::Array(parts.iter().map(|part| part.to_json(format)).collect()),
+    }
+}
+
+/// Content-addressed attachment files plus the `attachments` and `message_attachments`
+/// tables.
+#[derive(Clone)]
+pub struct AttachmentStore {
+    pool: SqlitePool,
+    dir: PathBuf,
+}
+
+impl AttachmentStore {
+    pub fn new(config: &ConfigService) -> Self {
+        Self {
+            pool: config.pool().clone(),
+            dir: config.paths.attachments_dir.clone(),
+        }
+    }
+
+    /// Reads or decodes `input`, stores it once per content hash and checks it can be sent
+    /// to a model.
+    pub async fn save(&self, input: &AttachmentInput) -> Result<Attachment, AttachmentError> {
+        if let Some(hash) = &input.hash {
+            let (mime_type, size) = sqlx::query_as::<_, (String, i64)>(
+                "SELECT mime_type, size FROM attachments WHERE hash = ?1",
+            )
+            .bind(hash)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AttachmentError::NotFound(hash.clone()))?;
+            return Ok(Attachment {
+                hash: hash.clone(),
+                name: input.name.clone().unwrap_or_else(|| "attachment".into()),
+                mime_type,
+                size,
+            });
+        }
+        let (bytes, name) = match (&input.path, &input.data) {
+            (Some(path), _) => {
+                let name = input.name.clone().unwrap_or_else(|| file_name(path));
+                let size = tokio::fs::metadata(path).await?.len();
+                if size > MAX_ATTACHMENT_BYTES as u64 {
+                    return Err(AttachmentError::TooLarge(name));
+                }
+                (tokio::fs::read(path).await?, name)
+            }
+            (None, Some(data)) => {
+                let name = input.name.clone().unwrap_or_else(|| "attachment".into());
+                let encoded = data
+                    .split_once(";base64,")
+                    .map_or(data.as_str(), |(_, rest)| rest);
+                if encoded.len() / 4 * 3 > MAX_ATTACHMENT_BYTES {
+                    return Err(AttachmentError::TooLarge(name));
+                }
+                (BASE64.decode(encoded.trim())?, name)
+            }
+            (None, None) => return Err(AttachmentError::MissingSource),
+        };
+        let mime_type = input
+            .mime_type
+            .clone()
+            .or_else(|| data_url_mime(input.data.as_deref()))
+            .unwrap_or_else(|| guess_mime(&name).to_string());
+        let attachment = Attachment {
+            hash: hex_digest(&bytes),
+            name,
+            mime_type,
+            size: bytes.len() as i64,
+        };
+        // Reject early rather than failing on every later turn of the conversation.
+        part_for(&attachment, &bytes)?;
+
+        let path = self.dir.join(&attachment.hash);
+        if !tokio::fs::try_exists(&path).await? {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            // Write then rename so a crash never leaves a truncated file under the hash.
+            let partial = self.dir.join(format!("{}.partial", attachment.hash));
+            tokio::fs::write(&partial, &bytes).await?;
+            tokio::fs::rename(&partial, &path).await?;
+        }
+        sqlx::query(
+            "INSERT OR IGNORE INTO attachments (hash, mime_type, size) VALUES (?1, ?2, ?3)",
+        )
+        .bind(&attachment.hash)
+        .bind(&attachment.mime_type)
+        .bind(attachment.size)
+        .execute(&self.pool)
+        .await?;
+        Ok(attachment)
+    }
+
+    pub async fn link(
+        &self,
+        message_id: i64,
+        attachments: &[Attachment],
+    ) -> Result<(), AttachmentError> {
+        let mut tx = self.pool.begin().await?;
+        for (position, attachment) in attachments.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO message_attachments (message_id, position, hash, name) VALUES (?1, ?2, ?3, ?4)",
+            )
+            .bind(message_id)
+            .bind(position as i64)
+            .bind(&attachment.hash)
+            .bind(&attachment.name)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn for_message(&self, message_id: i64) -> Result<Vec<Attachment>, AttachmentError> {
+        Ok(sqlx::query_as::<_, Attachment>(
+            r#"
+        SELECT ma.hash, ma.name, a.mime_type, a.size
+        FROM message_attachments ma
+        JOIN attachments a ON a.hash = ma.hash
+        WHERE ma.message_id = ?1
+        ORDER BY ma.position
+      "#,
+        )
+        .bind(message_id)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Attachments of every message in the conversation, keyed by message id.
+    pub async fn for_conversation(
+        &self,
+        conversation_id: &str,
+    ) -> Result<HashMap<i64, Vec<Attachment>>, AttachmentError> {
+        #[derive(FromRow)]
+        struct Row {
+            message_id: i64,
+            #[sqlx(flatten)]
+            attachment: Attachment,
+        }
+        let rows = sqlx::query_as::<_, Row>(
+            r#"
+        SELECT ma.message_id, ma.hash, ma.name, a.mime_type, a.size
+        FROM message_attachments ma
+        JOIN attachments a ON a.hash = ma.hash
+        JOIN messages m ON m.id = ma.message_id
+        WHERE m.conversation_id = ?1
+        ORDER BY ma.message_id, ma.position
+      "#,
+        )
+        .bind(conversation_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut by_message: HashMap<i64, Vec<Attachment>> = HashMap::new();
+        for row in rows {
+            by_message
+                .entry(row.message_id)
+                .or_default()
+                .push(row.attachment);
+        }
+        Ok(by_message)
+    }
+
+    /// Loads a stored attachment as the content part sent to the model.
+    pub async fn part(&self, attachment: &Attachment) -> Result<ContentPart, AttachmentError> {
+        let bytes = tokio::fs::read(self.dir.join(&attachment.hash)).await?;
+        part_for(attachment, &bytes)
+    }
+}
+
+/// Images and PDFs go to the model as they are; other files must be UTF-8 text and are
+/// inlined with their name, since providers only take binary documents as PDF.
+fn part_for(attachment: &Attachment, bytes: &[u8]) -> Result<ContentPart, AttachmentError> {
+    if attachment.is_image() {
+        return Ok(ContentPart::Image {
+            mime_type: attachment.mime_type.clone(),
+            data: BASE64.encode(bytes),
+        });
+    }
+    if attachment.mime_type == "application/pdf" {
+        return Ok(ContentPart::File {
+            name: attachment.name.clone(),
+            mime_type: attachment.mime_type.clone(),
+            data: BASE64.encode(bytes),
+        });
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Ok(ContentPart::Text(format!(
+            "Attached file `{}`:\n{text}",
+            attachment.name
+        ))),
+        Err(_) => Err(AttachmentError::Unsupported {
+            name: attachment.name.clone(),
+            mime_type: attachment.mime_type.clone(),
+        }),
+    }
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn data_url_mime(data: Option<&str>) -> Option<String> {
+    let header = data?.strip_prefix("data:")?.split_once(";base64,")?.0;
+    (!header.is_empty()).then(|| header.to_string())
+}
+
+fn guess_mime(name: &str) -> &'static str {
+    let extension = Path::new(name)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("pdf") => "application/pdf",
+        Some("md" | "markdown") => "text/markdown",
+        Some("csv") => "text/csv",
+        Some("json") => "application/json",
+        Some("txt" | "log") => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::config::paths::ConfigPaths;
+    use crate::services::conversations::{ConversationStore, NewMessage};
+
+    #[test]
+    fn maps_parts_to_each_provider_format() {
+        let image = ContentPart::Image {
+            mime_type: "image/png".into(),
+            data: "AAAA".into(),
+        };
+        assert_eq!(
+            image.to_json(PartFormat::OpenAi)["image_url"]["url"],
+            "data:image/png;base64,AAAA"
+        );
+        assert_eq!(
+            image.to_json(PartFormat::Anthropic)["source"]["media_type"],
+            "image/png"
+        );
+        assert_eq!(
+            image.to_json(PartFormat::Gemini)["inline_data"]["data"],
+            "AAAA"
+        );
+        let pdf = ContentPart::File {
+            name: "spec.pdf".into(),
+            mime_type: "application/pdf".into(),
+            data: "AAAA".into(),
+        };
+        assert_eq!(
+            pdf.to_json(PartFormat::OpenAi)["file"]["filename"],
+            "spec.pdf"
+        );
+        assert_eq!(pdf.to_json(PartFormat::Anthropic)["type"], "document");
+        assert_eq!(
+            pdf.to_json(PartFormat::Gemini)["inline_data"]["mime_type"],
+            "application/pdf"
+        );
+        assert_eq!(PartFormat::for_provider("anthropic"), PartFormat::Anthropic);
+        assert_eq!(PartFormat::for_provider("google"), PartFormat::Gemini);
+        assert_eq!(PartFormat::for_provider("openrouter"), PartFormat::OpenAi);
+
+        let text = ContentPart::Text("hi".into());
+        assert_eq!(
+            content_json(std::slice::from_ref(&text), PartFormat::OpenAi),
+            json!("hi")
+        );
+        assert_eq!(
+            content_json(&[text.clone(), image], PartFormat::OpenAi)[0],
+            json!({ "type": "text", "text": "hi" })
+        );
+        assert_eq!(content_json(&[], PartFormat::OpenAi), Value::Null);
+        assert_eq!(
+            content_json(&[text], PartFormat::Gemini),
+            json!([{ "text": "hi" }])
+        );
+    }
+
+    #[tokio::test]
+    async fn stores_attachments_once_and_links_them_to_messages() {
+        let temp_dir = tempdir().unwrap();
+        let config =
+            ConfigService::with_paths(ConfigPaths::from_base_dir(temp_dir.path()).unwrap())
+                .await
+                .unwrap();
+        let store = AttachmentStore::new(&config);
+        let png = temp_dir.path().join("chart.png");
+        std::fs::write(&png, [0x89, b'P', b'N', b'G']).unwrap();
+
+        let from_path = store
+            .save(&AttachmentInput {
+                path: Some(png.display().to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(from_path.name, "chart.png");
+        assert_eq!(from_path.mime_type, "image/png");
+        let from_bytes = store
+            .save(&AttachmentInput {
+                data: Some(format!(
+                    "data:image/png;base64,{}",
+                    BASE64.encode([0x89, b'P', b'N', b'G'])
+                )),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(from_bytes.hash, from_path.hash);
+        assert_eq!(from_bytes.mime_type, "image/png");
+        assert_eq!(
+            std::fs::read_dir(&config.paths.attachments_dir)
+                .unwrap()
+                .count(),
+            1
+        );
+
+        let notes = store
+            .save(&AttachmentInput {
+                data: Some(BASE64.encode("line one")),
+                name: Some("notes.txt".into()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(matches!(
+            store
+                .save(&AttachmentInput {
+                    data: Some(BASE64.encode([0xff, 0xfe])),
+                    name: Some("blob.bin".into()),
+                    ..Default::default()
+                })
+                .await,
+            Err(AttachmentError::Unsupported { .. })
+        ));
+        assert!(matches!(
+            store.save(&AttachmentInput::default()).await,
+            Err(AttachmentError::MissingSource)
+        ));
+
+        let conversations = ConversationStore::new(config.pool().clone());
+        conversations.ensure("c1").await.unwrap();
+        let message_id = conversations
+            .append(
+                "c1",
+                NewMessage {
+                    role: "user",
+                    content: "what does this show?",
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        store
+            .link(message_id, &[from_path.clone(), notes])
+            .await
+            .unwrap();
+        let linked = store.for_conversation("c1").await.unwrap();
+        let attachments = &linked[&message_id];
+        assert_eq!(attachments.len(), 2);
+        assert!(matches!(
+            store.part(&attachments[0]).await.unwrap(),
+            ContentPart::Image { .. }
+        ));
+        assert_eq!(
+            store.part(&attachments[1]).await.unwrap(),
+            ContentPart::Text("Attached file `notes.txt`:\nline one".into())
+        );
+    }
+}
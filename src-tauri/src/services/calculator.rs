@@ -0,0 +1,217 @@
+use std::f64::consts;
+
+const MAX_DEPTH: usize = 64;
+
+/// Evaluates an arithmetic expression for the built-in `calculate` tool. Supports `+ - * / %`,
+/// right-associative `^`, parentheses, `pi`, `e` and single-argument functions such as
+/// `sqrt` and `sin`.
+pub fn evaluate(expression: &str) -> Result<f64, String> {
+    let mut parser = Parser {
+        chars: expression.chars().filter(|c| !c.is_whitespace()).collect(),
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.expr()?;
+    if let Some(c) = parser.peek() {
+        return Err(format!("unexpected `{c}` at position {}", parser.pos + 1));
+    }
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err("result is not a finite number".into())
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Guards every recursive descent; the input comes from the model.
+    fn enter(&mut self) -> Result<(), String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err("expression is nested too deeply".into());
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self) -> Result<f64, String> {
+        self.enter()?;
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                break;
+            }
+        }
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value *= self.unary()?;
+            } else if self.eat('/') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    return Err("division by zero".into());
+                }
+                value /= divisor;
+            } else if self.eat('%') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    return Err("division by zero".into());
+                }
+                value %= divisor;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Prefix signs are read in a loop so a long run of them cannot exhaust the stack.
+    fn unary(&mut self) -> Result<f64, String> {
+        let mut negate = false;
+        loop {
+            if self.eat('-') {
+                negate = !negate;
+            } else if !self.eat('+') {
+                break;
+            }
+        }
+        let value = self.power()?;
+        Ok(if negate { -value } else { value })
+    }
+
+    fn power(&mut self) -> Result<f64, String> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            // Right-associative: 2^3^2 = 2^9.
+            self.enter()?;
+            let exponent = self.unary()?;
+            self.depth -= 1;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<f64, String> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let value = self.expr()?;
+                if !self.eat(')') {
+                    return Err("missing `)`".into());
+                }
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => self.identifier(),
+            Some(c) => Err(format!("unexpected `{c}` at position {}", self.pos + 1)),
+            None => Err("unexpected end of expression".into()),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.pos += 1;
+        }
+        if matches!(self.peek(), Some('e' | 'E'))
+            && self
+                .chars
+                .get(self.pos + 1)
+                .is_some_and(|c| c.is_ascii_digit() || *c == '-' || *c == '+')
+        {
+            self.pos += 2;
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.pos += 1;
+            }
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse().map_err(|_| format!("invalid number `{text}`"))
+    }
+
+    fn identifier(&mut self) -> Result<f64, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric()) {
+            self.pos += 1;
+        }
+        let name: String = self.chars[start..self.pos].iter().collect();
+        match name.as_str() {
+            "pi" => return Ok(consts::PI),
+            "e" => return Ok(consts::E),
+            _ => {}
+        }
+        let function: fn(f64) -> f64 = match name.as_str() {
+            "sqrt" => f64::sqrt,
+            "abs" => f64::abs,
+            "ln" => f64::ln,
+            "log" | "log10" => f64::log10,
+            "exp" => f64::exp,
+            "sin" => f64::sin,
+            "cos" => f64::cos,
+            "tan" => f64::tan,
+            "floor" => f64::floor,
+            "ceil" => f64::ceil,
+            "round" => f64::round,
+            _ => return Err(format!("unknown function or constant `{name}`")),
+        };
+        if !self.eat('(') {
+            return Err(format!("`{name}` needs parentheses"));
+        }
+        let argument = self.expr()?;
+        if !self.eat(')') {
+            return Err("missing `)`".into());
+        }
+        Ok(function(argument))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn respects_precedence_and_associativity() {
+        assert_eq!(evaluate("1 + 2 * 3").unwrap(), 7.0);
+        assert_eq!(evaluate("(1 + 2) * 3").unwrap(), 9.0);
+        assert_eq!(evaluate("2 ^ 3 ^ 2").unwrap(), 512.0);
+        assert_eq!(evaluate("-2 ^ 2").unwrap(), -4.0);
+        assert_eq!(evaluate("10 % 4 - 8 / 4").unwrap(), 0.0);
+        assert_eq!(evaluate("1.5e3 + sqrt(16)").unwrap(), 1504.0);
+        assert!((evaluate("cos(pi)").unwrap() + 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(evaluate("1 / 0").is_err());
+        assert!(evaluate("2 +").is_err());
+        assert!(evaluate("(1 + 2").is_err());
+        assert!(evaluate("rm -rf").is_err());
+        assert!(evaluate(&"(".repeat(100)).is_err());
+        assert_eq!(evaluate(&("-".repeat(1_000_001) + "1")), Ok(-1.0));
+        assert!(evaluate(&("2^".repeat(100_000) + "1")).is_err());
+    }
+}
@@ -1,23 +1,79 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use futures_util::StreamExt;
 use reqwest::StatusCode;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
 use tauri::Emitter;
 use thiserror::Error;
 use tracing::{debug, info, warn};
 
 use crate::config::service::{ConfigService, ProviderCredential};
 use crate::config::ConfigError;
+use crate::mcp::manager::McpManager;
+use crate::mcp::McpError;
+use crate::services::attachments::{
+    content_json, Attachment, AttachmentError, AttachmentInput, AttachmentStore, ContentPart,
+    PartFormat,
+};
+use crate::services::context_window::{
+    context_limit, estimate_tokens, message_tokens, overflow, BudgetItem, IMAGE_TOKENS,
+};
+use crate::services::conversations::{ConversationStore, NewMessage, StoredMessage, ToolCall};
+use crate::services::documents::{ground, DocumentError};
+use crate::services::mcp_context::{expand_prompt, render_resources, PromptRef, ResourceRef};
+use crate::services::memory::recall;
+use crate::services::structured_output::ResponseSchema;
+use crate::services::tool_policy::{
+    AuditDecision, AuditEntry, ToolApprovalRequest, ToolPolicyError, ToolPolicyService,
+};
+use crate::services::tools::{ToolCatalog, ToolOutcome, ToolSource};
 
 const OPENAI_CHAT_ENDPOINT: &str = "https://api.openai.com/v1/chat/completions";
+/// Upper bound on model → tool → model round trips for one prompt.
+const MAX_TOOL_ROUNDS: usize = 8;
+/// Smallest prompt budget left after reserving room for the reply.
+const MIN_PROMPT_TOKENS: usize = 1_024;
+const SUMMARY_INSTRUCTIONS: &str = "You compact chat histories. Summarize the conversation \
+     below in at most a few paragraphs, keeping facts, decisions, open questions, names, \
+     numbers and code identifiers the assistant may need later. Write the summary only.";
+const SUMMARY_PREFIX: &str = "Summary of the earlier part of this conversation:\n";
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ChatRequest {
+    #[serde(default)]
     pub prompt: String,
     pub model: Option<String>,
     pub conversation_id: Option<String>,
+    /// MCP resources whose contents are appended to the prompt.
+    #[serde(default)]
+    pub resources: Vec<ResourceRef>,
+    /// MCP prompt expanded into messages ahead of `prompt`.
+    pub mcp_prompt: Option<PromptRef>,
+    /// Provider to use instead of the default one.
+    pub provider: Option<String>,
+    /// Offer tools to the model. On unless explicitly disabled.
+    #[serde(default = "tools_enabled")]
+    pub tools: bool,
+    /// Recall relevant memories into the prompt; defaults to the `memory.enabled` setting.
+    pub memory: Option<bool>,
+    /// Document collection whose most relevant excerpts ground the answer.
+    pub collection_id: Option<i64>,
+    /// Images and files sent along with `prompt`.
+    #[serde(default)]
+    pub attachments: Vec<AttachmentInput>,
+    /// Require the final answer to be JSON matching this schema.
+    #[serde(default)]
+    pub response_schema: Option<ResponseSchema>,
+    /// Answer the last prompt of the active branch again instead of adding one. Set by
+    /// [`regenerate_message`].
+    #[serde(skip)]
+    pub regenerate: bool,
+}
+
+fn tools_enabled() -> bool {
+    true
 }
 
 #[derive(Debug, Serialize)]
@@ -25,15 +81,58 @@ pub struct ChatRequest {
 pub struct ChatResponse {
     pub reply: String,
     pub model: String,
+    pub conversation_id: String,
+    /// The parsed reply when the request carried a `response_schema`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structured: Option<Value>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkKind {
+    Content,
+    /// The model's thinking, streamed ahead of (or between) content.
+    Reasoning,
+    ToolCall,
+    ToolResult,
+    /// Token counts for one model round, sent once it finishes.
+    Usage,
+    /// The turn failed; `delta` holds the message. Always `done`.
+    Error,
 }
 
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ChatStreamChunk {
     pub conversation_id: String,
+    pub kind: ChunkKind,
     pub delta: String,
     pub done: bool,
     pub model: Option<String>,
+    pub tool: Option<ToolChunk>,
+    pub usage: Option<TokenUsage>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Part of `completion_tokens` spent thinking, when the provider reports it.
+    pub reasoning_tokens: Option<u64>,
+    pub total_tokens: u64,
+}
+
+/// Details for `tool_call` and `tool_result` chunks.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolChunk {
+    pub id: String,
+    pub name: String,
+    pub server: Option<String>,
+    pub arguments: Option<String>,
+    pub output: Option<String>,
+    pub is_error: bool,
 }
 
 #[derive(Debug, Error)]
@@ -48,62 +147,235 @@ pub enum ChatError {
     Network(String),
     #[error("provider error ({status}): {message}")]
     Provider { status: StatusCode, message: String },
+    #[error("{0}")]
+    Mcp(#[from] McpError),
+    #[error("{0}")]
+    ToolPolicy(#[from] ToolPolicyError),
+    #[error("conversation storage error: {0}")]
+    Storage(#[from] sqlx::Error),
+    #[error("model kept requesting tools after {0} rounds")]
+    ToolLoop(usize),
+    #[error("{0}")]
+    Documents(#[from] DocumentError),
+    #[error("{0}")]
+    Attachment(#[from] AttachmentError),
+    #[error("message {0} is not a user prompt")]
+    NotAPrompt(i64),
+    #[error("provider error: {0}")]
+    Stream(String),
+    #[error("reply does not match the response schema: {0}")]
+    InvalidStructuredOutput(String),
 }
 
 pub async fn send_chat(
+    app: &tauri::AppHandle,
     config: &ConfigService,
+    mcp: &McpManager,
+    policies: &ToolPolicyService,
     request: ChatRequest,
 ) -> Result<ChatResponse, ChatError> {
-    let credential = config.default_provider_credentials().await?;
+    let credential = credential_for(config, &request).await?;
     match credential.provider.as_str() {
-        "openai" | "openrouter" => send_openai(request, credential).await,
+        "openai" | "openrouter" => {
+            let services = ChatServices { app, mcp, policies };
+            let mut session =
+                ChatSession::open(config, services, request, credential, false).await?;
+            let reply = session.respond().await?;
+            Ok(ChatResponse {
+                reply,
+                model: session.model,
+                conversation_id: session.conversation_id,
+                structured: session.structured,
+            })
+        }
         other => Err(ChatError::UnsupportedProvider(other.to_string())),
     }
 }
 
+/// Streams the reply as `chat:chunk` events. Returns the conversation id.
 pub async fn stream_chat(
-    window: &tauri::Window,
+    app: &tauri::AppHandle,
     config: &ConfigService,
+    mcp: &McpManager,
+    policies: &ToolPolicyService,
     request: ChatRequest,
-) -> Result<(), ChatError> {
-    let credential = config.default_provider_credentials().await?;
+) -> Result<String, ChatError> {
+    let credential = credential_for(config, &request).await?;
     info!(
         provider = credential.provider.as_str(),
         "starting streaming chat"
     );
     match credential.provider.as_str() {
-        "openai" | "openrouter" => stream_openai(window, request, credential).await,
+        "openai" | "openrouter" => {
+            let services = ChatServices { app, mcp, policies };
+            let mut session =
+                ChatSession::open(config, services, request, credential, true).await?;
+            if let Err(err) = session.respond().await {
+                // Listeners may already be showing partial output; tell them it ends here.
+                session.emit(ChatStreamChunk {
+                    conversation_id: session.conversation_id.clone(),
+                    kind: ChunkKind::Error,
+                    delta: err.to_string(),
+                    done: true,
+                    model: Some(session.model.clone()),
+                    tool: None,
+                    usage: None,
+                })?;
+                return Err(err);
+            }
+            session.emit(ChatStreamChunk {
+                conversation_id: session.conversation_id.clone(),
+                kind: ChunkKind::Content,
+                delta: String::new(),
+                done: true,
+                model: Some(session.model.clone()),
+                tool: None,
+                usage: None,
+            })?;
+            Ok(session.conversation_id)
+        }
         other => Err(ChatError::UnsupportedProvider(other.to_string())),
     }
 }
 
-async fn send_openai(
-    request: ChatRequest,
-    credential: ProviderCredential,
-) -> Result<ChatResponse, ChatError> {
-    let model = request
-        .model
-        .or(credential.default_model.clone())
-        .unwrap_or_else(|| "gpt-4o-mini".to_string());
+/// Streams a new reply to the prompt behind `message_id` (the prompt itself or any
+/// message of its reply), as a sibling branch of the existing reply. `request` carries the
+/// options, e.g. another model; its prompt is ignored.
+pub async fn regenerate_message(
+    app: &tauri::AppHandle,
+    config: &ConfigService,
+    mcp: &McpManager,
+    policies: &ToolPolicyService,
+    message_id: i64,
+    mut request: ChatRequest,
+) -> Result<String, ChatError> {
+    let store = ConversationStore::new(config.pool().clone());
+    let prompt = store.prompt_of(message_id).await?;
+    request.prompt.clear();
+    request.regenerate = true;
+    on_branch(
+        app,
+        config,
+        mcp,
+        policies,
+        &prompt.conversation_id,
+        Some(prompt.id),
+        request,
+    )
+    .await
+}
+
+/// Streams a reply to `request.prompt` as an edited version of the prompt `message_id`,
+/// on a new branch next to the original. The original's attachments are kept unless the
+/// request brings its own.
+pub async fn edit_and_resend(
+    app: &tauri::AppHandle,
+    config: &ConfigService,
+    mcp: &McpManager,
+    policies: &ToolPolicyService,
+    message_id: i64,
+    mut request: ChatRequest,
+) -> Result<String, ChatError> {
+    let store = ConversationStore::new(config.pool().clone());
+    let original = store.node(message_id).await?;
+    if original.role != "user" {
+        return Err(ChatError::NotAPrompt(message_id));
+    }
+    if request.attachments.is_empty() {
+        request.attachments = AttachmentStore::new(config)
+            .for_message(message_id)
+            .await?
+            .into_iter()
+            .map(|attachment| AttachmentInput {
+                hash: Some(attachment.hash),
+                name: Some(attachment.name),
+                ..Default::default()
+            })
+            .collect();
+    }
+    on_branch(
+        app,
+        config,
+        mcp,
+        policies,
+        &original.conversation_id,
+        original.parent_id,
+        request,
+    )
+    .await
+}
 
+/// Runs `request` with the conversation's active leaf moved to `leaf`. The previous branch
+/// is active again if the request fails, since the session then takes back what it added.
+async fn on_branch(
+    app: &tauri::AppHandle,
+    config: &ConfigService,
+    mcp: &McpManager,
+    policies: &ToolPolicyService,
+    conversation_id: &str,
+    leaf: Option<i64>,
+    mut request: ChatRequest,
+) -> Result<String, ChatError> {
+    let store = ConversationStore::new(config.pool().clone());
+    let previous = store.active_leaf(conversation_id).await?;
+    store.set_active_leaf(conversation_id, leaf).await?;
+    request.conversation_id = Some(conversation_id.to_string());
+    let result = stream_chat(app, config, mcp, policies, request).await;
+    if result.is_err() && store.active_leaf(conversation_id).await? == leaf {
+        store.set_active_leaf(conversation_id, previous).await?;
+    }
+    result
+}
+
+/// A single request without history or tools, for housekeeping such as titling. Returns
+/// the reply text.
+pub async fn complete_once(
+    credential: &ProviderCredential,
+    model: &str,
+    instructions: &str,
+    input: String,
+) -> Result<String, ChatError> {
+    if !matches!(credential.provider.as_str(), "openai" | "openrouter") {
+        return Err(ChatError::UnsupportedProvider(credential.provider.clone()));
+    }
     let client = reqwest::Client::builder()
         .timeout(Duration::from_secs(30))
         .build()
         .map_err(|err| ChatError::Network(err.to_string()))?;
-
     let body = OpenAiRequest {
-        model: model.clone(),
-        messages: vec![OpenAiMessage {
-            role: "user".into(),
-            content: request.prompt,
-        }],
+        model: model.to_string(),
+        messages: vec![
+            OpenAiMessage::text("system", instructions.into()),
+            OpenAiMessage::text("user", input),
+        ],
         stream: None,
+        stream_options: None,
+        tools: None,
+        output_format: Map::new(),
     };
+    let payload: OpenAiResponse = post_completion(&client, credential, &body)
+        .await?
+        .json()
+        .await
+        .map_err(|err| ChatError::Network(err.to_string()))?;
+    payload
+        .choices
+        .into_iter()
+        .find_map(|choice| choice.message?.content)
+        .map(|reply| split_thinking(&reply).1)
+        .filter(|reply| !reply.trim().is_empty())
+        .ok_or(ChatError::EmptyResponse)
+}
 
+async fn post_completion(
+    client: &reqwest::Client,
+    credential: &ProviderCredential,
+    body: &OpenAiRequest,
+) -> Result<reqwest::Response, ChatError> {
     let response = client
         .post(OPENAI_CHAT_ENDPOINT)
         .bearer_auth(&credential.api_key)
-        .json(&body)
+        .json(body)
         .send()
         .await
         .map_err(|err| ChatError::Network(err.to_string()))?;
@@ -119,127 +391,821 @@ async fn send_openai(
             message: text,
         });
     }
+    Ok(response)
+}
 
-    let payload: OpenAiResponse = response
-        .json()
-        .await
-        .map_err(|err| ChatError::Network(err.to_string()))?;
-
-    let reply = payload
-        .choices
-        .into_iter()
-        .find_map(|choice| choice.message.and_then(|m| m.content))
-        .ok_or(ChatError::EmptyResponse)?;
+async fn credential_for(
+    config: &ConfigService,
+    request: &ChatRequest,
+) -> Result<ProviderCredential, ChatError> {
+    Ok(match &request.provider {
+        Some(provider) => config.provider_credentials(provider).await?,
+        None => config.default_provider_credentials().await?,
+    })
+}
 
-    Ok(ChatResponse { reply, model })
+/// App state a chat turn talks to besides the provider.
+struct ChatServices<'a> {
+    app: &'a tauri::AppHandle,
+    mcp: &'a McpManager,
+    policies: &'a ToolPolicyService,
 }
 
-async fn stream_openai(
-    window: &tauri::Window,
-    request: ChatRequest,
+/// One prompt's worth of work: the stored history, the tools on offer and the provider
+/// connection. Content chunks are emitted to the app when `stream` is set.
+struct ChatSession<'a> {
+    services: ChatServices<'a>,
+    stream: bool,
+    store: ConversationStore,
+    tools: ToolCatalog,
     credential: ProviderCredential,
-) -> Result<(), ChatError> {
-    let model = request
-        .model
-        .or(credential.default_model.clone())
-        .unwrap_or_else(|| "gpt-4o-mini".to_string());
+    client: reqwest::Client,
+    model: String,
+    conversation_id: String,
+    messages: Vec<OpenAiMessage>,
+    /// Index of the first message of the turn being answered; older ones may be trimmed.
+    pinned: usize,
+    /// Tokens the prompt may use, after reserving room for the reply.
+    budget: usize,
+    /// Compact trimmed history into a stored summary instead of forgetting it.
+    summarize: bool,
+    summary: Option<String>,
+    response_schema: Option<ResponseSchema>,
+    /// The validated answer once `run` succeeds with a `response_schema`.
+    structured: Option<Value>,
+    /// Prompt messages (role, content) stored once the turn starts.
+    pending: Vec<(String, String)>,
+    /// Attachments of the prompt, saved but not yet linked to it.
+    attachments: Vec<Attachment>,
+    attachment_store: AttachmentStore,
+    /// The first message stored by this request, removed again if it fails.
+    first_added: Option<i64>,
+}
 
-    let conversation_id = request
-        .conversation_id
-        .unwrap_or_else(|| "default".to_string());
+struct AssistantTurn {
+    content: String,
+    reasoning: String,
+    tool_calls: Vec<ToolCall>,
+}
 
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(60))
-        .build()
-        .map_err(|err| ChatError::Network(err.to_string()))?;
+impl<'a> ChatSession<'a> {
+    async fn open(
+        config: &ConfigService,
+        services: ChatServices<'a>,
+        request: ChatRequest,
+        credential: ProviderCredential,
+        stream: bool,
+    ) -> Result<Self, ChatError> {
+        let mcp = services.mcp;
+        let model = request
+            .model
+            .or(credential.default_model.clone())
+            .unwrap_or_else(|| "gpt-4o-mini".to_string());
+        let conversation_id = request
+            .conversation_id
+            .unwrap_or_else(|| "default".to_string());
 
-    let body = OpenAiRequest {
-        model: model.clone(),
-        messages: vec![OpenAiMessage {
-            role: "user".into(),
-            content: request.prompt,
-        }],
-        stream: Some(true),
-    };
+        let store = ConversationStore::new(config.pool().clone());
+        store.ensure(&conversation_id).await?;
+        let attachment_store = AttachmentStore::new(config);
+        let mut stored_attachments = attachment_store.for_conversation(&conversation_id).await?;
+        let history = store.history(&conversation_id).await?;
+        // A summary only stands in for the branch it was written on.
+        let summary = store.summary(&conversation_id).await?.filter(|summary| {
+            history
+                .iter()
+                .any(|message| message.id == summary.through_id)
+        });
+        let summarized_through = summary.as_ref().map_or(0, |summary| summary.through_id);
+        let mut messages = Vec::new();
+        if let Some(summary) = &summary {
+            messages.push(OpenAiMessage::summary(&summary.summary));
+        }
+        for message in history {
+            if message.id <= summarized_through {
+                continue;
+            }
+            let mut parts = Vec::new();
+            for attachment in stored_attachments.remove(&message.id).unwrap_or_default() {
+                parts.push(attachment_store.part(&attachment).await?);
+            }
+            messages.push(OpenAiMessage::stored(message, parts));
+        }
+        let mut pinned = messages.len();
+        if request.regenerate {
+            // The prompt being answered again is part of the history but must not be trimmed.
+            pinned = messages
+                .iter()
+                .rposition(|message| message.role == "user")
+                .unwrap_or(pinned);
+        }
+        let mut attachments = Vec::with_capacity(request.attachments.len());
+        for input in &request.attachments {
+            attachments.push(attachment_store.save(input).await?);
+        }
+        let mut turn: Vec<(String, String)> = Vec::new();
+        if let Some(prompt) = &request.mcp_prompt {
+            turn.extend(
+                expand_prompt(mcp, prompt)
+                    .await?
+                    .into_iter()
+                    .map(|message| (message.role, message.content)),
+            );
+        }
+        let use_memory = match request.memory {
+            Some(enabled) => enabled,
+            None => config.get_value("memory.enabled").await?.unwrap_or(false),
+        };
+        if use_memory && !request.prompt.trim().is_empty() {
+            // Recalled context is sent with this turn only and never stored, so it cannot
+            // end up in the index itself. Recall is best effort.
+            match recall(config, &request.prompt, &conversation_id).await {
+                Ok(Some(memories)) => messages.push(OpenAiMessage::text("system", memories)),
+                Ok(None) => {}
+                Err(err) => warn!("memory recall failed: {err}"),
+            }
+        }
+        if let Some(collection_id) = request.collection_id {
+            // Unlike memory, grounding was asked for explicitly, so failures are errors.
+            if let Some(excerpts) = ground(config, collection_id, &request.prompt).await? {
+                messages.push(OpenAiMessage::text("system", excerpts));
+            }
+        }
+        let mut content = request.prompt;
+        if let Some(context) = render_resources(mcp, &request.resources).await? {
+            if !content.is_empty() {
+                content.push_str("\n\n");
+            }
+            content.push_str(&context);
+        }
+        let needs_prompt = turn.is_empty() && !request.regenerate;
+        if !content.is_empty() || needs_prompt || !attachments.is_empty() {
+            turn.push(("user".into(), content));
+        }
+        let tools = if request.tools {
+            ToolCatalog::discover(config, mcp).await?
+        } else {
+            ToolCatalog::default()
+        };
+        let context_tokens = match config.get_value::<i64>("chat.context_tokens").await? {
+            Some(tokens) if tokens > 0 => tokens as usize,
+            _ => context_limit(&model),
+        };
+        let reply_tokens: i64 = config
+            .get_value("chat.reply_tokens")
+            .await?
+            .unwrap_or(4_096);
+        let strategy: String = config
+            .get_value("chat.context_strategy")
+            .await?
+            .unwrap_or_else(|| "truncate".into());
+        let timeout = if stream { 60 } else { 30 };
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(timeout))
+            .build()
+            .map_err(|err| ChatError::Network(err.to_string()))?;
 
-    let response = client
-        .post(OPENAI_CHAT_ENDPOINT)
-        .bearer_auth(&credential.api_key)
-        .json(&body)
-        .send()
-        .await
-        .map_err(|err| ChatError::Network(err.to_string()))?;
+        Ok(Self {
+            services,
+            stream,
+            store,
+            tools,
+            credential,
+            client,
+            model,
+            conversation_id,
+            messages,
+            pinned,
+            budget: context_tokens
+                .saturating_sub(reply_tokens.max(0) as usize)
+                .max(MIN_PROMPT_TOKENS),
+            summarize: strategy == "summarize",
+            summary: summary.map(|summary| summary.summary),
+            response_schema: request.response_schema,
+            structured: None,
+            pending: turn,
+            attachments,
+            attachment_store,
+            first_added: None,
+        })
+    }
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "unknown error".to_string());
-        return Err(ChatError::Provider {
-            status,
-            message: text,
-        });
+    /// Stores the prompt and answers it. A request that fails leaves no trace: whatever it
+    /// added is deleted and the branch it started from is active again.
+    async fn respond(&mut self) -> Result<String, ChatError> {
+        let result = match self.start_turn().await {
+            Ok(()) => self.run().await,
+            Err(err) => Err(err),
+        };
+        if result.is_err() {
+            if let Some(first) = self.first_added {
+                if let Err(err) = self.store.discard(first).await {
+                    warn!("unable to remove the failed turn: {err}");
+                }
+            }
+        }
+        result
     }
 
-    let mut buffer = String::new();
-    let mut stream = response.bytes_stream();
+    /// Stores the prompt messages prepared by [`open`](Self::open).
+    async fn start_turn(&mut self) -> Result<(), ChatError> {
+        let mut last_id = None;
+        for (role, content) in std::mem::take(&mut self.pending) {
+            let id = self
+                .store
+                .append(
+                    &self.conversation_id,
+                    NewMessage {
+                        role: &role,
+                        content: &content,
+                        provider: Some(&self.credential.provider),
+                        model: Some(&self.model),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            self.first_added.get_or_insert(id);
+            last_id = Some(id);
+            self.messages
+                .push(OpenAiMessage::text(&role, content).with_id(id));
+        }
+        // The user prompt is always the last message of the turn when there are attachments.
+        if let (Some(message_id), Some(message)) = (last_id, self.messages.last_mut()) {
+            if !self.attachments.is_empty() {
+                self.attachment_store
+                    .link(message_id, &self.attachments)
+                    .await?;
+                for attachment in &self.attachments {
+                    message
+                        .content
+                        .push(self.attachment_store.part(attachment).await?);
+                }
+                drop_empty_text(&mut message.content);
+            }
+        }
+        Ok(())
+    }
+
+    /// Alternates between the model and tool execution until the model answers without
+    /// requesting tools. Returns the final answer, which with a response schema is retried
+    /// once with the validation error before giving up.
+    async fn run(&mut self) -> Result<String, ChatError> {
+        let mut retried = false;
+        for _ in 0..MAX_TOOL_ROUNDS {
+            self.fit_context().await?;
+            let turn = if self.stream {
+                self.stream_turn().await?
+            } else {
+                self.complete_turn().await?
+            };
+            let id = self
+                .store
+                .append(
+                    &self.conversation_id,
+                    NewMessage {
+                        role: "assistant",
+                        content: &turn.content,
+                        tool_calls: &turn.tool_calls,
+                        provider: Some(&self.credential.provider),
+                        model: Some(&self.model),
+                        reasoning: Some(turn.reasoning.as_str()).filter(|text| !text.is_empty()),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            self.first_added.get_or_insert(id);
+            self.messages
+                .push(OpenAiMessage::assistant(&turn).with_id(id));
+            if turn.tool_calls.is_empty() {
+                let Some(schema) = &self.response_schema else {
+                    return Ok(turn.content);
+                };
+                match schema.parse_reply(&turn.content) {
+                    Ok(value) => {
+                        self.structured = Some(value);
+                        return Ok(turn.content);
+                    }
+                    Err(problem) if !retried => {
+                        retried = true;
+                        self.ask_for_correction(&problem).await?;
+                        continue;
+                    }
+                    Err(problem) => return Err(ChatError::InvalidStructuredOutput(problem)),
+                }
+            }
+            for call in &turn.tool_calls {
+                self.run_tool(call).await?;
+            }
+        }
+        Err(ChatError::ToolLoop(MAX_TOOL_ROUNDS))
+    }
 
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|err| ChatError::Network(err.to_string()))?;
-        buffer.push_str(&String::from_utf8_lossy(&chunk));
+    /// Stores and sends the validation error so the model can fix its answer.
+    async fn ask_for_correction(&mut self, problem: &str) -> Result<(), ChatError> {
+        let content = format!(
+            "Your reply does not match the required JSON schema: {problem}. \
+             Reply again with only the corrected JSON."
+        );
+        let id = self
+            .store
+            .append(
+                &self.conversation_id,
+                NewMessage {
+                    role: "user",
+                    content: &content,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        self.first_added.get_or_insert(id);
+        self.messages
+            .push(OpenAiMessage::text("user", content).with_id(id));
+        Ok(())
+    }
 
-        while let Some(idx) = buffer.find("\n\n") {
-            let mut event = buffer[..idx].trim().to_string();
-            buffer = buffer[idx + 2..].to_string();
-            if !event.starts_with("data:") {
-                continue;
+    /// Drops the oldest turns that do not fit the budget. In summarize mode they are first
+    /// folded into the conversation's stored summary, which then stands in for them.
+    async fn fit_context(&mut self) -> Result<(), ChatError> {
+        let items: Vec<BudgetItem> = self
+            .messages
+            .iter()
+            .map(|message| BudgetItem {
+                role: &message.role,
+                tokens: message.tokens(&self.model),
+            })
+            .collect();
+        let dropped = overflow(&items, self.pinned, self.budget);
+        if dropped.is_empty() {
+            return Ok(());
+        }
+        let removed: Vec<OpenAiMessage> = self.messages.drain(dropped).collect();
+        self.pinned -= removed.len();
+        info!(
+            conversation = self.conversation_id.as_str(),
+            dropped = removed.len(),
+            "trimmed history to fit the context window"
+        );
+        let Some(through_id) = removed.iter().filter_map(|message| message.id).max() else {
+            return Ok(());
+        };
+        if !self.summarize {
+            return Ok(());
+        }
+        // Without a summary the trimmed turns are simply forgotten, as in truncate mode.
+        let summary = match self.summarize_history(&removed).await {
+            Ok(summary) => summary,
+            Err(err) => {
+                warn!("unable to summarize trimmed history: {err}");
+                return Ok(());
             }
-            event = event.replace("data:", "").trim().to_string();
-            if event == "[DONE]" {
-                debug!("stream finished");
-                window
-                    .emit(
-                        "chat:chunk",
-                        ChatStreamChunk {
-                            conversation_id: conversation_id.clone(),
-                            delta: String::new(),
-                            done: true,
-                            model: Some(model.clone()),
+        };
+        self.store
+            .save_summary(&self.conversation_id, &summary, through_id)
+            .await?;
+        let message = OpenAiMessage::summary(&summary);
+        if self.summary.is_some() {
+            self.messages[0] = message;
+        } else {
+            self.messages.insert(0, message);
+            self.pinned += 1;
+        }
+        self.summary = Some(summary);
+        Ok(())
+    }
+
+    /// Asks the model to merge `removed` into the existing summary, if any.
+    async fn summarize_history(&self, removed: &[OpenAiMessage]) -> Result<String, ChatError> {
+        let mut transcript = String::new();
+        if let Some(summary) = &self.summary {
+            transcript.push_str(&format!("Earlier summary:\n{summary}\n\nThen:\n"));
+        }
+        for message in removed {
+            transcript.push_str(&format!("{}: {}\n", message.role, message.plain_text()));
+        }
+        // The transcript is itself bounded by the budget; keep its most recent part.
+        let max_chars = self.budget.saturating_mul(3);
+        let skip = transcript.chars().count().saturating_sub(max_chars);
+        let transcript: String = transcript.chars().skip(skip).collect();
+        let body = OpenAiRequest {
+            model: self.model.clone(),
+            messages: vec![
+                OpenAiMessage::text("system", SUMMARY_INSTRUCTIONS.into()),
+                OpenAiMessage::text("user", transcript),
+            ],
+            stream: None,
+            stream_options: None,
+            tools: None,
+            output_format: Map::new(),
+        };
+        let payload: OpenAiResponse = self
+            .post(&body)
+            .await?
+            .json()
+            .await
+            .map_err(|err| ChatError::Network(err.to_string()))?;
+        payload
+            .choices
+            .into_iter()
+            .find_map(|choice| choice.message?.content)
+            .map(|summary| split_thinking(&summary).1)
+            .filter(|summary| !summary.trim().is_empty())
+            .ok_or(ChatError::EmptyResponse)
+    }
+
+    async fn run_tool(&mut self, call: &ToolCall) -> Result<(), ChatError> {
+        let source = self.tools.find(&call.name).map(|tool| tool.source.clone());
+        let server = source
+            .as_ref()
+            .and_then(|source| source.server().map(str::to_string));
+        self.emit_tool(ChunkKind::ToolCall, call, server.clone(), None)?;
+
+        let decision = match &source {
+            Some(source) => {
+                self.services
+                    .policies
+                    .authorize(
+                        self.services.app,
+                        ToolApprovalRequest {
+                            approval_id: String::new(),
+                            conversation_id: self.conversation_id.clone(),
+                            call_id: call.id.clone(),
+                            server: source.server().unwrap_or_default().to_string(),
+                            tool: source.tool().to_string(),
+                            arguments: call.arguments.clone(),
                         },
                     )
-                    .map_err(|err| ChatError::Network(err.to_string()))?;
-                return Ok(());
+                    .await?
             }
+            None => AuditDecision::Unknown,
+        };
+        let started = Instant::now();
+        let outcome = match decision {
+            AuditDecision::Denied => ToolOutcome::error("tool call denied by policy".into()),
+            AuditDecision::Rejected => ToolOutcome::error("tool call rejected by the user".into()),
+            _ => self.tools.execute(self.services.mcp, call).await,
+        };
+        self.services
+            .policies
+            .record(AuditEntry {
+                conversation_id: &self.conversation_id,
+                call_id: &call.id,
+                server: server.as_deref(),
+                tool: source.as_ref().map_or(call.name.as_str(), ToolSource::tool),
+                arguments: &call.arguments,
+                decision,
+                is_error: outcome.is_error,
+                result_bytes: outcome.output.len(),
+                duration: started.elapsed(),
+            })
+            .await?;
+
+        self.emit_tool(ChunkKind::ToolResult, call, server, Some(&outcome))?;
+        let id = self
+            .store
+            .append(
+                &self.conversation_id,
+                NewMessage {
+                    role: "tool",
+                    content: &outcome.output,
+                    tool_call_id: Some(&call.id),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        self.first_added.get_or_insert(id);
+        self.messages
+            .push(OpenAiMessage::tool_result(&call.id, outcome.output).with_id(id));
+        Ok(())
+    }
 
-            let payload: OpenAiStreamChunk =
-                serde_json::from_str(&event).map_err(|err| ChatError::Network(err.to_string()))?;
-            if let Some(delta) = payload
-                .choices
-                .into_iter()
-                .find_map(|choice| choice.delta.and_then(|m| m.content))
-            {
-                if delta.is_empty() {
+    fn request_body(&self, stream: bool) -> OpenAiRequest {
+        let tools = self
+            .tools
+            .definitions()
+            .iter()
+            .map(|tool| OpenAiTool {
+                kind: "function",
+                function: OpenAiFunction {
+                    name: tool.name.clone(),
+                    description: tool.description.clone(),
+                    parameters: tool.parameters.clone(),
+                },
+            })
+            .collect::<Vec<_>>();
+        OpenAiRequest {
+            model: self.model.clone(),
+            messages: self.messages.clone(),
+            stream: stream.then_some(true),
+            stream_options: stream.then_some(OpenAiStreamOptions {
+                include_usage: true,
+            }),
+            tools: (!tools.is_empty()).then_some(tools),
+            output_format: self
+                .response_schema
+                .as_ref()
+                .map(|schema| {
+                    schema.request_fields(PartFormat::for_provider(&self.credential.provider))
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    async fn post(&self, body: &OpenAiRequest) -> Result<reqwest::Response, ChatError> {
+        post_completion(&self.client, &self.credential, body).await
+    }
+
+    async fn complete_turn(&self) -> Result<AssistantTurn, ChatError> {
+        let response = self.post(&self.request_body(false)).await?;
+        let payload: OpenAiResponse = response
+            .json()
+            .await
+            .map_err(|err| ChatError::Network(err.to_string()))?;
+
+        let message = payload
+            .choices
+            .into_iter()
+            .find_map(|choice| choice.message)
+            .ok_or(ChatError::EmptyResponse)?;
+        let tool_calls: Vec<ToolCall> = message
+            .tool_calls
+            .unwrap_or_default()
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        if message.content.is_none() && tool_calls.is_empty() {
+            return Err(ChatError::EmptyResponse);
+        }
+        let (inline, content) = split_thinking(message.content.as_deref().unwrap_or_default());
+        Ok(AssistantTurn {
+            content,
+            reasoning: message.reasoning.reasoning().unwrap_or_default() + &inline,
+            tool_calls,
+        })
+    }
+
+    async fn stream_turn(&self) -> Result<AssistantTurn, ChatError> {
+        let response = self.post(&self.request_body(true)).await?;
+        let mut content = String::new();
+        let mut reasoning = String::new();
+        let mut thinking = ThinkSplitter::default();
+        let mut pending_calls: Vec<PartialToolCall> = Vec::new();
+        let mut buffer = String::new();
+        let mut stream = response.bytes_stream();
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|err| ChatError::Network(err.to_string()))?;
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+            while let Some(idx) = buffer.find("\n\n") {
+                let mut event = buffer[..idx].trim().to_string();
+                buffer = buffer[idx + 2..].to_string();
+                if !event.starts_with("data:") {
                     continue;
                 }
-                debug!(delta = delta.as_str(), "stream delta");
-                window
-                    .emit(
-                        "chat:chunk",
-                        ChatStreamChunk {
-                            conversation_id: conversation_id.clone(),
-                            delta,
-                            done: false,
-                            model: None,
-                        },
-                    )
+                event = event.replacen("data:", "", 1).trim().to_string();
+                if event == "[DONE]" {
+                    debug!("stream finished");
+                    let (thought, answer) = thinking.finish();
+                    self.emit_text(ChunkKind::Reasoning, thought, &mut reasoning)?;
+                    self.emit_text(ChunkKind::Content, answer, &mut content)?;
+                    return Ok(AssistantTurn {
+                        content,
+                        reasoning,
+                        tool_calls: pending_calls
+                            .into_iter()
+                            .enumerate()
+                            .map(|(index, call)| call.finish(index))
+                            .collect(),
+                    });
+                }
+
+                let payload: OpenAiStreamChunk = serde_json::from_str(&event)
                     .map_err(|err| ChatError::Network(err.to_string()))?;
+                if let Some(error) = payload.error {
+                    return Err(ChatError::Stream(error.message));
+                }
+                for delta in payload
+                    .choices
+                    .into_iter()
+                    .filter_map(|choice| choice.delta)
+                {
+                    for call in delta.tool_calls.unwrap_or_default() {
+                        if pending_calls.len() <= call.index {
+                            pending_calls.resize_with(call.index + 1, Default::default);
+                        }
+                        pending_calls[call.index].absorb(call);
+                    }
+                    if let Some(thought) = delta.reasoning.reasoning() {
+                        self.emit_text(ChunkKind::Reasoning, thought, &mut reasoning)?;
+                    }
+                    let Some(delta) = delta.content.filter(|delta| !delta.is_empty()) else {
+                        continue;
+                    };
+                    debug!(delta = delta.as_str(), "stream delta");
+                    let (thought, answer) = thinking.push(&delta);
+                    self.emit_text(ChunkKind::Reasoning, thought, &mut reasoning)?;
+                    self.emit_text(ChunkKind::Content, answer, &mut content)?;
+                }
+                if let Some(usage) = payload.usage {
+                    self.emit(ChatStreamChunk {
+                        conversation_id: self.conversation_id.clone(),
+                        kind: ChunkKind::Usage,
+                        delta: String::new(),
+                        done: false,
+                        model: Some(self.model.clone()),
+                        tool: None,
+                        usage: Some(usage.into()),
+                    })?;
+                }
+            }
+        }
+
+        warn!("stream ended without completion");
+        Err(ChatError::EmptyResponse)
+    }
+
+    /// Emits a non-empty `delta` as a `kind` chunk and appends it to `text`.
+    fn emit_text(
+        &self,
+        kind: ChunkKind,
+        delta: String,
+        text: &mut String,
+    ) -> Result<(), ChatError> {
+        if delta.is_empty() {
+            return Ok(());
+        }
+        text.push_str(&delta);
+        self.emit(ChatStreamChunk {
+            conversation_id: self.conversation_id.clone(),
+            kind,
+            delta,
+            done: false,
+            model: None,
+            tool: None,
+            usage: None,
+        })
+    }
+
+    fn emit(&self, chunk: ChatStreamChunk) -> Result<(), ChatError> {
+        if self.stream {
+            self.services
+                .app
+                .emit("chat:chunk", chunk)
+                .map_err(|err| ChatError::Network(err.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn emit_tool(
+        &self,
+        kind: ChunkKind,
+        call: &ToolCall,
+        server: Option<String>,
+        outcome: Option<&ToolOutcome>,
+    ) -> Result<(), ChatError> {
+        self.emit(ChatStreamChunk {
+            conversation_id: self.conversation_id.clone(),
+            kind,
+            delta: String::new(),
+            done: false,
+            model: None,
+            tool: Some(ToolChunk {
+                id: call.id.clone(),
+                name: call.name.clone(),
+                server,
+                arguments: Some(call.arguments.clone()),
+                output: outcome.map(|outcome| outcome.output.clone()),
+                is_error: outcome.is_some_and(|outcome| outcome.is_error),
+            }),
+            usage: None,
+        })
+    }
+}
+
+const THINK_OPEN: &str = "<think>";
+const THINK_CLOSE: &str = "</think>";
+
+/// Separates the `<think>…</think>` block some open models (DeepSeek R1, QwQ) put at the
+/// start of their content. Tags may be split across deltas, so text that could be the
+/// start of one is held back until the next delta decides.
+#[derive(Debug, Default)]
+struct ThinkSplitter {
+    state: ThinkState,
+    pending: String,
+    /// Set by `</think>` until the answer's first visible text arrives.
+    trim_answer: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ThinkState {
+    #[default]
+    Start,
+    Thinking,
+    Answer,
+}
+
+impl ThinkSplitter {
+    /// Feeds a content delta; returns the reasoning and content that can be shown now.
+    fn push(&mut self, delta: &str) -> (String, String) {
+        self.pending.push_str(delta);
+        let mut reasoning = String::new();
+        loop {
+            match self.state {
+                ThinkState::Start => {
+                    let trimmed = self.pending.trim_start();
+                    if let Some(rest) = trimmed.strip_prefix(THINK_OPEN) {
+                        self.pending = rest.to_string();
+                        self.state = ThinkState::Thinking;
+                    } else if THINK_OPEN.starts_with(trimmed) {
+                        return (reasoning, String::new());
+                    } else {
+                        self.state = ThinkState::Answer;
+                    }
+                }
+                ThinkState::Thinking => {
+                    if let Some(end) = self.pending.find(THINK_CLOSE) {
+                        reasoning.push_str(&self.pending[..end]);
+                        self.pending.drain(..end + THINK_CLOSE.len());
+                        self.state = ThinkState::Answer;
+                        self.trim_answer = true;
+                        continue;
+                    }
+                    let held = (1..THINK_CLOSE.len())
+                        .rev()
+                        .find(|&len| self.pending.ends_with(&THINK_CLOSE[..len]))
+                        .unwrap_or(0);
+                    let ready = self.pending.len() - held;
+                    reasoning.extend(self.pending.drain(..ready));
+                    return (reasoning, String::new());
+                }
+                ThinkState::Answer => return (reasoning, self.take_answer()),
+            }
+        }
+    }
+
+    /// Flushes whatever is still held back once the stream ends.
+    fn finish(&mut self) -> (String, String) {
+        match self.state {
+            ThinkState::Thinking => (std::mem::take(&mut self.pending), String::new()),
+            _ => (String::new(), self.take_answer()),
+        }
+    }
+
+    fn take_answer(&mut self) -> String {
+        let mut answer = std::mem::take(&mut self.pending);
+        if self.trim_answer {
+            // The blank line after `</think>` is not part of the answer.
+            answer = answer.trim_start().to_string();
+            self.trim_answer = answer.is_empty();
+        }
+        answer
+    }
+}
+
+/// Splits a complete reply into its inline reasoning and the answer.
+fn split_thinking(text: &str) -> (String, String) {
+    let mut splitter = ThinkSplitter::default();
+    let (mut reasoning, mut answer) = splitter.push(text);
+    let (rest_reasoning, rest_answer) = splitter.finish();
+    reasoning.push_str(&rest_reasoning);
+    answer.push_str(&rest_answer);
+    (reasoning, answer)
+}
+
+/// Accumulates a streamed tool call whose id, name and arguments arrive in fragments.
+#[derive(Debug, Default)]
+struct PartialToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+impl PartialToolCall {
+    fn absorb(&mut self, delta: OpenAiToolCallDelta) {
+        if let Some(id) = delta.id {
+            self.id = id;
+        }
+        if let Some(function) = delta.function {
+            if let Some(name) = function.name {
+                self.name.push_str(&name);
+            }
+            if let Some(arguments) = function.arguments {
+                self.arguments.push_str(&arguments);
             }
         }
     }
 
-    warn!("stream ended without completion");
-    Err(ChatError::EmptyResponse)
+    fn finish(self, index: usize) -> ToolCall {
+        ToolCall {
+            id: if self.id.is_empty() {
+                format!("call_{index}")
+            } else {
+                self.id
+            },
+            name: self.name,
+            arguments: self.arguments,
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -248,12 +1214,204 @@ struct OpenAiRequest {
     messages: Vec<OpenAiMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
     stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<OpenAiStreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<OpenAiTool>>,
+    /// The provider's structured output fields when a response schema was requested.
+    #[serde(flatten)]
+    output_format: Map<String, Value>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone)]
 struct OpenAiMessage {
     role: String,
-    content: String,
+    #[serde(serialize_with = "openai_content")]
+    content: Vec<ContentPart>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<Vec<OpenAiToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+    /// Row id in `messages`; `None` for context that is never stored.
+    #[serde(skip)]
+    id: Option<i64>,
+}
+
+impl OpenAiMessage {
+    fn text(role: &str, content: String) -> Self {
+        Self {
+            role: role.into(),
+            content: vec![ContentPart::Text(content)],
+            tool_calls: None,
+            tool_call_id: None,
+            id: None,
+        }
+    }
+
+    fn assistant(turn: &AssistantTurn) -> Self {
+        Self {
+            role: "assistant".into(),
+            content: text_parts(turn.content.clone()),
+            tool_calls: (!turn.tool_calls.is_empty())
+                .then(|| turn.tool_calls.iter().cloned().map(Into::into).collect()),
+            tool_call_id: None,
+            id: None,
+        }
+    }
+
+    fn tool_result(call_id: &str, output: String) -> Self {
+        Self {
+            role: "tool".into(),
+            content: vec![ContentPart::Text(output)],
+            tool_calls: None,
+            tool_call_id: Some(call_id.into()),
+            id: None,
+        }
+    }
+
+    fn summary(summary: &str) -> Self {
+        Self::text("system", format!("{SUMMARY_PREFIX}{summary}"))
+    }
+
+    fn with_id(self, id: i64) -> Self {
+        Self {
+            id: Some(id),
+            ..self
+        }
+    }
+
+    fn tokens(&self, model: &str) -> usize {
+        let content: usize = self
+            .content
+            .iter()
+            .map(|part| match part {
+                ContentPart::Text(text) => estimate_tokens(model, text),
+                ContentPart::Image { .. } => IMAGE_TOKENS,
+                // Base64 inflates by a third; count the raw bytes at ~4 per token.
+                ContentPart::File { data, .. } => data.len() * 3 / 16,
+            })
+            .sum();
+        let calls: usize = self
+            .tool_calls
+            .iter()
+            .flatten()
+            .map(|call| {
+                estimate_tokens(model, &call.function.name)
+                    + estimate_tokens(model, &call.function.arguments)
+            })
+            .sum();
+        message_tokens(content + calls)
+    }
+
+    /// Content as text for transcripts, with placeholders for binary parts.
+    fn plain_text(&self) -> String {
+        let mut parts: Vec<String> = self
+            .content
+            .iter()
+            .map(|part| match part {
+                ContentPart::Text(text) => text.clone(),
+                ContentPart::Image { .. } => "[image]".into(),
+                ContentPart::File { name, .. } => format!("[file {name}]"),
+            })
+            .collect();
+        for call in self.tool_calls.iter().flatten() {
+            parts.push(format!(
+                "[called {} with {}]",
+                call.function.name, call.function.arguments
+            ));
+        }
+        parts.join(" ")
+    }
+
+    /// A message from the history, with its attachments loaded as extra parts.
+    fn stored(message: StoredMessage, attachments: Vec<ContentPart>) -> Self {
+        let mut content = vec![ContentPart::Text(message.content)];
+        content.extend(attachments);
+        drop_empty_text(&mut content);
+        Self {
+            role: message.role,
+            content,
+            tool_calls: (!message.tool_calls.is_empty())
+                .then(|| message.tool_calls.into_iter().map(Into::into).collect()),
+            tool_call_id: message.tool_call_id,
+            id: Some(message.id),
+        }
+    }
+}
+
+/// Attachments sent without a prompt need no empty text part next to them.
+fn drop_empty_text(parts: &mut Vec<ContentPart>) {
+    if parts.len() > 1 {
+        parts.retain(|part| !matches!(part, ContentPart::Text(text) if text.is_empty()));
+    }
+}
+
+fn text_parts(text: String) -> Vec<ContentPart> {
+    if text.is_empty() {
+        Vec::new()
+    } else {
+        vec![ContentPart::Text(text)]
+    }
+}
+
+fn openai_content<S: Serializer>(parts: &[ContentPart], serializer: S) -> Result<S::Ok, S::Error> {
+    content_json(parts, PartFormat::OpenAi).serialize(serializer)
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAiTool {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    function: OpenAiFunction,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAiFunction {
+    name: String,
+    description: String,
+    parameters: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct OpenAiToolCall {
+    id: String,
+    #[serde(rename = "type", default = "function_kind")]
+    kind: String,
+    function: OpenAiFunctionCall,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct OpenAiFunctionCall {
+    name: String,
+    #[serde(default)]
+    arguments: String,
+}
+
+impl From<ToolCall> for OpenAiToolCall {
+    fn from(call: ToolCall) -> Self {
+        Self {
+            id: call.id,
+            kind: function_kind(),
+            function: OpenAiFunctionCall {
+                name: call.name,
+                arguments: call.arguments,
+            },
+        }
+    }
+}
+
+impl From<OpenAiToolCall> for ToolCall {
+    fn from(call: OpenAiToolCall) -> Self {
+        Self {
+            id: call.id,
+            name: call.function.name,
+            arguments: call.function.arguments,
+        }
+    }
+}
+
+fn function_kind() -> String {
+    "function".into()
 }
 
 #[derive(Debug, Deserialize)]
@@ -269,14 +1427,136 @@ struct OpenAiChoice {
 #[derive(Debug, Deserialize)]
 struct OpenAiChoiceMessage {
     content: Option<String>,
+    tool_calls: Option<Vec<OpenAiToolCall>>,
+    #[serde(flatten)]
+    reasoning: ReasoningFields,
+}
+
+/// Where providers put the model's thinking: OpenRouter and Ollama use `reasoning`,
+/// DeepSeek and vLLM-style servers `reasoning_content`. OpenAI's own o-series keep it
+/// hidden and only report a token count.
+#[derive(Debug, Default, Deserialize)]
+struct ReasoningFields {
+    reasoning: Option<String>,
+    reasoning_content: Option<String>,
+}
+
+impl ReasoningFields {
+    fn reasoning(self) -> Option<String> {
+        self.reasoning
+            .into_iter()
+            .chain(self.reasoning_content)
+            .find(|text| !text.is_empty())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAiStreamOptions {
+    include_usage: bool,
 }
 
 #[derive(Debug, Deserialize)]
 struct OpenAiStreamChunk {
+    /// Empty on the final usage chunk.
+    #[serde(default)]
     choices: Vec<OpenAiStreamChoice>,
+    usage: Option<OpenAiUsage>,
+    /// Set when the provider fails after the stream has started.
+    error: Option<OpenAiStreamError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiStreamError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+    #[serde(default)]
+    total_tokens: u64,
+    completion_tokens_details: Option<OpenAiCompletionDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiCompletionDetails {
+    reasoning_tokens: Option<u64>,
+}
+
+impl From<OpenAiUsage> for TokenUsage {
+    fn from(usage: OpenAiUsage) -> Self {
+        Self {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            reasoning_tokens: usage
+                .completion_tokens_details
+                .and_then(|details| details.reasoning_tokens),
+            total_tokens: usage.total_tokens,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct OpenAiStreamChoice {
-    delta: Option<OpenAiChoiceMessage>,
+    delta: Option<OpenAiStreamDelta>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiStreamDelta {
+    content: Option<String>,
+    tool_calls: Option<Vec<OpenAiToolCallDelta>>,
+    #[serde(flatten)]
+    reasoning: ReasoningFields,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiToolCallDelta {
+    index: usize,
+    id: Option<String>,
+    function: Option<OpenAiFunctionDelta>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiFunctionDelta {
+    name: Option<String>,
+    arguments: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_inline_thinking_across_deltas() {
+        let mut splitter = ThinkSplitter::default();
+        let mut reasoning = String::new();
+        let mut answer = String::new();
+        for delta in [
+            "<th",
+            "ink>Check the ",
+            "units.</thi",
+            "nk>\n\n",
+            "42 km",
+            "",
+        ] {
+            let (thought, text) = splitter.push(delta);
+            reasoning.push_str(&thought);
+            answer.push_str(&text);
+        }
+        let (thought, text) = splitter.finish();
+        reasoning.push_str(&thought);
+        answer.push_str(&text);
+        assert_eq!(reasoning, "Check the units.");
+        assert_eq!(answer, "42 km");
+
+        assert_eq!(
+            split_thinking("  indented <think> stays"),
+            (String::new(), "  indented <think> stays".into())
+        );
+        // A reply cut off mid-thought is all reasoning.
+        assert_eq!(split_thinking("<think>hmm"), ("hmm".into(), String::new()));
+    }
 }
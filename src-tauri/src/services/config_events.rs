@@ -0,0 +1,27 @@
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::warn;
+
+use crate::config::{events::ConfigChange, service::ConfigService};
+
+pub const CONFIG_CHANGED_EVENT: &str = "config:changed";
+
+/// Re-emits every [`ConfigChange`] to all windows so they can refresh stale state.
+pub fn forward_config_changes(app: AppHandle, config: &ConfigService) {
+    let mut changes = config.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let change = match changes.recv().await {
+                Ok(change) => change,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!(skipped, "config change listener lagged");
+                    ConfigChange::Reloaded
+                }
+                Err(RecvError::Closed) => break,
+            };
+            if let Err(err) = app.emit(CONFIG_CHANGED_EVENT, change) {
+                warn!("failed to emit config change: {err}");
+            }
+        }
+    });
+}
@@ -0,0 +1,179 @@
+use std::ops::Range;
+
+/// Tokens charged per message for role and framing, on top of its content.
+const MESSAGE_OVERHEAD: usize = 4;
+/// Rough cost of one image; providers bill between a few hundred and ~1,500 depending on
+/// size, so this errs on the high side.
+pub const IMAGE_TOKENS: usize = 1_000;
+const DEFAULT_CONTEXT_TOKENS: usize = 8_192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFamily {
+    Gpt,
+    Claude,
+    Gemini,
+    /// Llama, Mistral, Qwen and the like, whose tokenizers are less compact on English.
+    Open,
+}
+
+impl ModelFamily {
+    pub fn of(model: &str) -> Self {
+        let name = base_name(model);
+        if name.starts_with("claude") {
+            Self::Claude
+        } else if name.starts_with("gemini") || name.starts_with("gemma") {
+            Self::Gemini
+        } else if name.starts_with("gpt") || is_reasoning_model(&name) {
+            Self::Gpt
+        } else {
+            Self::Open
+        }
+    }
+
+    fn chars_per_token(self) -> f64 {
+        match self {
+            Self::Gpt | Self::Gemini => 4.0,
+            Self::Claude => 3.5,
+            Self::Open => 3.2,
+        }
+    }
+}
+
+/// OpenRouter names models `vendor/model`; only the model part says anything about size.
+/// Lowercased, since names are matched case-insensitively.
+fn base_name(model: &str) -> String {
+    model
+        .rsplit('/')
+        .next()
+        .unwrap_or(model)
+        .to_ascii_lowercase()
+}
+
+fn is_reasoning_model(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next() == Some('o') && chars.next().is_some_and(|c| c.is_ascii_digit())
+}
+
+/// Estimated tokens in `text` for `model`. Deliberately a character ratio rather than a
+/// tokenizer: it only has to keep requests under the limit, not bill them.
+pub fn estimate_tokens(model: &str, text: &str) -> usize {
+    let chars = text.chars().count() as f64;
+    (chars / ModelFamily::of(model).chars_per_token()).ceil() as usize
+}
+
+pub fn message_tokens(content_tokens: usize) -> usize {
+    content_tokens + MESSAGE_OVERHEAD
+}
+
+/// Context window of `model` in tokens, from its name.
+pub fn context_limit(model: &str) -> usize {
+    let name = base_name(model);
+    let starts = |prefix: &str| name.starts_with(prefix);
+    if starts("gpt-4.1") || starts("gemini-1.5") || starts("gemini-2") {
+        1_000_000
+    } else if starts("claude") || is_reasoning_model(&name) {
+        200_000
+    } else if starts("gpt-4o") || starts("gpt-4-turbo") || starts("gpt-5") {
+        128_000
+    } else if starts("gpt-4-32k") {
+        32_768
+    } else if starts("gpt-4") {
+        8_192
+    } else if starts("gpt-3.5") {
+        16_385
+    } else if starts("gemini") {
+        32_768
+    } else if starts("llama-3.1") || starts("llama-3.2") || starts("llama-3.3") {
+        128_000
+    } else if starts("mistral") || starts("mixtral") || starts("qwen") {
+        32_768
+    } else {
+        DEFAULT_CONTEXT_TOKENS
+    }
+}
+
+/// A message as the budgeter sees it.
+#[derive(Debug, Clone, Copy)]
+pub struct BudgetItem<'a> {
+    pub role: &'a str,
+    pub tokens: usize,
+}
+
+/// The messages to drop so the rest fits in `budget`: whole turns, oldest first. Leading
+/// system messages (the system prompt and any summary) and everything from `pinned` on
+/// (the turn being answered) are always kept, so the result may still be over budget.
+pub fn overflow(items: &[BudgetItem], pinned: usize, budget: usize) -> Range<usize> {
+    let pinned = pinned.min(items.len());
+    let total: usize = items.iter().map(|item| item.tokens).sum();
+    let start = items[..pinned]
+        .iter()
+        .take_while(|item| item.role == "system")
+        .count();
+    if total <= budget {
+        return start..start;
+    }
+    let excess = total - budget;
+    let mut end = start;
+    let mut dropped = 0;
+    while dropped < excess && end < pinned {
+        // A turn runs from one user message to the next, so tool results never lose the
+        // assistant message that asked for them.
+        loop {
+            dropped += items[end].tokens;
+            end += 1;
+            if end >= pinned || items[end].role == "user" {
+                break;
+            }
+        }
+    }
+    start..end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(spec: &[(&'static str, usize)]) -> Vec<BudgetItem<'static>> {
+        spec.iter()
+            .map(|&(role, tokens)| BudgetItem { role, tokens })
+            .collect()
+    }
+
+    #[test]
+    fn knows_model_families_and_limits() {
+        assert_eq!(context_limit("gpt-4o-mini"), 128_000);
+        assert_eq!(context_limit("openai/gpt-4o"), 128_000);
+        assert_eq!(context_limit("gpt-4"), 8_192);
+        assert_eq!(context_limit("anthropic/claude-3.5-sonnet"), 200_000);
+        assert_eq!(context_limit("o3-mini"), 200_000);
+        assert_eq!(context_limit("something-local"), DEFAULT_CONTEXT_TOKENS);
+        assert_eq!(ModelFamily::of("o1"), ModelFamily::Gpt);
+        assert_eq!(ModelFamily::of("ollama/llama3"), ModelFamily::Open);
+        assert_eq!(ModelFamily::of("GPT-4o"), ModelFamily::Gpt);
+        assert_eq!(ModelFamily::of("Claude-3-Opus"), ModelFamily::Claude);
+        assert_eq!(context_limit("Claude-3-Opus"), 200_000);
+        assert_eq!(estimate_tokens("gpt-4o", "abcdefgh"), 2);
+        assert!(estimate_tokens("llama3", "abcdefgh") > 2);
+    }
+
+    #[test]
+    fn drops_whole_oldest_turns_and_keeps_the_system_prompt() {
+        let messages = items(&[
+            ("system", 10),
+            ("user", 20),
+            ("assistant", 20),
+            ("tool", 50),
+            ("assistant", 20),
+            ("user", 20),
+            ("assistant", 20),
+            ("user", 30),
+        ]);
+        let pinned = 7;
+        assert_eq!(overflow(&messages, pinned, 1_000), 1..1);
+        // Dropping the first user message alone would orphan its tool result.
+        assert_eq!(overflow(&messages, pinned, 180), 1..5);
+        assert_eq!(overflow(&messages, pinned, 60), 1..7);
+        // Nothing left to drop: the current turn and system prompt stay regardless.
+        assert_eq!(overflow(&messages, pinned, 5), 1..7);
+    }
+}
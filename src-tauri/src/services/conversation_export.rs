@@ -0,0 +1,780 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{FromRow, SqlitePool};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::services::conversations::ToolCall;
+
+/// Bumped when [`ExportedConversation`] changes incompatibly.
+pub const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("conversation `{0}` not found")]
+    NotFound(String),
+    #[error("unsupported export version {0}")]
+    UnsupportedVersion(u32),
+    #[error("file is neither an exported conversation nor a ChatGPT conversations.json")]
+    UnknownFormat,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+    Html,
+}
+
+/// A whole conversation tree, every branch included. This is the JSON export format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedConversation {
+    pub version: u32,
+    pub id: String,
+    pub title: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub active_leaf_id: Option<i64>,
+    pub messages: Vec<ExportedMessage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedMessage {
+    pub id: i64,
+    pub parent_id: Option<i64>,
+    pub role: String,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    pub tool_call_id: Option<String>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
+    pub created_at: String,
+    /// References only; the files themselves stay in the attachment store.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<AttachmentRef>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentRef {
+    pub hash: String,
+    pub name: String,
+    pub mime_type: String,
+    pub size: i64,
+}
+
+#[derive(Debug, FromRow)]
+struct ConversationRow {
+    id: String,
+    title: Option<String>,
+    created_at: String,
+    updated_at: String,
+    active_leaf_id: Option<i64>,
+}
+
+#[derive(Debug, FromRow)]
+struct MessageRow {
+    id: i64,
+    parent_id: Option<i64>,
+    role: String,
+    content: String,
+    tool_calls: Option<String>,
+    tool_call_id: Option<String>,
+    provider: Option<String>,
+    model: Option<String>,
+    reasoning: Option<String>,
+    created_at: String,
+}
+
+pub async fn load_conversation(
+    pool: &SqlitePool,
+    conversation_id: &str,
+) -> Result<ExportedConversation, ExportError> {
+    let conversation = sqlx::query_as::<_, ConversationRow>(
+        "SELECT id, title, created_at, updated_at, active_leaf_id FROM conversations WHERE id = ?1",
+    )
+    .bind(conversation_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| ExportError::NotFound(conversation_id.to_string()))?;
+    let rows = sqlx::query_as::<_, MessageRow>(
+        r#"
+        SELECT id, parent_id, role, content, tool_calls, tool_call_id, provider, model, reasoning,
+               created_at
+        FROM messages
+        WHERE conversation_id = ?1
+        ORDER BY id ASC
+      "#,
+    )
+    .bind(conversation_id)
+    .fetch_all(pool)
+    .await?;
+
+    #[derive(FromRow)]
+    struct LinkedAttachment {
+        message_id: i64,
+        #[sqlx(flatten)]
+        attachment: AttachmentRef,
+    }
+    let mut attachments: HashMap<i64, Vec<AttachmentRef>> = HashMap::new();
+    for linked in sqlx::query_as::<_, LinkedAttachment>(
+        r#"
+        SELECT ma.message_id, ma.hash, ma.name, a.mime_type, a.size
+        FROM message_attachments ma
+        JOIN attachments a ON a.hash = ma.hash
+        JOIN messages m ON m.id = ma.message_id
+        WHERE m.conversation_id = ?1
+        ORDER BY ma.message_id, ma.position
+      "#,
+    )
+    .bind(conversation_id)
+    .fetch_all(pool)
+    .await?
+    {
+        attachments
+            .entry(linked.message_id)
+            .or_default()
+            .push(linked.attachment);
+    }
+
+    Ok(ExportedConversation {
+        version: EXPORT_VERSION,
+        id: conversation.id,
+        title: conversation.title,
+        created_at: conversation.created_at,
+        updated_at: conversation.updated_at,
+        active_leaf_id: conversation.active_leaf_id,
+        messages: rows
+            .into_iter()
+            .map(|row| ExportedMessage {
+                attachments: attachments.remove(&row.id).unwrap_or_default(),
+                id: row.id,
+                parent_id: row.parent_id,
+                role: row.role,
+                content: row.content,
+                tool_calls: row
+                    .tool_calls
+                    .and_then(|raw| serde_json::from_str(&raw).ok())
+                    .unwrap_or_default(),
+                tool_call_id: row.tool_call_id,
+                provider: row.provider,
+                model: row.model,
+                reasoning: row.reasoning,
+                created_at: row.created_at,
+            })
+            .collect(),
+    })
+}
+
+pub async fn export_conversation(
+    pool: &SqlitePool,
+    conversation_id: &str,
+    format: ExportFormat,
+) -> Result<String, ExportError> {
+    let conversation = load_conversation(pool, conversation_id).await?;
+    Ok(match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&conversation)?,
+        ExportFormat::Markdown => render_markdown(&conversation),
+        ExportFormat::Html => render_html(&conversation),
+    })
+}
+
+impl ExportedConversation {
+    /// The messages of the active branch, root first; what Markdown and HTML show.
+    pub fn active_branch(&self) -> Vec<&ExportedMessage> {
+        let by_id: HashMap<i64, &ExportedMessage> = self
+            .messages
+            .iter()
+            .map(|message| (message.id, message))
+            .collect();
+        let mut branch = Vec::new();
+        let mut next = self.active_leaf_id;
+        while let Some(message) = next.and_then(|id| by_id.get(&id)) {
+            branch.push(*message);
+            next = message.parent_id;
+        }
+        branch.reverse();
+        branch
+    }
+
+    fn display_title(&self) -> &str {
+        self.title
+            .as_deref()
+            .filter(|title| !title.trim().is_empty())
+            .unwrap_or("Conversation")
+    }
+}
+
+fn heading(message: &ExportedMessage) -> String {
+    let role = match message.role.as_str() {
+        "user" => "User",
+        "assistant" => "Assistant",
+        "system" => "System",
+        "tool" => "Tool result",
+        other => other,
+    };
+    match &message.model {
+        Some(model) if message.role == "assistant" => {
+            format!("{role} ({model}) · {}", message.created_at)
+        }
+        _ => format!("{role} · {}", message.created_at),
+    }
+}
+
+/// A code fence longer than any backtick run inside `text`, so the block cannot end early.
+fn fence(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+pub fn render_markdown(conversation: &ExportedConversation) -> String {
+    let mut out = format!(
+        "# {}\n\n_Started {}, last updated {}_\n",
+        conversation.display_title(),
+        conversation.created_at,
+        conversation.updated_at
+    );
+    for message in conversation.active_branch() {
+        out.push_str(&format!("\n## {}\n\n", heading(message)));
+        if let Some(reasoning) = &message.reasoning {
+            out.push_str(&format!(
+                "<details>\n<summary>Reasoning</summary>\n\n{reasoning}\n\n</details>\n\n"
+            ));
+        }
+        if message.role == "tool" {
+            let fence = fence(&message.content);
+            out.push_str(&format!("{fence}\n{}\n{fence}\n", message.content));
+        } else if !message.content.is_empty() {
+            out.push_str(&message.content);
+            out.push('\n');
+        }
+        for call in &message.tool_calls {
+            let fence = fence(&call.arguments);
+            out.push_str(&format!(
+                "\n**Tool call** `{}`\n\n{fence}json\n{}\n{fence}\n",
+                call.name, call.arguments
+            ));
+        }
+        for attachment in &message.attachments {
+            out.push_str(&format!(
+                "\n> Attachment: {} ({}, {} bytes, sha256 {})\n",
+                attachment.name, attachment.mime_type, attachment.size, attachment.hash
+            ));
+        }
+    }
+    out
+}
+
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:48rem;margin:2rem auto;\
+padding:0 1rem;color:#1f2328;line-height:1.5}header p,.meta{color:#59636e;font-size:.85rem}\
+section{border-top:1px solid #d1d9e0;padding:.75rem 0}.content{white-space:pre-wrap}\
+pre{background:#f6f8fa;padding:.75rem;overflow-x:auto;white-space:pre-wrap}\
+.user .role{color:#0969da}.assistant .role{color:#8250df}";
+
+/// A self-contained page: inline styles, no scripts, no external resources.
+pub fn render_html(conversation: &ExportedConversation) -> String {
+    let title = escape_html(conversation.display_title());
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n\
+         <header><h1>{title}</h1><p>Started {}, last updated {}</p></header>\n",
+        escape_html(&conversation.created_at),
+        escape_html(&conversation.updated_at)
+    );
+    for message in conversation.active_branch() {
+        out.push_str(&format!(
+            "<section class=\"{}\">\n<div class=\"meta\"><span class=\"role\">{}</span></div>\n",
+            escape_html(&message.role),
+            escape_html(&heading(message))
+        ));
+        if let Some(reasoning) = &message.reasoning {
+            out.push_str(&format!(
+                "<details><summary>Reasoning</summary><div class=\"content\">{}</div></details>\n",
+                escape_html(reasoning)
+            ));
+        }
+        if message.role == "tool" {
+            out.push_str(&format!("<pre>{}</pre>\n", escape_html(&message.content)));
+        } else if !message.content.is_empty() {
+            out.push_str(&format!(
+                "<div class=\"content\">{}</div>\n",
+                escape_html(&message.content)
+            ));
+        }
+        for call in &message.tool_calls {
+            out.push_str(&format!(
+                "<p><strong>Tool call</strong> <code>{}</code></p>\n<pre>{}</pre>\n",
+                escape_html(&call.name),
+                escape_html(&call.arguments)
+            ));
+        }
+        for attachment in &message.attachments {
+            out.push_str(&format!(
+                "<p class=\"meta\">Attachment: {} ({}, {} bytes)</p>\n",
+                escape_html(&attachment.name),
+                escape_html(&attachment.mime_type),
+                attachment.size
+            ));
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut html = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+/// Imports conversations from our JSON export or a ChatGPT `conversations.json`. Every
+/// import gets a fresh id so nothing existing is overwritten. Returns the new ids.
+pub async fn import_conversations(
+    pool: &SqlitePool,
+    contents: &str,
+) -> Result<Vec<String>, ExportError> {
+    let value: Value = serde_json::from_str(contents)?;
+    let conversations = if value.get("version").is_some() && value.get("messages").is_some() {
+        let conversation: ExportedConversation = serde_json::from_value(value)?;
+        if conversation.version > EXPORT_VERSION {
+            return Err(ExportError::UnsupportedVersion(conversation.version));
+        }
+        vec![conversation]
+    } else if let Value::Array(items) = value {
+        items
+            .into_iter()
+            .map(from_chatgpt)
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        return Err(ExportError::UnknownFormat);
+    };
+    let mut ids = Vec::with_capacity(conversations.len());
+    for conversation in conversations {
+        ids.push(insert_conversation(pool, conversation).await?);
+    }
+    Ok(ids)
+}
+
+async fn insert_conversation(
+    pool: &SqlitePool,
+    mut conversation: ExportedConversation,
+) -> Result<String, ExportError> {
+    let id = Uuid::new_v4().to_string();
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "INSERT INTO conversations (id, title, created_at, updated_at) VALUES (?1, ?2, ?3, ?4)",
+    )
+    .bind(&id)
+    .bind(&conversation.title)
+    .bind(&conversation.created_at)
+    .bind(&conversation.updated_at)
+    .execute(&mut *tx)
+    .await?;
+    // Parents are always older than their children, so id order inserts them first.
+    conversation.messages.sort_by_key(|message| message.id);
+    let mut new_ids: HashMap<i64, i64> = HashMap::new();
+    for message in &conversation.messages {
+        let tool_calls = if message.tool_calls.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&message.tool_calls)?)
+        };
+        let new_id = sqlx::query(
+            r#"
+        INSERT INTO messages
+          (conversation_id, parent_id, role, content, tool_calls, tool_call_id, provider, model,
+           reasoning, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+      "#,
+        )
+        .bind(&id)
+        .bind(message.parent_id.and_then(|parent| new_ids.get(&parent)))
+        .bind(&message.role)
+        .bind(&message.content)
+        .bind(tool_calls)
+        .bind(&message.tool_call_id)
+        .bind(&message.provider)
+        .bind(&message.model)
+        .bind(&message.reasoning)
+        .bind(&message.created_at)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+        new_ids.insert(message.id, new_id);
+        // Only files this install already has can be linked again.
+        for (position, attachment) in message.attachments.iter().enumerate() {
+            sqlx::query(
+                r#"
+        INSERT INTO message_attachments (message_id, position, hash, name)
+        SELECT ?1, ?2, hash, ?4 FROM attachments WHERE hash = ?3
+      "#,
+            )
+            .bind(new_id)
+            .bind(position as i64)
+            .bind(&attachment.hash)
+            .bind(&attachment.name)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    let leaf = conversation
+        .active_leaf_id
+        .and_then(|leaf| new_ids.get(&leaf).copied())
+        .or_else(|| new_ids.values().max().copied());
+    sqlx::query("UPDATE conversations SET active_leaf_id = ?2 WHERE id = ?1")
+        .bind(&id)
+        .bind(leaf)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+/// Converts one conversation of a ChatGPT data export. Its `mapping` is a tree of nodes
+/// keyed by string ids; hidden system and tool nodes are skipped and their children hang
+/// off the nearest kept ancestor.
+fn from_chatgpt(item: Value) -> Result<ExportedConversation, ExportError> {
+    let mapping = item
+        .get("mapping")
+        .and_then(Value::as_object)
+        .ok_or(ExportError::UnknownFormat)?;
+    let created_at = timestamp(item.get("create_time"));
+    let mut conversation = ExportedConversation {
+        version: EXPORT_VERSION,
+        id: String::new(),
+        title: item
+            .get("title")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        updated_at: item
+            .get("update_time")
+            .map_or_else(|| created_at.clone(), |time| timestamp(Some(time))),
+        created_at,
+        active_leaf_id: None,
+        messages: Vec::new(),
+    };
+
+    // Walk from the roots so every parent gets its number before its children. Nodes whose
+    // parent is missing start a tree of their own, and nodes seen before are skipped so a
+    // malformed file with cyclic children cannot loop.
+    let mut numbers: HashMap<&str, Option<i64>> = HashMap::new();
+    let mut pending: Vec<(&str, Option<i64>)> = mapping
+        .iter()
+        .filter(|(_, node)| {
+            node.get("parent")
+                .and_then(Value::as_str)
+                .map_or(true, |parent| !mapping.contains_key(parent))
+        })
+        .map(|(key, _)| (key.as_str(), None))
+        .collect();
+    while let Some((key, parent)) = pending.pop() {
+        if numbers.contains_key(key) {
+            continue;
+        }
+        let Some(node) = mapping.get(key) else {
+            continue;
+        };
+        let kept = node.get("message").and_then(|message| {
+            let role = message.pointer("/author/role")?.as_str()?;
+            let content = chatgpt_text(message.get("content")?);
+            if !matches!(role, "user" | "assistant") || content.trim().is_empty() {
+                return None;
+            }
+            Some(ExportedMessage {
+                id: conversation.messages.len() as i64 + 1,
+                parent_id: parent,
+                role: role.to_string(),
+                content,
+                tool_calls: Vec::new(),
+                tool_call_id: None,
+                provider: Some("openai".into()),
+                model: message
+                    .pointer("/metadata/model_slug")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                reasoning: None,
+                created_at: message
+                    .get("create_time")
+                    .filter(|time| !time.is_null())
+                    .map_or_else(
+                        || conversation.created_at.clone(),
+                        |time| timestamp(Some(time)),
+                    ),
+                attachments: Vec::new(),
+            })
+        });
+        let number = match kept {
+            Some(message) => {
+                let id = message.id;
+                conversation.messages.push(message);
+                Some(id)
+            }
+            None => parent,
+        };
+        numbers.insert(key, number);
+        for child in node
+            .get("children")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .rev()
+        {
+            pending.push((child, number));
+        }
+    }
+    conversation.active_leaf_id = item
+        .get("current_node")
+        .and_then(Value::as_str)
+        .and_then(|node| numbers.get(node).copied().flatten());
+    Ok(conversation)
+}
+
+/// Text of a ChatGPT message. Non-text parts (images, audio) become placeholders.
+fn chatgpt_text(content: &Value) -> String {
+    if let Some(text) = content.get("text").and_then(Value::as_str) {
+        return text.to_string();
+    }
+    content
+        .get("parts")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|part| match part {
+            Value::String(text) => text.clone(),
+            other => format!(
+                "[{}]",
+                other
+                    .get("content_type")
+                    .and_then(Value::as_str)
+                    .unwrap_or("attachment")
+            ),
+        })
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Unix seconds (as ChatGPT stores them) to SQLite's `YYYY-MM-DD HH:MM:SS`.
+fn timestamp(value: Option<&Value>) -> String {
+    let seconds = value.and_then(Value::as_f64).unwrap_or(0.0) as i64;
+    let days = seconds.div_euclid(86_400);
+    let secs = seconds.rem_euclid(86_400);
+    // Howard Hinnant's days-from-civil inverse.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::config::{paths::ConfigPaths, service::ConfigService};
+    use crate::services::conversations::{ConversationStore, NewMessage};
+
+    #[tokio::test]
+    async fn exports_and_round_trips_conversations() {
+        let temp_dir = tempdir().unwrap();
+        let config =
+            ConfigService::with_paths(ConfigPaths::from_base_dir(temp_dir.path()).unwrap())
+                .await
+                .unwrap();
+        let pool = config.pool();
+        let store = ConversationStore::new(pool.clone());
+        store.ensure("c").await.unwrap();
+        store.set_title("c", "Weather <script>").await.unwrap();
+        let question = store
+            .append(
+                "c",
+                NewMessage {
+                    role: "user",
+                    content: "Weather in Oslo?",
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        let call = ToolCall {
+            id: "call_1".into(),
+            name: "weather__forecast".into(),
+            arguments: r#"{"city":"Oslo"}"#.into(),
+        };
+        for message in [
+            NewMessage {
+                role: "assistant",
+                tool_calls: std::slice::from_ref(&call),
+                model: Some("gpt-4o"),
+                ..Default::default()
+            },
+            NewMessage {
+                role: "tool",
+                content: "```\n4°C, rain\n```",
+                tool_call_id: Some("call_1"),
+                ..Default::default()
+            },
+            NewMessage {
+                role: "assistant",
+                content: "Rainy, 4°C.",
+                model: Some("gpt-4o"),
+                reasoning: Some("The tool says rain."),
+                ..Default::default()
+            },
+        ] {
+            store.append("c", message).await.unwrap();
+        }
+        // An abandoned branch: exported in JSON, hidden in Markdown and HTML.
+        store.set_active_leaf("c", Some(question)).await.unwrap();
+        store
+            .append(
+                "c",
+                NewMessage {
+                    role: "assistant",
+                    content: "Sunny!",
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        store.switch_branch(question + 1).await.unwrap();
+
+        let markdown = export_conversation(pool, "c", ExportFormat::Markdown)
+            .await
+            .unwrap();
+        assert!(markdown.starts_with("# Weather <script>"));
+        assert!(markdown.contains("## Assistant (gpt-4o) · "));
+        assert!(markdown.contains("**Tool call** `weather__forecast`"));
+        assert!(markdown.contains("````\n```\n4°C"), "{markdown}");
+        assert!(!markdown.contains("Sunny"));
+
+        let html = export_conversation(pool, "c", ExportFormat::Html)
+            .await
+            .unwrap();
+        assert!(html.contains("<title>Weather &lt;script&gt;</title>"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<summary>Reasoning</summary>"));
+        assert!(!html.contains("Sunny"));
+
+        let json = export_conversation(pool, "c", ExportFormat::Json)
+            .await
+            .unwrap();
+        let ids = import_conversations(pool, &json).await.unwrap();
+        assert_eq!(ids.len(), 1);
+        let imported = load_conversation(pool, &ids[0]).await.unwrap();
+        let original = load_conversation(pool, "c").await.unwrap();
+        assert_eq!(imported.messages.len(), 5);
+        assert_eq!(imported.title, original.title);
+        assert_eq!(imported.messages[1].tool_calls[0].name, "weather__forecast");
+        assert_eq!(
+            imported.messages[3].reasoning.as_deref(),
+            Some("The tool says rain.")
+        );
+        let contents = |conversation: &ExportedConversation| {
+            conversation
+                .active_branch()
+                .iter()
+                .map(|message| message.content.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(contents(&imported), contents(&original));
+        assert_eq!(render_markdown(&imported), render_markdown(&original));
+    }
+
+    #[tokio::test]
+    async fn imports_chatgpt_exports() {
+        let temp_dir = tempdir().unwrap();
+        let config =
+            ConfigService::with_paths(ConfigPaths::from_base_dir(temp_dir.path()).unwrap())
+                .await
+                .unwrap();
+        let node = |id: &str, parent: Option<&str>, children: &[&str], message: Value| json!({ "id": id, "parent": parent, "children": children, "message": message });
+        let message = |role: &str, text: &str| {
+            json!({
+                "author": { "role": role },
+                "content": { "content_type": "text", "parts": [text] },
+                "create_time": 1_700_000_000.5,
+                "metadata": { "model_slug": "gpt-4" },
+            })
+        };
+        let export = json!([{
+            "title": "Trip",
+            "create_time": 1_700_000_000.0,
+            "update_time": 1_700_000_100.0,
+            "current_node": "a2",
+            "mapping": {
+                "root": node("root", None, &["sys"], Value::Null),
+                "sys": node("sys", Some("root"), &["u1"], message("system", "")),
+                "u1": node("u1", Some("sys"), &["a1", "a2"], message("user", "Plan a trip")),
+                "a1": node("a1", Some("u1"), &[], message("assistant", "Go to Rome")),
+                "a2": node("a2", Some("u1"), &[], message("assistant", "Go to Lisbon")),
+            },
+        }]);
+        let ids = import_conversations(config.pool(), &export.to_string())
+            .await
+            .unwrap();
+        let conversation = load_conversation(config.pool(), &ids[0]).await.unwrap();
+        assert_eq!(conversation.title.as_deref(), Some("Trip"));
+        assert_eq!(conversation.created_at, "2023-11-14 22:13:20");
+        assert_eq!(conversation.messages.len(), 3);
+        let branch = conversation.active_branch();
+        assert_eq!(branch.len(), 2);
+        assert_eq!(branch[1].content, "Go to Lisbon");
+        assert_eq!(branch[1].model.as_deref(), Some("gpt-4"));
+
+        // Cyclic children end the walk; a node with a missing parent becomes a root.
+        let malformed = from_chatgpt(json!({
+            "mapping": {
+                "u1": node("u1", None, &["a1"], message("user", "Hi")),
+                "a1": node("a1", Some("u1"), &["u1"], message("assistant", "Hello")),
+                "lost": node("lost", Some("gone"), &[], message("user", "Still here")),
+            },
+        }))
+        .unwrap();
+        assert_eq!(malformed.messages.len(), 3);
+        assert!(malformed
+            .messages
+            .iter()
+            .any(|message| message.content == "Still here" && message.parent_id.is_none()));
+
+        assert!(matches!(
+            import_conversations(config.pool(), "{\"hello\": 1}").await,
+            Err(ExportError::UnknownFormat)
+        ));
+    }
+}
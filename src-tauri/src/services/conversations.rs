@@ -0,0 +1,411 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+
+/// A tool invocation requested by the model, in provider-neutral form.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    /// JSON-encoded arguments exactly as produced by the model.
+    pub arguments: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredMessage {
+    pub id: i64,
+    pub parent_id: Option<i64>,
+    pub role: String,
+    pub content: String,
+    pub tool_calls: Vec<ToolCall>,
+    pub tool_call_id: Option<String>,
+    pub model: Option<String>,
+    /// The model's thinking, kept apart from `content`.
+    pub reasoning: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+struct MessageRow {
+    id: i64,
+    parent_id: Option<i64>,
+    role: String,
+    content: String,
+    tool_calls: Option<String>,
+    tool_call_id: Option<String>,
+    model: Option<String>,
+    reasoning: Option<String>,
+}
+
+impl From<MessageRow> for StoredMessage {
+    fn from(row: MessageRow) -> Self {
+        Self {
+            id: row.id,
+            parent_id: row.parent_id,
+            role: row.role,
+            content: row.content,
+            tool_calls: row
+                .tool_calls
+                .and_then(|raw| serde_json::from_str(&raw).ok())
+                .unwrap_or_default(),
+            tool_call_id: row.tool_call_id,
+            model: row.model,
+            reasoning: row.reasoning,
+        }
+    }
+}
+
+/// Where a message sits in the conversation tree.
+#[derive(Debug, Clone, FromRow)]
+pub struct MessageNode {
+    pub id: i64,
+    pub conversation_id: String,
+    pub parent_id: Option<i64>,
+    pub role: String,
+}
+
+/// A message on the active branch, with the alternatives the user can switch to.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchMessage {
+    #[serde(flatten)]
+    pub message: StoredMessage,
+    /// Ids of every message sharing this one's parent, itself included, oldest first.
+    pub sibling_ids: Vec<i64>,
+}
+
+/// A compacted prefix of a conversation: everything up to and including `through_id`.
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationSummary {
+    pub conversation_id: String,
+    pub summary: String,
+    pub through_id: i64,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Default)]
+pub struct NewMessage<'a> {
+    pub role: &'a str,
+    pub content: &'a str,
+    pub tool_calls: &'a [ToolCall],
+    pub tool_call_id: Option<&'a str>,
+    pub provider: Option<&'a str>,
+    pub model: Option<&'a str>,
+    pub reasoning: Option<&'a str>,
+}
+
+/// Data access for the `conversations` and `messages` tables.
+#[derive(Clone)]
+pub struct ConversationStore {
+    pool: SqlitePool,
+}
+
+impl ConversationStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn ensure(&self, conversation_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO conversations (id) VALUES (?1) ON CONFLICT(id) DO NOTHING")
+            .bind(conversation_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn title(&self, conversation_id: &str) -> Result<Option<String>, sqlx::Error> {
+        let title: Option<Option<String>> =
+            sqlx::query_scalar("SELECT title FROM conversations WHERE id = ?1")
+                .bind(conversation_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(title.flatten())
+    }
+
+    pub async fn set_title(&self, conversation_id: &str, title: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE conversations SET title = ?2 WHERE id = ?1")
+            .bind(conversation_id)
+            .bind(title)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Messages of the active branch, root first. A child is always newer than its parent,
+    /// so id order is path order.
+    pub async fn history(&self, conversation_id: &str) -> Result<Vec<StoredMessage>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, MessageRow>(
+            r#"
+        WITH RECURSIVE path (id) AS (
+          SELECT active_leaf_id FROM conversations WHERE id = ?1
+          UNION ALL
+          SELECT m.parent_id FROM messages m JOIN path ON m.id = path.id
+          WHERE m.parent_id IS NOT NULL
+        )
+        SELECT id, parent_id, role, content, tool_calls, tool_call_id, model, reasoning
+        FROM messages
+        WHERE id IN (SELECT id FROM path)
+        ORDER BY id ASC
+      "#,
+        )
+        .bind(conversation_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    /// Adds a message under the active leaf and makes it the new leaf.
+    pub async fn append(
+        &self,
+        conversation_id: &str,
+        message: NewMessage<'_>,
+    ) -> Result<i64, sqlx::Error> {
+        let tool_calls = if message.tool_calls.is_empty() {
+            None
+        } else {
+            serde_json::to_string(message.tool_calls).ok()
+        };
+        let mut tx = self.pool.begin().await?;
+        let id = sqlx::query(
+            r#"
+        INSERT INTO messages
+          (conversation_id, role, content, tool_calls, tool_call_id, provider, model, reasoning,
+           parent_id)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8,
+                (SELECT active_leaf_id FROM conversations WHERE id = ?1))
+      "#,
+        )
+        .bind(conversation_id)
+        .bind(message.role)
+        .bind(message.content)
+        .bind(tool_calls)
+        .bind(message.tool_call_id)
+        .bind(message.provider)
+        .bind(message.model)
+        .bind(message.reasoning)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+        sqlx::query(
+            "UPDATE conversations SET active_leaf_id = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+        )
+        .bind(conversation_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    pub async fn node(&self, message_id: i64) -> Result<MessageNode, sqlx::Error> {
+        sqlx::query_as::<_, MessageNode>(
+            "SELECT id, conversation_id, parent_id, role FROM messages WHERE id = ?1",
+        )
+        .bind(message_id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn active_leaf(&self, conversation_id: &str) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar("SELECT active_leaf_id FROM conversations WHERE id = ?1")
+            .bind(conversation_id)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Points the conversation at another branch; `None` means the next message starts a
+    /// new root.
+    pub async fn set_active_leaf(
+        &self,
+        conversation_id: &str,
+        leaf: Option<i64>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE conversations SET active_leaf_id = ?2 WHERE id = ?1")
+            .bind(conversation_id)
+            .bind(leaf)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// The user message a reply answers: `message_id` itself if it is a prompt, else its
+    /// nearest user ancestor.
+    pub async fn prompt_of(&self, message_id: i64) -> Result<MessageNode, sqlx::Error> {
+        let mut node = self.node(message_id).await?;
+        while node.role != "user" {
+            match node.parent_id {
+                Some(parent) => node = self.node(parent).await?,
+                None => return Err(sqlx::Error::RowNotFound),
+            }
+        }
+        Ok(node)
+    }
+
+    /// Makes the branch through `message_id` active, following the newest child below it
+    /// down to a leaf. Returns the conversation id.
+    pub async fn switch_branch(&self, message_id: i64) -> Result<String, sqlx::Error> {
+        let node = self.node(message_id).await?;
+        let mut leaf = node.id;
+        while let Some(child) = sqlx::query_scalar::<_, Option<i64>>(
+            "SELECT MAX(id) FROM messages WHERE parent_id = ?1",
+        )
+        .bind(leaf)
+        .fetch_one(&self.pool)
+        .await?
+        {
+            leaf = child;
+        }
+        self.set_active_leaf(&node.conversation_id, Some(leaf))
+            .await?;
+        Ok(node.conversation_id)
+    }
+
+    /// Deletes `message_id` with everything below it and makes its parent the active leaf.
+    /// Used to take back a turn that failed.
+    pub async fn discard(&self, message_id: i64) -> Result<(), sqlx::Error> {
+        let node = self.node(message_id).await?;
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM messages WHERE id = ?1")
+            .bind(message_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE conversations SET active_leaf_id = ?2 WHERE id = ?1")
+            .bind(&node.conversation_id)
+            .bind(node.parent_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+
+    /// The active branch with each message's siblings, for rendering branch switchers.
+    pub async fn branch(&self, conversation_id: &str) -> Result<Vec<BranchMessage>, sqlx::Error> {
+        let mut branch = Vec::new();
+        for message in self.history(conversation_id).await? {
+            let sibling_ids = sqlx::query_scalar(
+                r#"
+        SELECT id FROM messages
+        WHERE conversation_id = ?1 AND parent_id IS ?2
+        ORDER BY id ASC
+      "#,
+            )
+            .bind(conversation_id)
+            .bind(message.parent_id)
+            .fetch_all(&self.pool)
+            .await?;
+            branch.push(BranchMessage {
+                message,
+                sibling_ids,
+            });
+        }
+        Ok(branch)
+    }
+
+    pub async fn summary(
+        &self,
+        conversation_id: &str,
+    ) -> Result<Option<ConversationSummary>, sqlx::Error> {
+        sqlx::query_as::<_, ConversationSummary>(
+            r#"
+        SELECT conversation_id, summary, through_id, updated_at
+        FROM conversation_summaries
+        WHERE conversation_id = ?1
+      "#,
+        )
+        .bind(conversation_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Replaces the conversation's summary; it now stands in for messages up to `through_id`.
+    pub async fn save_summary(
+        &self,
+        conversation_id: &str,
+        summary: &str,
+        through_id: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+        INSERT INTO conversation_summaries (conversation_id, summary, through_id)
+        VALUES (?1, ?2, ?3)
+        ON CONFLICT(conversation_id) DO UPDATE SET
+          summary = excluded.summary,
+          through_id = excluded.through_id,
+          updated_at = CURRENT_TIMESTAMP
+      "#,
+        )
+        .bind(conversation_id)
+        .bind(summary)
+        .bind(through_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::config::{paths::ConfigPaths, service::ConfigService};
+
+    #[tokio::test]
+    async fn edits_and_regenerations_become_switchable_branches() {
+        let temp_dir = tempdir().unwrap();
+        let config =
+            ConfigService::with_paths(ConfigPaths::from_base_dir(temp_dir.path()).unwrap())
+                .await
+                .unwrap();
+        let store = ConversationStore::new(config.pool().clone());
+        store.ensure("c").await.unwrap();
+        let say = |role: &'static str, content: &'static str| NewMessage {
+            role,
+            content,
+            ..Default::default()
+        };
+        let question = store.append("c", say("user", "2+2?")).await.unwrap();
+        let first = store.append("c", say("assistant", "5")).await.unwrap();
+
+        // Regenerate: answer the same prompt again.
+        store.set_active_leaf("c", Some(question)).await.unwrap();
+        let second = store.append("c", say("assistant", "4")).await.unwrap();
+        let contents = |history: Vec<StoredMessage>| {
+            history
+                .into_iter()
+                .map(|message| message.content)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(contents(store.history("c").await.unwrap()), ["2+2?", "4"]);
+        assert_eq!(store.prompt_of(second).await.unwrap().id, question);
+
+        // Edit: a new root prompt next to the original one.
+        let edited = store.node(question).await.unwrap();
+        store.set_active_leaf("c", edited.parent_id).await.unwrap();
+        store.append("c", say("user", "3+3?")).await.unwrap();
+        store.append("c", say("assistant", "6")).await.unwrap();
+        let branch = store.branch("c").await.unwrap();
+        assert_eq!(branch[0].message.content, "3+3?");
+        assert_eq!(branch[0].sibling_ids.len(), 2);
+        assert_eq!(branch[0].sibling_ids[0], question);
+
+        // Switching follows the newest reply below the chosen message.
+        assert_eq!(store.switch_branch(question).await.unwrap(), "c");
+        assert_eq!(contents(store.history("c").await.unwrap()), ["2+2?", "4"]);
+        store.switch_branch(first).await.unwrap();
+        let branch = store.branch("c").await.unwrap();
+        assert_eq!(branch[1].message.content, "5");
+        assert_eq!(branch[1].sibling_ids, [first, second]);
+
+        // A failed turn is taken back with its replies.
+        let failed = store.append("c", say("user", "and 4+4?")).await.unwrap();
+        store.append("c", say("tool", "partial")).await.unwrap();
+        store.discard(failed).await.unwrap();
+        assert_eq!(store.active_leaf("c").await.unwrap(), Some(first));
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+            .fetch_one(config.pool())
+            .await
+            .unwrap();
+        assert_eq!(count, 5);
+    }
+}
@@ -0,0 +1,819 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{FromRow, SqlitePool};
+use thiserror::Error;
+use tracing::info;
+
+use crate::config::service::{ConfigService, SharedConfigService};
+use crate::config::ConfigError;
+use crate::services::embeddings::{
+    decode_vector, encode_vector, top_k, EmbeddingClient, EmbeddingError,
+};
+use crate::services::job_queue::{JobContext, JobQueue, JobQueueError, JobResult};
+
+/// Job queue kind that ingests files into a collection; see [`IngestRequest`].
+pub const INGEST_DOCUMENTS_JOB: &str = "documents.ingest";
+/// Characters per chunk and how many of them repeat at the start of the next chunk, so a
+/// passage cut at a boundary is still whole in one of the two.
+const CHUNK_CHARS: usize = 1_500;
+const CHUNK_OVERLAP: usize = 200;
+const EMBED_BATCH: usize = 32;
+const MAX_FILE_BYTES: u64 = 16 * 1024 * 1024;
+const MIN_SIMILARITY: f32 = 0.3;
+const PDF_TIMEOUT: Duration = Duration::from_secs(60);
+const TEXT_EXTENSIONS: &[&str] = &[
+    "txt", "md", "markdown", "rst", "org", "csv", "tsv", "json", "yaml", "yml", "toml", "ini",
+    "xml", "html", "css", "rs", "py", "js", "jsx", "ts", "tsx", "go", "java", "kt", "c", "h",
+    "cpp", "hpp", "cs", "rb", "php", "swift", "sh", "sql", "lua",
+];
+/// Dependency and build directories that are never worth indexing.
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "dist", "build", "__pycache__"];
+
+#[derive(Debug, Error)]
+pub enum DocumentError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("{0}")]
+    Config(#[from] ConfigError),
+    #[error("{0}")]
+    Embedding(#[from] EmbeddingError),
+    #[error("{0}")]
+    Queue(#[from] JobQueueError),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("collection {0} not found")]
+    CollectionNotFound(i64),
+    #[error("a collection named `{0}` already exists")]
+    CollectionExists(String),
+    #[error("collection name is empty")]
+    EmptyName,
+    #[error("path `{0}` does not exist")]
+    PathNotFound(String),
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentCollection {
+    pub id: i64,
+    pub name: String,
+    pub files: i64,
+    pub chunks: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentFile {
+    pub id: i64,
+    pub path: String,
+    /// Hex SHA-256 of the contents when last indexed.
+    pub hash: String,
+    pub size: i64,
+    pub chunks: i64,
+    pub indexed_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentHit {
+    pub file_id: i64,
+    pub path: String,
+    pub chunk_index: i64,
+    pub content: String,
+    #[sqlx(skip)]
+    pub score: f32,
+}
+
+#[derive(Debug, FromRow)]
+struct Candidate {
+    #[sqlx(flatten)]
+    hit: DocumentHit,
+    embedding: Vec<u8>,
+}
+
+/// Payload of an [`INGEST_DOCUMENTS_JOB`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestRequest {
+    pub collection_id: i64,
+    /// Files or folders; folders are walked recursively.
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IngestReport {
+    /// Files chunked and embedded in this run, new or changed.
+    pub indexed: usize,
+    pub unchanged: usize,
+    /// Files gone from disk whose chunks were dropped.
+    pub removed: usize,
+    pub chunks: usize,
+    pub skipped: Vec<SkippedFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, FromRow)]
+struct IndexedFile {
+    id: i64,
+    path: String,
+    hash: String,
+    model: String,
+}
+
+/// Collections of local files, chunked and embedded for retrieval.
+#[derive(Clone)]
+pub struct DocumentStore {
+    pool: SqlitePool,
+}
+
+impl DocumentStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create_collection(&self, name: &str) -> Result<DocumentCollection, DocumentError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(DocumentError::EmptyName);
+        }
+        let id = sqlx::query("INSERT INTO document_collections (name) VALUES (?1)")
+            .bind(name)
+            .execute(&self.pool)
+            .await
+            .map_err(|err| match &err {
+                sqlx::Error::Database(db) if db.is_unique_violation() => {
+                    DocumentError::CollectionExists(name.to_string())
+                }
+                _ => err.into(),
+            })?
+            .last_insert_rowid();
+        self.collection(id).await
+    }
+
+    pub async fn collection(&self, id: i64) -> Result<DocumentCollection, DocumentError> {
+        sqlx::query_as::<_, DocumentCollection>(&collections_query("WHERE c.id = ?1"))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(DocumentError::CollectionNotFound(id))
+    }
+
+    pub async fn list_collections(&self) -> Result<Vec<DocumentCollection>, DocumentError> {
+        Ok(
+            sqlx::query_as::<_, DocumentCollection>(&collections_query("ORDER BY c.name"))
+                .fetch_all(&self.pool)
+                .await?,
+        )
+    }
+
+    pub async fn delete_collection(&self, id: i64) -> Result<(), DocumentError> {
+        let deleted = sqlx::query("DELETE FROM document_collections WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if deleted.rows_affected() == 0 {
+            return Err(DocumentError::CollectionNotFound(id));
+        }
+        Ok(())
+    }
+
+    pub async fn list_files(&self, collection_id: i64) -> Result<Vec<DocumentFile>, DocumentError> {
+        self.collection(collection_id).await?;
+        Ok(sqlx::query_as::<_, DocumentFile>(
+            r#"
+        SELECT id, path, hash, size, chunks, indexed_at
+        FROM document_files
+        WHERE collection_id = ?1
+        ORDER BY path
+      "#,
+        )
+        .bind(collection_id)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Brings the collection in line with `paths`: new files and files whose contents or
+    /// embedding model changed are (re)embedded, unchanged ones are left alone, and files
+    /// under a folder in `paths` that no longer exist are dropped. `progress` receives the
+    /// number of files looked at so far and the total.
+    pub async fn ingest<F, Fut>(
+        &self,
+        client: &EmbeddingClient,
+        collection_id: i64,
+        paths: &[PathBuf],
+        mut progress: F,
+    ) -> Result<IngestReport, DocumentError>
+    where
+        F: FnMut(usize, usize) -> Fut,
+        Fut: std::future::Future<Output = ()>,
+    {
+        self.collection(collection_id).await?;
+        let model = client.model_key();
+        let mut roots = Vec::with_capacity(paths.len());
+        for path in paths {
+            roots.push(
+                tokio::fs::canonicalize(path)
+                    .await
+                    .map_err(|_| DocumentError::PathNotFound(path.display().to_string()))?,
+            );
+        }
+        let mut report = IngestReport::default();
+        let files = collect_files(&roots, &mut report.skipped).await?;
+        let mut seen = HashSet::new();
+        for (done, file) in files.iter().enumerate() {
+            let path = file.display().to_string();
+            seen.insert(path.clone());
+            match self.ingest_file(client, &model, collection_id, file).await {
+                Ok(Some(chunks)) => {
+                    report.indexed += 1;
+                    report.chunks += chunks;
+                }
+                Ok(None) => report.unchanged += 1,
+                Err(FileOutcome::Skipped(reason)) => {
+                    // A file that can no longer be read must not keep its old chunks.
+                    let removed = sqlx::query(
+                        "DELETE FROM document_files WHERE collection_id = ?1 AND path = ?2",
+                    )
+                    .bind(collection_id)
+                    .bind(&path)
+                    .execute(&self.pool)
+                    .await?
+                    .rows_affected();
+                    report.removed += removed as usize;
+                    report.skipped.push(SkippedFile { path, reason })
+                }
+                Err(FileOutcome::Failed(err)) => return Err(err),
+            }
+            progress(done + 1, files.len()).await;
+        }
+
+        let indexed = sqlx::query_as::<_, IndexedFile>(
+            "SELECT id, path, hash, model FROM document_files WHERE collection_id = ?1",
+        )
+        .bind(collection_id)
+        .fetch_all(&self.pool)
+        .await?;
+        for file in indexed {
+            let under_root = roots
+                .iter()
+                .any(|root| Path::new(&file.path).starts_with(root));
+            if under_root && !seen.contains(&file.path) {
+                sqlx::query("DELETE FROM document_files WHERE id = ?1")
+                    .bind(file.id)
+                    .execute(&self.pool)
+                    .await?;
+                report.removed += 1;
+            }
+        }
+        sqlx::query("UPDATE document_collections SET updated_at = CURRENT_TIMESTAMP WHERE id = ?1")
+            .bind(collection_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(report)
+    }
+
+    /// Returns the number of chunks stored, or `None` when the file was already indexed
+    /// with the same contents and model.
+    async fn ingest_file(
+        &self,
+        client: &EmbeddingClient,
+        model: &str,
+        collection_id: i64,
+        file: &Path,
+    ) -> Result<Option<usize>, FileOutcome> {
+        let path = file.display().to_string();
+        let size = tokio::fs::metadata(file).await.map_err(skipped)?.len();
+        if size > MAX_FILE_BYTES {
+            return Err(FileOutcome::Skipped(format!(
+                "larger than {} MiB",
+                MAX_FILE_BYTES / (1024 * 1024)
+            )));
+        }
+        let bytes = tokio::fs::read(file).await.map_err(skipped)?;
+        let hash = hex_digest(&bytes);
+        let existing = sqlx::query_as::<_, IndexedFile>(
+            "SELECT id, path, hash, model FROM document_files WHERE collection_id = ?1 AND path = ?2",
+        )
+        .bind(collection_id)
+        .bind(&path)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(failed)?;
+        if existing
+            .as_ref()
+            .is_some_and(|row| row.hash == hash && row.model == model)
+        {
+            return Ok(None);
+        }
+
+        let text = extract_text(file, bytes)
+            .await
+            .map_err(FileOutcome::Skipped)?;
+        let chunks = chunk_text(&text, CHUNK_CHARS, CHUNK_OVERLAP);
+        let mut vectors = Vec::with_capacity(chunks.len());
+        for batch in chunks.chunks(EMBED_BATCH) {
+            vectors.extend(client.embed(batch).await.map_err(failed)?);
+        }
+
+        let mut tx = self.pool.begin().await.map_err(failed)?;
+        if let Some(row) = existing {
+            sqlx::query("DELETE FROM document_files WHERE id = ?1")
+                .bind(row.id)
+                .execute(&mut *tx)
+                .await
+                .map_err(failed)?;
+        }
+        let file_id = sqlx::query(
+            r#"
+        INSERT INTO document_files (collection_id, path, hash, size, model, chunks)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+      "#,
+        )
+        .bind(collection_id)
+        .bind(&path)
+        .bind(&hash)
+        .bind(size as i64)
+        .bind(model)
+        .bind(chunks.len() as i64)
+        .execute(&mut *tx)
+        .await
+        .map_err(failed)?
+        .last_insert_rowid();
+        for (index, (chunk, vector)) in chunks.iter().zip(&vectors).enumerate() {
+            sqlx::query(
+                r#"
+        INSERT INTO document_chunks
+          (file_id, collection_id, chunk_index, content, dimensions, embedding)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+      "#,
+            )
+            .bind(file_id)
+            .bind(collection_id)
+            .bind(index as i64)
+            .bind(chunk)
+            .bind(vector.len() as i64)
+            .bind(encode_vector(vector))
+            .execute(&mut *tx)
+            .await
+            .map_err(failed)?;
+        }
+        tx.commit().await.map_err(failed)?;
+        Ok(Some(chunks.len()))
+    }
+
+    /// The `limit` chunks of the collection closest to `query`.
+    pub async fn search(
+        &self,
+        client: &EmbeddingClient,
+        collection_id: i64,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<DocumentHit>, DocumentError> {
+        self.collection(collection_id).await?;
+        let Some(query_vector) = client.embed(&[query.to_string()]).await?.pop() else {
+            return Ok(Vec::new());
+        };
+        let candidates = sqlx::query_as::<_, Candidate>(
+            r#"
+        SELECT ch.file_id, f.path, ch.chunk_index, ch.content, ch.embedding
+        FROM document_chunks ch
+        JOIN document_files f ON f.id = ch.file_id
+        WHERE ch.collection_id = ?1 AND f.model = ?2 AND ch.dimensions = ?3
+      "#,
+        )
+        .bind(collection_id)
+        .bind(client.model_key())
+        .bind(query_vector.len() as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(top_k(
+            &query_vector,
+            candidates
+                .into_iter()
+                .map(|candidate| (candidate.hit, decode_vector(&candidate.embedding))),
+            limit,
+            MIN_SIMILARITY,
+        )
+        .into_iter()
+        .map(|(hit, score)| DocumentHit { score, ..hit })
+        .collect())
+    }
+}
+
+enum FileOutcome {
+    /// Worth reporting but not worth failing the whole run over.
+    Skipped(String),
+    Failed(DocumentError),
+}
+
+fn skipped(err: std::io::Error) -> FileOutcome {
+    FileOutcome::Skipped(err.to_string())
+}
+
+fn failed(err: impl Into<DocumentError>) -> FileOutcome {
+    FileOutcome::Failed(err.into())
+}
+
+fn collections_query(tail: &str) -> String {
+    format!(
+        r#"
+        SELECT
+          c.id, c.name, c.created_at, c.updated_at,
+          (SELECT COUNT(*) FROM document_files f WHERE f.collection_id = c.id) AS files,
+          (SELECT COUNT(*) FROM document_chunks ch WHERE ch.collection_id = c.id) AS chunks
+        FROM document_collections c
+        {tail}
+      "#
+    )
+}
+
+/// Every supported file under `roots`, sorted. Hidden entries and dependency folders are
+/// not descended into; explicitly listed files of an unsupported type are reported.
+async fn collect_files(
+    roots: &[PathBuf],
+    skipped: &mut Vec<SkippedFile>,
+) -> Result<Vec<PathBuf>, DocumentError> {
+    let mut files = Vec::new();
+    let mut pending: Vec<PathBuf> = Vec::new();
+    for root in roots {
+        if root.is_dir() {
+            pending.push(root.clone());
+        } else if is_supported(root) {
+            files.push(root.clone());
+        } else {
+            skipped.push(SkippedFile {
+                path: root.display().to_string(),
+                reason: "unsupported file type".into(),
+            });
+        }
+    }
+    while let Some(dir) = pending.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with('.') {
+                continue;
+            }
+            let file_type = entry.file_type().await?;
+            let path = entry.path();
+            if file_type.is_dir() {
+                if !SKIPPED_DIRS.contains(&name.as_ref()) {
+                    pending.push(path);
+                }
+            } else if file_type.is_file() && is_supported(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+}
+
+fn is_supported(path: &Path) -> bool {
+    extension(path).is_some_and(|extension| {
+        extension == "pdf" || TEXT_EXTENSIONS.contains(&extension.as_str())
+    })
+}
+
+async fn extract_text(path: &Path, bytes: Vec<u8>) -> Result<String, String> {
+    if extension(path).as_deref() == Some("pdf") {
+        return pdf_text(bytes).await;
+    }
+    String::from_utf8(bytes).map_err(|_| "not valid UTF-8 text".to_string())
+}
+
+/// Extraction is CPU-bound and the parser panics on some malformed files, so it runs on
+/// the blocking pool where a panic only fails this file.
+async fn pdf_text(bytes: Vec<u8>) -> Result<String, String> {
+    let extract = tokio::task::spawn_blocking(move || pdf_extract::extract_text_from_mem(&bytes));
+    tokio::time::timeout(PDF_TIMEOUT, extract)
+        .await
+        .map_err(|_| "PDF text extraction timed out".to_string())?
+        .map_err(|_| "PDF could not be parsed".to_string())?
+        .map_err(|err| format!("PDF text extraction failed: {err}"))
+}
+
+pub(crate) fn hex_digest(bytes: &[u8]) -> String {
+    digest(&SHA256, bytes)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Splits `text` into chunks of at most `size` characters, each starting `overlap`
+/// characters before the previous one ended. Cuts prefer whitespace in the second half
+/// of a chunk so words stay whole.
+fn chunk_text(text: &str, size: usize, overlap: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let overlap = overlap.min(size / 2);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = (start + size).min(chars.len());
+        if end < chars.len() {
+            if let Some(space) = (start + size / 2..end)
+                .rev()
+                .find(|&index| chars[index].is_whitespace())
+            {
+                end = space;
+            }
+        }
+        let chunk: String = chars[start..end].iter().collect();
+        if !chunk.trim().is_empty() {
+            chunks.push(chunk.trim().to_string());
+        }
+        if end == chars.len() {
+            break;
+        }
+        let mut next = end.saturating_sub(overlap).max(start + 1);
+        // Start on a word rather than in the middle of one.
+        if let Some(offset) = chars[next..end].iter().position(|c| c.is_whitespace()) {
+            next += offset + 1;
+        }
+        start = next;
+    }
+    chunks
+}
+
+/// Retrieves the chunks of `collection_id` most relevant to `prompt`, as a labelled block
+/// the model can cite, or `None` when nothing in the collection is close enough.
+pub async fn ground(
+    config: &ConfigService,
+    collection_id: i64,
+    prompt: &str,
+) -> Result<Option<String>, DocumentError> {
+    let client = EmbeddingClient::from_settings(config).await?;
+    let limit: i64 = config.get_value("documents.top_k").await?.unwrap_or(6);
+    let hits = DocumentStore::new(config.pool().clone())
+        .search(&client, collection_id, prompt, limit.max(1) as usize)
+        .await?;
+    Ok(render_documents(&hits))
+}
+
+fn render_documents(hits: &[DocumentHit]) -> Option<String> {
+    if hits.is_empty() {
+        return None;
+    }
+    let mut block = String::from(
+        "Excerpts from the user's documents. Base your answer on them, cite them as [D1], \
+         [D2] and so on, and say so when they do not contain the answer.\n",
+    );
+    for (index, hit) in hits.iter().enumerate() {
+        block.push_str(&format!(
+            "\n[D{}] {} (part {}):\n{}\n",
+            index + 1,
+            hit.path,
+            hit.chunk_index + 1,
+            hit.content
+        ));
+    }
+    Some(block)
+}
+
+pub fn register_document_jobs(queue: &JobQueue, config: SharedConfigService) {
+    queue.register(INGEST_DOCUMENTS_JOB, move |context| {
+        let config = config.clone();
+        async move { ingest_job(&config, context).await }
+    });
+}
+
+async fn ingest_job(config: &ConfigService, context: JobContext) -> JobResult {
+    let request: IngestRequest =
+        serde_json::from_value(context.payload.clone()).map_err(|err| err.to_string())?;
+    let client = EmbeddingClient::from_settings(config)
+        .await
+        .map_err(|err| err.to_string())?;
+    let paths: Vec<PathBuf> = request.paths.iter().map(PathBuf::from).collect();
+    let report = DocumentStore::new(config.pool().clone())
+        .ingest(&client, request.collection_id, &paths, |done, total| {
+            let context = &context;
+            async move {
+                context
+                    .progress(
+                        done as f64 / total.max(1) as f64,
+                        format!("processed {done} of {total} files"),
+                    )
+                    .await
+            }
+        })
+        .await
+        .map_err(|err| err.to_string())?;
+    info!(
+        collection = request.collection_id,
+        indexed = report.indexed,
+        removed = report.removed,
+        "document collection updated"
+    );
+    Ok(json!(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::config::paths::ConfigPaths;
+    use crate::services::embeddings::{fake_embeddings_server, EmbeddingProvider};
+
+    #[test]
+    fn chunks_overlap_without_splitting_words() {
+        let text = "alpha beta gamma delta epsilon zeta eta theta iota kappa";
+        let chunks = chunk_text(text, 20, 8);
+        assert!(chunks.len() > 2);
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 20));
+        let words: HashSet<&str> = text.split(' ').collect();
+        for chunk in &chunks {
+            assert!(chunk.split(' ').all(|word| words.contains(word)), "{chunk}");
+        }
+        for pair in chunks.windows(2) {
+            let last_word = pair[0].rsplit(' ').next().unwrap();
+            assert!(pair[1].contains(last_word), "{pair:?} share no text");
+        }
+        assert_eq!(chunk_text("short", 20, 8), ["short"]);
+        assert!(chunk_text("   ", 20, 8).is_empty());
+    }
+
+    /// A one-page PDF showing `text` in a standard font, with a correct xref table.
+    fn tiny_pdf(text: &str) -> Vec<u8> {
+        let stream = format!("BT /F1 12 Tf 72 720 Td ({text}) Tj ET");
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R \
+             /Resources << /Font << /F1 5 0 R >> >> >>"
+                .to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{stream}\nendstream",
+                stream.len()
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+        ];
+        let mut pdf = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::new();
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.push_str(&format!("{} 0 obj\n{object}\nendobj\n", index + 1));
+        }
+        let xref = pdf.len();
+        pdf.push_str(&format!(
+            "xref\n0 {}\n0000000000 65535 f \n",
+            objects.len() + 1
+        ));
+        for offset in offsets {
+            pdf.push_str(&format!("{offset:010} 00000 n \n"));
+        }
+        pdf.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        ));
+        pdf.into_bytes()
+    }
+
+    #[tokio::test]
+    async fn extracts_pdf_text_without_external_tools() {
+        let text = extract_text(Path::new("report.pdf"), tiny_pdf("Quarterly revenue grew"))
+            .await
+            .unwrap();
+        assert!(text.contains("Quarterly revenue grew"), "{text:?}");
+        assert!(
+            extract_text(Path::new("broken.pdf"), b"%PDF-1.4 nonsense".to_vec())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn ingests_incrementally_and_retrieves_chunks() {
+        let (_server, url) = fake_embeddings_server().await;
+        let temp_dir = tempdir().unwrap();
+        let config =
+            ConfigService::with_paths(ConfigPaths::from_base_dir(temp_dir.path()).unwrap())
+                .await
+                .unwrap();
+        let docs = temp_dir.path().join("docs");
+        std::fs::create_dir_all(docs.join("src")).unwrap();
+        std::fs::create_dir_all(docs.join(".git")).unwrap();
+        std::fs::write(
+            docs.join("guide.md"),
+            "# Deploying\nRun the deploy script with the staging flag before production.",
+        )
+        .unwrap();
+        std::fs::write(
+            docs.join("src/lib.rs"),
+            "fn parse_invoice() { /* reads invoice totals */ }",
+        )
+        .unwrap();
+        std::fs::write(docs.join("src/data.txt"), [0xff, 0xfe, 0x00]).unwrap();
+        std::fs::write(docs.join("logo.png"), [0x89, 0x50]).unwrap();
+        std::fs::write(docs.join(".git/config"), "ignored").unwrap();
+
+        let roots = [docs.clone()];
+        let store = DocumentStore::new(config.pool().clone());
+        let collection = store.create_collection("Handbook").await.unwrap();
+        assert!(matches!(
+            store.create_collection(" Handbook ").await,
+            Err(DocumentError::CollectionExists(_))
+        ));
+        let client =
+            EmbeddingClient::new(EmbeddingProvider::Ollama, None, None, Some(url)).unwrap();
+
+        let mut reports = Vec::new();
+        let report = store
+            .ingest(&client, collection.id, &roots, |done, total| {
+                reports.push((done, total));
+                async {}
+            })
+            .await
+            .unwrap();
+        assert_eq!(report.indexed, 2);
+        assert_eq!(report.skipped.len(), 1, "{:?}", report.skipped);
+        assert!(report.skipped[0].path.ends_with("data.txt"));
+        assert_eq!(reports.last(), Some(&(3, 3)));
+        let collection = store.collection(collection.id).await.unwrap();
+        assert_eq!((collection.files, collection.chunks), (2, 2));
+
+        let hits = store
+            .search(
+                &client,
+                collection.id,
+                "run the deploy script on staging",
+                1,
+            )
+            .await
+            .unwrap();
+        assert!(hits[0].path.ends_with("guide.md"));
+        assert!(render_documents(&hits).unwrap().contains("[D1] "));
+
+        let report = store
+            .ingest(&client, collection.id, &roots, |_, _| async {})
+            .await
+            .unwrap();
+        assert_eq!((report.indexed, report.unchanged), (0, 2));
+
+        std::fs::write(
+            docs.join("guide.md"),
+            "Deploys now go through the release bot.",
+        )
+        .unwrap();
+        std::fs::remove_file(docs.join("src/lib.rs")).unwrap();
+        let report = store
+            .ingest(&client, collection.id, &roots, |_, _| async {})
+            .await
+            .unwrap();
+        assert_eq!((report.indexed, report.removed), (1, 1));
+        let files = store.list_files(collection.id).await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(store
+            .search(&client, collection.id, "parse invoice totals", 5)
+            .await
+            .unwrap()
+            .iter()
+            .all(|hit| hit.path.ends_with("guide.md")));
+
+        std::fs::write(docs.join("guide.md"), [0xff, 0xfe, 0x00]).unwrap();
+        let report = store
+            .ingest(&client, collection.id, &roots, |_, _| async {})
+            .await
+            .unwrap();
+        assert_eq!((report.skipped.len(), report.removed), (2, 1));
+        assert!(store.list_files(collection.id).await.unwrap().is_empty());
+
+        assert!(matches!(
+            store
+                .ingest(
+                    &client,
+                    collection.id,
+                    &[docs.join("missing")],
+                    |_, _| async {}
+                )
+                .await,
+            Err(DocumentError::PathNotFound(_))
+        ));
+        store.delete_collection(collection.id).await.unwrap();
+        assert!(matches!(
+            store.list_files(collection.id).await,
+            Err(DocumentError::CollectionNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,360 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::config::service::ConfigService;
+use crate::config::ConfigError;
+
+const OPENAI_API_BASE: &str = "https://api.openai.com/v1";
+const GEMINI_API_BASE: &str = "https://generativelanguage.googleapis.com/v1beta";
+const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+/// Inputs are cut to roughly this many characters, comfortably inside every provider's
+/// per-input token limit.
+const MAX_INPUT_CHARS: usize = 8_000;
+
+#[derive(Debug, Error)]
+pub enum EmbeddingError {
+    #[error("{0}")]
+    Config(#[from] ConfigError),
+    #[error("unsupported embedding provider `{0}`")]
+    UnsupportedProvider(String),
+    #[error("network error: {0}")]
+    Network(String),
+    #[error("provider error ({status}): {message}")]
+    Provider { status: StatusCode, message: String },
+    #[error("unexpected embeddings response: {0}")]
+    Response(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingProvider {
+    OpenAi,
+    /// Gemini, stored under the `google` provider id.
+    Google,
+    Ollama,
+}
+
+impl EmbeddingProvider {
+    pub fn parse(value: &str) -> Result<Self, EmbeddingError> {
+        match value {
+            "openai" => Ok(Self::OpenAi),
+            "google" => Ok(Self::Google),
+            "ollama" => Ok(Self::Ollama),
+            other => Err(EmbeddingError::UnsupportedProvider(other.to_string())),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::OpenAi => "openai",
+            Self::Google => "google",
+            Self::Ollama => "ollama",
+        }
+    }
+
+    fn default_model(self) -> &'static str {
+        match self {
+            Self::OpenAi => "text-embedding-3-small",
+            Self::Google => "text-embedding-004",
+            Self::Ollama => "nomic-embed-text",
+        }
+    }
+}
+
+/// Calls one provider's embeddings endpoint, configured from the `memory.*` settings.
+#[derive(Debug, Clone)]
+pub struct EmbeddingClient {
+    provider: EmbeddingProvider,
+    model: String,
+    api_key: Option<String>,
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl EmbeddingClient {
+    pub fn new(
+        provider: EmbeddingProvider,
+        model: Option<String>,
+        api_key: Option<String>,
+        base_url: Option<String>,
+    ) -> Result<Self, EmbeddingError> {
+        let base_url = base_url.unwrap_or_else(|| {
+            match provider {
+                EmbeddingProvider::OpenAi => OPENAI_API_BASE,
+                EmbeddingProvider::Google => GEMINI_API_BASE,
+                EmbeddingProvider::Ollama => DEFAULT_OLLAMA_URL,
+            }
+            .to_string()
+        });
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+            .map_err(|err| EmbeddingError::Network(err.to_string()))?;
+        Ok(Self {
+            provider,
+            model: model
+                .filter(|model| !model.trim().is_empty())
+                .unwrap_or_else(|| provider.default_model().to_string()),
+            api_key,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http,
+        })
+    }
+
+    pub async fn from_settings(config: &ConfigService) -> Result<Self, EmbeddingError> {
+        let provider: String = config
+            .get_value("memory.provider")
+            .await?
+            .unwrap_or_else(|| "openai".into());
+        let provider = EmbeddingProvider::parse(&provider)?;
+        let model = config.get_value("memory.model").await?;
+        let (api_key, base_url) = match provider {
+            EmbeddingProvider::Ollama => (None, config.get_value("memory.ollama_url").await?),
+            _ => (
+                Some(
+                    config
+                        .provider_credentials(provider.as_str())
+                        .await?
+                        .api_key,
+                ),
+                None,
+            ),
+        };
+        Self::new(provider, model, api_key, base_url)
+    }
+
+    /// `provider:model`, stored with every vector. Vectors from different models are not
+    /// comparable, so searches only look at rows with the current key.
+    pub fn model_key(&self) -> String {
+        format!("{}:{}", self.provider.as_str(), self.model)
+    }
+
+    /// One vector per input, in input order.
+    pub async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let inputs: Vec<&str> = inputs.iter().map(|input| truncate(input)).collect();
+        let vectors = match self.provider {
+            EmbeddingProvider::OpenAi => {
+                let url = format!("{}/embeddings", self.base_url);
+                let response: OpenAiEmbeddings = self
+                    .post(&url, json!({ "model": self.model, "input": inputs }))
+                    .await?;
+                let mut data = response.data;
+                data.sort_by_key(|item| item.index);
+                data.into_iter().map(|item| item.embedding).collect()
+            }
+            EmbeddingProvider::Google => {
+                let model = format!("models/{}", self.model);
+                let requests: Vec<Value> = inputs
+                    .iter()
+                    .map(|text| json!({ "model": model, "content": { "parts": [{ "text": text }] } }))
+                    .collect();
+                let url = format!("{}/{model}:batchEmbedContents", self.base_url);
+                let response: GeminiEmbeddings =
+                    self.post(&url, json!({ "requests": requests })).await?;
+                response
+                    .embeddings
+                    .into_iter()
+                    .map(|item| item.values)
+                    .collect()
+            }
+            EmbeddingProvider::Ollama => {
+                let url = format!("{}/api/embed", self.base_url);
+                let response: OllamaEmbeddings = self
+                    .post(&url, json!({ "model": self.model, "input": inputs }))
+                    .await?;
+                response.embeddings
+            }
+        };
+        if vectors.len() != inputs.len() {
+            return Err(EmbeddingError::Response(format!(
+                "expected {} vectors, got {}",
+                inputs.len(),
+                vectors.len()
+            )));
+        }
+        Ok(vectors)
+    }
+
+    async fn post<T: for<'de> Deserialize<'de>>(
+        &self,
+        url: &str,
+        body: Value,
+    ) -> Result<T, EmbeddingError> {
+        let mut request = self.http.post(url).json(&body);
+        request = match (self.provider, &self.api_key) {
+            (EmbeddingProvider::OpenAi, Some(key)) => request.bearer_auth(key),
+            (EmbeddingProvider::Google, Some(key)) => request.header("x-goog-api-key", key),
+            _ => request,
+        };
+        let response = request
+            .send()
+            .await
+            .map_err(|err| EmbeddingError::Network(err.to_string()))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let message = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "unknown error".to_string());
+            return Err(EmbeddingError::Provider { status, message });
+        }
+        response
+            .json()
+            .await
+            .map_err(|err| EmbeddingError::Response(err.to_string()))
+    }
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbeddings {
+    data: Vec<OpenAiEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct GeminiEmbeddings {
+    embeddings: Vec<GeminiEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct GeminiEmbedding {
+    values: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct OllamaEmbeddings {
+    embeddings: Vec<Vec<f32>>,
+}
+
+fn truncate(input: &str) -> &str {
+    match input.char_indices().nth(MAX_INPUT_CHARS) {
+        Some((end, _)) => &input[..end],
+        None => input,
+    }
+}
+
+/// Little-endian `f32`s, the layout of the `embedding` BLOB columns.
+pub fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+pub fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+/// Cosine similarity in `[-1, 1]`; 0 for mismatched or zero-length vectors.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0_f32, 0.0_f32, 0.0_f32);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// Brute-force nearest neighbours: the `k` best `(item, score)` pairs at or above
+/// `min_score`, best first.
+pub fn top_k<T>(
+    query: &[f32],
+    candidates: impl IntoIterator<Item = (T, Vec<f32>)>,
+    k: usize,
+    min_score: f32,
+) -> Vec<(T, f32)> {
+    let mut scored: Vec<(T, f32)> = candidates
+        .into_iter()
+        .map(|(item, vector)| {
+            let score = cosine_similarity(query, &vector);
+            (item, score)
+        })
+        .filter(|(_, score)| *score >= min_score)
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(k);
+    scored
+}
+
+/// Starts `tests/fixtures/fake_embeddings_server.py` and returns it with its base URL.
+/// The server dies with the returned child.
+#[cfg(test)]
+pub(crate) async fn fake_embeddings_server() -> (tokio::process::Child, String) {
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    let mut child = tokio::process::Command::new("python3")
+        .arg(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/fake_embeddings_server.py"
+        ))
+        .stdout(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    let stdout = child.stdout.take().unwrap();
+    let port = BufReader::new(stdout)
+        .lines()
+        .next_line()
+        .await
+        .unwrap()
+        .unwrap();
+    (child, format!("http://127.0.0.1:{port}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vectors_round_trip_and_rank_by_cosine() {
+        let vector = vec![0.25, -1.5, 3.0];
+        assert_eq!(decode_vector(&encode_vector(&vector)), vector);
+
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+
+        let hits = top_k(
+            &[1.0, 0.0],
+            [
+                ("orthogonal", vec![0.0, 1.0]),
+                ("close", vec![0.9, 0.1]),
+                ("exact", vec![3.0, 0.0]),
+                ("opposite", vec![-1.0, 0.0]),
+            ],
+            2,
+            0.1,
+        );
+        let names: Vec<&str> = hits.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["exact", "close"]);
+    }
+
+    #[test]
+    fn defaults_model_per_provider() {
+        let client = EmbeddingClient::new(EmbeddingProvider::Ollama, None, None, None).unwrap();
+        assert_eq!(client.model_key(), "ollama:nomic-embed-text");
+        assert_eq!(client.base_url, DEFAULT_OLLAMA_URL);
+        assert!(EmbeddingProvider::parse("anthropic").is_err());
+    }
+}
@@ -0,0 +1,638 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{FromRow, SqlitePool};
+use tauri::{AppHandle, Emitter};
+use thiserror::Error;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, Notify};
+use tracing::{info, warn};
+
+pub const JOB_UPDATED_EVENT: &str = "job:updated";
+pub const DEFAULT_MAX_ATTEMPTS: i64 = 3;
+pub const DEFAULT_WORKERS: usize = 2;
+/// Idle workers re-check the table this often so delayed retries are picked up.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Retry delays double from this many seconds, capped at `MAX_BACKOFF_SECS`.
+const BACKOFF_BASE_SECS: i64 = 2;
+const MAX_BACKOFF_SECS: i64 = 300;
+
+pub type SharedJobQueue = Arc<JobQueue>;
+/// What a handler returns: a JSON result, or an error message that counts as a failed attempt.
+pub type JobResult = Result<Value, String>;
+type Handler = Arc<dyn Fn(JobContext) -> BoxFuture<'static, JobResult> + Send + Sync>;
+
+#[derive(Debug, Error)]
+pub enum JobQueueError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("job {0} not found")]
+    NotFound(i64),
+    #[error("job {0} is not dead-lettered")]
+    NotDead(i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    /// Out of attempts; stays put until retried by hand.
+    Dead,
+}
+
+impl JobStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Dead => "dead",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "running" => Self::Running,
+            "succeeded" => Self::Succeeded,
+            "dead" => Self::Dead,
+            _ => Self::Queued,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedJob {
+    pub id: i64,
+    pub kind: String,
+    pub payload: Value,
+    pub status: JobStatus,
+    pub attempts: i64,
+    pub max_attempts: i64,
+    pub progress: f64,
+    pub message: Option<String>,
+    pub last_error: Option<String>,
+    pub result: Option<Value>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, FromRow)]
+struct JobRow {
+    id: i64,
+    kind: String,
+    payload: String,
+    status: String,
+    attempts: i64,
+    max_attempts: i64,
+    progress: f64,
+    message: Option<String>,
+    last_error: Option<String>,
+    result: Option<String>,
+    created_at: String,
+    updated_at: String,
+}
+
+impl From<JobRow> for QueuedJob {
+    fn from(row: JobRow) -> Self {
+        Self {
+            id: row.id,
+            kind: row.kind,
+            payload: serde_json::from_str(&row.payload).unwrap_or(Value::Null),
+            status: JobStatus::parse(&row.status),
+            attempts: row.attempts,
+            max_attempts: row.max_attempts,
+            progress: row.progress,
+            message: row.message,
+            last_error: row.last_error,
+            result: row.result.and_then(|raw| serde_json::from_str(&raw).ok()),
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+const JOB_COLUMNS: &str = "id, kind, payload, status, attempts, max_attempts, progress, message, \
+                           last_error, result, created_at, updated_at";
+
+/// Payload of [`JOB_UPDATED_EVENT`], sent on every status change and progress report.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobUpdate {
+    pub id: i64,
+    pub kind: String,
+    pub status: JobStatus,
+    pub progress: f64,
+    pub message: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewJob<'a> {
+    pub kind: &'a str,
+    pub payload: Value,
+    pub max_attempts: i64,
+}
+
+/// Handed to a handler for one attempt of a job.
+pub struct JobContext {
+    pub id: i64,
+    pub kind: String,
+    pub payload: Value,
+    queue: SharedJobQueue,
+}
+
+impl JobContext {
+    /// Records progress (0.0–1.0) and notifies listeners. Reporting is best effort.
+    pub async fn progress(&self, fraction: f64, message: impl Into<String>) {
+        let fraction = fraction.clamp(0.0, 1.0);
+        let message = message.into();
+        if let Err(err) = sqlx::query("UPDATE jobs SET progress = ?2, message = ?3 WHERE id = ?1")
+            .bind(self.id)
+            .bind(fraction)
+            .bind(&message)
+            .execute(&self.queue.pool)
+            .await
+        {
+            warn!(job = self.id, "failed to record job progress: {err}");
+        }
+        self.queue.publish(JobUpdate {
+            id: self.id,
+            kind: self.kind.clone(),
+            status: JobStatus::Running,
+            progress: fraction,
+            message: Some(message),
+            error: None,
+        });
+    }
+}
+
+/// SQLite-backed work queue drained by a pool of tokio workers. Services register a handler
+/// per job kind and enqueue work with a JSON payload; jobs left running by a previous
+/// session are queued again on startup.
+pub struct JobQueue {
+    pool: SqlitePool,
+    handlers: RwLock<HashMap<String, Handler>>,
+    updates: broadcast::Sender<JobUpdate>,
+    wake: Notify,
+}
+
+impl JobQueue {
+    /// Never fails so callers can rely on a queue; jobs left `running` by a failed requeue
+    /// are only picked up again on a later launch.
+    pub async fn new(pool: SqlitePool) -> SharedJobQueue {
+        match sqlx::query(
+            "UPDATE jobs SET status = 'queued', updated_at = CURRENT_TIMESTAMP WHERE status = 'running'",
+        )
+        .execute(&pool)
+        .await
+        {
+            Ok(done) if done.rows_affected() > 0 => info!(
+                requeued = done.rows_affected(),
+                "requeued jobs interrupted by the last shutdown"
+            ),
+            Ok(_) => {}
+            Err(err) => warn!("unable to requeue interrupted jobs: {err}"),
+        }
+        let (updates, _) = broadcast::channel(64);
+        Arc::new(Self {
+            pool,
+            handlers: RwLock::new(HashMap::new()),
+            updates,
+            wake: Notify::new(),
+        })
+    }
+
+    /// Sets the handler for `kind`, replacing any earlier one.
+    pub fn register<F, Fut>(&self, kind: &str, handler: F)
+    where
+        F: Fn(JobContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = JobResult> + Send + 'static,
+    {
+        let handler: Handler = Arc::new(move |context| handler(context).boxed());
+        self.handlers
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(kind.to_string(), handler);
+        self.wake.notify_waiters();
+    }
+
+    /// Spawns `workers` tasks that run jobs until the app exits.
+    pub fn start(self: &Arc<Self>, workers: usize) {
+        for _ in 0..workers.max(1) {
+            let queue = self.clone();
+            tauri::async_runtime::spawn(async move { queue.work().await });
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<JobUpdate> {
+        self.updates.subscribe()
+    }
+
+    pub async fn enqueue(&self, kind: &str, payload: Value) -> Result<i64, JobQueueError> {
+        self.enqueue_job(NewJob {
+            kind,
+            payload,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+        })
+        .await
+    }
+
+    pub async fn enqueue_job(&self, job: NewJob<'_>) -> Result<i64, JobQueueError> {
+        let id = sqlx::query("INSERT INTO jobs (kind, payload, max_attempts) VALUES (?1, ?2, ?3)")
+            .bind(job.kind)
+            .bind(serde_json::to_string(&job.payload)?)
+            .bind(job.max_attempts.max(1))
+            .execute(&self.pool)
+            .await?
+            .last_insert_rowid();
+        self.publish(JobUpdate {
+            id,
+            kind: job.kind.to_string(),
+            status: JobStatus::Queued,
+            progress: 0.0,
+            message: None,
+            error: None,
+        });
+        self.wake.notify_one();
+        Ok(id)
+    }
+
+    /// Enqueues a job unless one of the same kind is already waiting to run. Returns the
+    /// new job's id, or `None` when an existing job covers it.
+    pub async fn enqueue_once(
+        &self,
+        kind: &str,
+        payload: Value,
+    ) -> Result<Option<i64>, JobQueueError> {
+        let waiting: Option<i64> =
+            sqlx::query_scalar("SELECT id FROM jobs WHERE kind = ?1 AND status = 'queued' LIMIT 1")
+                .bind(kind)
+                .fetch_optional(&self.pool)
+                .await?;
+        if waiting.is_some() {
+            return Ok(None);
+        }
+        self.enqueue(kind, payload).await.map(Some)
+    }
+
+    pub async fn get(&self, id: i64) -> Result<QueuedJob, JobQueueError> {
+        sqlx::query_as::<_, JobRow>(&format!("SELECT {JOB_COLUMNS} FROM jobs WHERE id = ?1"))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .map(Into::into)
+            .ok_or(JobQueueError::NotFound(id))
+    }
+
+    /// Newest first, optionally only jobs in `status`.
+    pub async fn list(
+        &self,
+        status: Option<JobStatus>,
+        limit: i64,
+    ) -> Result<Vec<QueuedJob>, JobQueueError> {
+        let rows = sqlx::query_as::<_, JobRow>(&format!(
+            "SELECT {JOB_COLUMNS} FROM jobs WHERE ?1 IS NULL OR status = ?1 ORDER BY id DESC LIMIT ?2"
+        ))
+        .bind(status.map(JobStatus::as_str))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    /// Gives a dead-lettered job a fresh set of attempts.
+    pub async fn retry(&self, id: i64) -> Result<QueuedJob, JobQueueError> {
+        let updated = sqlx::query(
+            r#"
+        UPDATE jobs
+        SET status = 'queued', attempts = 0, progress = 0, message = NULL,
+            run_after = CAST(strftime('%s', 'now') AS INTEGER), updated_at = CURRENT_TIMESTAMP
+        WHERE id = ?1 AND status = 'dead'
+      "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        if updated.rows_affected() == 0 {
+            self.get(id).await?;
+            return Err(JobQueueError::NotDead(id));
+        }
+        self.wake.notify_one();
+        let job = self.get(id).await?;
+        self.publish(JobUpdate {
+            id,
+            kind: job.kind.clone(),
+            status: JobStatus::Queued,
+            progress: 0.0,
+            message: None,
+            error: job.last_error.clone(),
+        });
+        Ok(job)
+    }
+
+    async fn work(self: Arc<Self>) {
+        loop {
+            match self.claim().await {
+                Ok(Some(job)) => self.run(job).await,
+                Ok(None) => {
+                    let _ = tokio::time::timeout(POLL_INTERVAL, self.wake.notified()).await;
+                }
+                Err(err) => {
+                    warn!("failed to claim job: {err}");
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    /// Takes the next due job of a kind that has a handler. Other kinds stay queued until
+    /// one is registered, e.g. when the service owning them failed to start.
+    async fn claim(&self) -> Result<Option<JobRow>, JobQueueError> {
+        let kinds: Vec<String> = self
+            .handlers
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .keys()
+            .cloned()
+            .collect();
+        if kinds.is_empty() {
+            return Ok(None);
+        }
+        let placeholders = vec!["?"; kinds.len()].join(", ");
+        let sql = format!(
+            r#"
+        UPDATE jobs
+        SET status = 'running', attempts = attempts + 1, progress = 0, message = NULL,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = (
+          SELECT id FROM jobs
+          WHERE status = 'queued' AND run_after <= CAST(strftime('%s', 'now') AS INTEGER)
+            AND kind IN ({placeholders})
+          ORDER BY run_after, id
+          LIMIT 1
+        )
+        RETURNING {JOB_COLUMNS}
+      "#
+        );
+        let mut query = sqlx::query_as::<_, JobRow>(&sql);
+        for kind in &kinds {
+            query = query.bind(kind);
+        }
+        Ok(query.fetch_optional(&self.pool).await?)
+    }
+
+    async fn run(self: &Arc<Self>, job: JobRow) {
+        self.publish(JobUpdate {
+            id: job.id,
+            kind: job.kind.clone(),
+            status: JobStatus::Running,
+            progress: 0.0,
+            message: None,
+            error: None,
+        });
+        let handler = self
+            .handlers
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&job.kind)
+            .cloned();
+        let outcome = match handler {
+            Some(handler) => {
+                let context = JobContext {
+                    id: job.id,
+                    kind: job.kind.clone(),
+                    payload: serde_json::from_str(&job.payload).unwrap_or(Value::Null),
+                    queue: self.clone(),
+                };
+                // A separate task so a panicking handler fails the attempt instead of the worker.
+                match tokio::spawn(handler(context)).await {
+                    Ok(outcome) => outcome,
+                    Err(err) => Err(format!("job handler panicked: {err}")),
+                }
+            }
+            None => Err(format!("no handler registered for job kind `{}`", job.kind)),
+        };
+        if let Err(err) = self.finish(&job, outcome).await {
+            warn!(job = job.id, "failed to record job outcome: {err}");
+        }
+    }
+
+    async fn finish(&self, job: &JobRow, outcome: JobResult) -> Result<(), JobQueueError> {
+        let (status, error) = match outcome {
+            Ok(result) => {
+                sqlx::query(
+                    r#"
+        UPDATE jobs
+        SET status = 'succeeded', progress = 1, result = ?2, last_error = NULL,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = ?1
+      "#,
+                )
+                .bind(job.id)
+                .bind(serde_json::to_string(&result)?)
+                .execute(&self.pool)
+                .await?;
+                (JobStatus::Succeeded, None)
+            }
+            Err(error) => {
+                let status = if job.attempts >= job.max_attempts {
+                    JobStatus::Dead
+                } else {
+                    JobStatus::Queued
+                };
+                warn!(
+                    job = job.id,
+                    kind = job.kind.as_str(),
+                    attempt = job.attempts,
+                    "job failed: {error}"
+                );
+                sqlx::query(
+                    r#"
+        UPDATE jobs
+        SET status = ?2, last_error = ?3,
+            run_after = CAST(strftime('%s', 'now') AS INTEGER) + ?4,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = ?1
+      "#,
+                )
+                .bind(job.id)
+                .bind(status.as_str())
+                .bind(&error)
+                .bind(backoff_secs(job.attempts))
+                .execute(&self.pool)
+                .await?;
+                (status, Some(error))
+            }
+        };
+        let progress = if status == JobStatus::Succeeded {
+            1.0
+        } else {
+            0.0
+        };
+        self.publish(JobUpdate {
+            id: job.id,
+            kind: job.kind.clone(),
+            status,
+            progress,
+            message: None,
+            error,
+        });
+        Ok(())
+    }
+
+    fn publish(&self, update: JobUpdate) {
+        // No subscribers is fine; the table is the source of truth.
+        let _ = self.updates.send(update);
+    }
+}
+
+fn backoff_secs(attempts: i64) -> i64 {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    BACKOFF_BASE_SECS
+        .saturating_mul(2_i64.pow(exponent))
+        .min(MAX_BACKOFF_SECS)
+}
+
+/// Re-emits every [`JobUpdate`] to the frontend.
+pub fn forward_job_updates(app: AppHandle, queue: &JobQueue) {
+    let mut updates = queue.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let update = match updates.recv().await {
+                Ok(update) => update,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!(skipped, "job update listener lagged");
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            if let Err(err) = app.emit(JOB_UPDATED_EVENT, update) {
+                warn!("failed to emit job update: {err}");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::config::{paths::ConfigPaths, service::ConfigService};
+
+    async fn wait_for(queue: &JobQueue, id: i64, status: JobStatus) -> QueuedJob {
+        for _ in 0..600 {
+            let job = queue.get(id).await.unwrap();
+            if job.status == status {
+                return job;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("job {id} never reached {status:?}");
+    }
+
+    #[tokio::test]
+    async fn runs_retries_and_dead_letters_jobs() {
+        let temp_dir = tempdir().unwrap();
+        let config =
+            ConfigService::with_paths(ConfigPaths::from_base_dir(temp_dir.path()).unwrap())
+                .await
+                .unwrap();
+        let queue = JobQueue::new(config.pool().clone()).await;
+        let mut updates = queue.subscribe();
+        queue.register("double", |context| async move {
+            context.progress(0.5, "halfway").await;
+            let value = context.payload["value"].as_i64().ok_or("missing value")?;
+            Ok(json!(value * 2))
+        });
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = attempts.clone();
+        queue.register("flaky", move |_context| {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Err("upstream unavailable".to_string())
+            }
+        });
+        queue.start(2);
+
+        let doubled = queue
+            .enqueue("double", json!({ "value": 21 }))
+            .await
+            .unwrap();
+        let job = wait_for(&queue, doubled, JobStatus::Succeeded).await;
+        assert_eq!(job.result, Some(json!(42)));
+        assert_eq!(job.attempts, 1);
+        let mut saw_progress = false;
+        while let Ok(update) = updates.try_recv() {
+            saw_progress |= update.message.as_deref() == Some("halfway");
+        }
+        assert!(saw_progress);
+
+        let flaky = queue
+            .enqueue_job(NewJob {
+                kind: "flaky",
+                payload: Value::Null,
+                max_attempts: 2,
+            })
+            .await
+            .unwrap();
+        let job = wait_for(&queue, flaky, JobStatus::Dead).await;
+        assert_eq!(job.attempts, 2);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(job.last_error.as_deref(), Some("upstream unavailable"));
+        assert_eq!(
+            queue.list(Some(JobStatus::Dead), 10).await.unwrap()[0].id,
+            flaky
+        );
+
+        let orphan = queue
+            .enqueue_job(NewJob {
+                kind: "unknown",
+                payload: Value::Null,
+                max_attempts: 1,
+            })
+            .await
+            .unwrap();
+        // Jobs without a handler wait instead of burning their attempts.
+        let later = queue
+            .enqueue_job(NewJob {
+                kind: "double",
+                payload: json!({ "value": 1 }),
+                max_attempts: 1,
+            })
+            .await
+            .unwrap();
+        wait_for(&queue, later, JobStatus::Succeeded).await;
+        let job = queue.get(orphan).await.unwrap();
+        assert_eq!((job.status, job.attempts), (JobStatus::Queued, 0));
+        assert!(matches!(
+            queue.retry(doubled).await,
+            Err(JobQueueError::NotDead(_))
+        ));
+        queue.register("unknown", |_context| async { Ok(Value::Null) });
+        wait_for(&queue, orphan, JobStatus::Succeeded).await;
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        assert_eq!(backoff_secs(1), 2);
+        assert_eq!(backoff_secs(2), 4);
+        assert_eq!(backoff_secs(4), 16);
+        assert_eq!(backoff_secs(40), MAX_BACKOFF_SECS);
+    }
+}
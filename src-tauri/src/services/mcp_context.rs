@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::mcp::manager::McpManager;
+use crate::mcp::McpError;
+
+/// A resource on a running MCP server, attached to a chat turn as context.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceRef {
+    pub server: String,
+    pub uri: String,
+}
+
+/// A server-provided prompt to expand at the start of a chat turn.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptRef {
+    pub server: String,
+    pub name: String,
+    #[serde(default)]
+    pub arguments: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpandedMessage {
+    pub role: String,
+    pub content: String,
+}
+
+/// Fetches a prompt and flattens its messages to text. MCP prompts only use the `user` and
+/// `assistant` roles; anything else is treated as `user`.
+pub async fn expand_prompt(
+    mcp: &McpManager,
+    prompt: &PromptRef,
+) -> Result<Vec<ExpandedMessage>, McpError> {
+    let result = mcp
+        .get_prompt(&prompt.server, &prompt.name, &prompt.arguments)
+        .await?;
+    Ok(result
+        .messages
+        .into_iter()
+        .map(|message| ExpandedMessage {
+            role: if message.role == "assistant" {
+                "assistant".into()
+            } else {
+                "user".into()
+            },
+            content: message.content.to_text(),
+        })
+        .collect())
+}
+
+/// Reads every attached resource into one context block, or `None` when nothing is attached.
+pub async fn render_resources(
+    mcp: &McpManager,
+    resources: &[ResourceRef],
+) -> Result<Option<String>, McpError> {
+    if resources.is_empty() {
+        return Ok(None);
+    }
+    let mut blocks = Vec::new();
+    for reference in resources {
+        for contents in mcp.read_resource(&reference.server, &reference.uri).await? {
+            blocks.push(format!(
+                "<resource server=\"{}\" uri=\"{}\">\n{}\n</resource>",
+                reference.server,
+                contents.uri,
+                contents.to_text()
+            ));
+        }
+    }
+    Ok(Some(blocks.join("\n\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::config::{paths::ConfigPaths, service::ConfigService};
+    use crate::mcp::manager::AddMcpServerPayload;
+    use crate::mcp::store::ServerLaunch;
+
+    #[tokio::test]
+    async fn expands_prompts_and_renders_resources() {
+        let temp_dir = tempdir().unwrap();
+        let config =
+            ConfigService::with_paths(ConfigPaths::from_base_dir(temp_dir.path()).unwrap())
+                .await
+                .unwrap();
+        let mcp = McpManager::new(config).await;
+        mcp.add(AddMcpServerPayload {
+            slug: "fake".into(),
+            name: "Fake".into(),
+            launch: ServerLaunch::Stdio {
+                command: "python3".into(),
+                args: vec![concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/tests/fixtures/fake_mcp_server.py"
+                )
+                .into()],
+                env: Default::default(),
+            },
+            auto_start: false,
+            auth_header: None,
+        })
+        .await
+        .unwrap();
+        mcp.start("fake").await.unwrap();
+
+        let uris: Vec<String> = mcp
+            .list_resources()
+            .await
+            .into_iter()
+            .map(|listed| listed.resource.uri)
+            .collect();
+        assert_eq!(uris, ["memo://greeting", "memo://logo"]);
+
+        let messages = expand_prompt(
+            &mcp,
+            &PromptRef {
+                server: "fake".into(),
+                name: "summarize".into(),
+                arguments: BTreeMap::from([("topic".into(), "MCP".into())]),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(messages[0].content, "Summarize MCP.");
+        assert_eq!(messages[1].content, "Hello from fake-mcp");
+
+        let context = render_resources(
+            &mcp,
+            &[ResourceRef {
+                server: "fake".into(),
+                uri: "memo://greeting".into(),
+            }],
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(context.contains("uri=\"memo://greeting\""));
+        assert!(context.contains("Hello from fake-mcp"));
+
+        let missing = render_resources(
+            &mcp,
+            &[ResourceRef {
+                server: "fake".into(),
+                uri: "memo://missing".into(),
+            }],
+        )
+        .await;
+        assert!(missing.is_err());
+    }
+}
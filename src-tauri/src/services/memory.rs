@@ -0,0 +1,458 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::{FromRow, SqlitePool};
+use thiserror::Error;
+use tracing::info;
+
+use crate::config::service::{ConfigService, SharedConfigService};
+use crate::config::ConfigError;
+use crate::services::embeddings::{
+    decode_vector, encode_vector, top_k, EmbeddingClient, EmbeddingError,
+};
+use crate::services::job_queue::{JobContext, JobQueue, JobQueueError, JobResult};
+
+/// Job queue kind that embeds every message and note not yet in the index.
+pub const INDEX_MEMORY_JOB: &str = "memory.index";
+const INDEX_BATCH: i64 = 32;
+/// Matches weaker than this are noise rather than memories.
+const MIN_SIMILARITY: f32 = 0.3;
+/// Recalled text is cut to this many characters before it goes into a prompt.
+const MAX_MEMORY_CHARS: usize = 1_200;
+
+#[derive(Debug, Error)]
+pub enum MemoryError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("{0}")]
+    Config(#[from] ConfigError),
+    #[error("{0}")]
+    Embedding(#[from] EmbeddingError),
+    #[error("{0}")]
+    Queue(#[from] JobQueueError),
+    #[error("note {0} not found")]
+    NoteNotFound(i64),
+    #[error("note content is empty")]
+    EmptyNote,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryNote {
+    pub id: i64,
+    pub title: String,
+    pub content: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A recalled message or note. Exactly one of `message_id` and `note_id` is set.
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryHit {
+    pub message_id: Option<i64>,
+    pub conversation_id: Option<String>,
+    pub note_id: Option<i64>,
+    /// Conversation or note title.
+    pub title: Option<String>,
+    pub content: String,
+    pub created_at: String,
+    #[sqlx(skip)]
+    pub score: f32,
+}
+
+#[derive(Debug, FromRow)]
+struct Candidate {
+    #[sqlx(flatten)]
+    hit: MemoryHit,
+    embedding: Vec<u8>,
+}
+
+#[derive(Debug, FromRow)]
+struct PendingItem {
+    message_id: Option<i64>,
+    note_id: Option<i64>,
+    content: String,
+}
+
+/// Vector index over past conversation messages and user notes.
+#[derive(Clone)]
+pub struct MemoryStore {
+    pool: SqlitePool,
+}
+
+impl MemoryStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn add_note(&self, title: &str, content: &str) -> Result<MemoryNote, MemoryError> {
+        if content.trim().is_empty() {
+            return Err(MemoryError::EmptyNote);
+        }
+        let id = sqlx::query("INSERT INTO memory_notes (title, content) VALUES (?1, ?2)")
+            .bind(title.trim())
+            .bind(content)
+            .execute(&self.pool)
+            .await?
+            .last_insert_rowid();
+        Ok(sqlx::query_as::<_, MemoryNote>(
+            "SELECT id, title, content, created_at, updated_at FROM memory_notes WHERE id = ?1",
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    pub async fn list_notes(&self) -> Result<Vec<MemoryNote>, MemoryError> {
+        Ok(sqlx::query_as::<_, MemoryNote>(
+            "SELECT id, title, content, created_at, updated_at FROM memory_notes ORDER BY id DESC",
+        )
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    pub async fn delete_note(&self, id: i64) -> Result<(), MemoryError> {
+        let deleted = sqlx::query("DELETE FROM memory_notes WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if deleted.rows_affected() == 0 {
+            return Err(MemoryError::NoteNotFound(id));
+        }
+        Ok(())
+    }
+
+    /// User and assistant messages plus notes without a vector for `model`.
+    async fn pending(&self, model: &str, limit: i64) -> Result<Vec<PendingItem>, MemoryError> {
+        Ok(sqlx::query_as::<_, PendingItem>(
+            r#"
+        SELECT m.id AS message_id, NULL AS note_id, m.content
+        FROM messages m
+        WHERE m.role IN ('user', 'assistant') AND TRIM(m.content) <> ''
+          AND NOT EXISTS (
+            SELECT 1 FROM memory_embeddings e WHERE e.model = ?1 AND e.message_id = m.id
+          )
+        UNION ALL
+        SELECT NULL AS message_id, n.id AS note_id,
+               CASE WHEN n.title = '' THEN n.content ELSE n.title || char(10) || n.content END
+        FROM memory_notes n
+        WHERE NOT EXISTS (
+            SELECT 1 FROM memory_embeddings e WHERE e.model = ?1 AND e.note_id = n.id
+          )
+        LIMIT ?2
+      "#,
+        )
+        .bind(model)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn pending_count(&self, model: &str) -> Result<i64, MemoryError> {
+        Ok(sqlx::query_scalar(
+            r#"
+        SELECT
+          (SELECT COUNT(*) FROM messages m
+           WHERE m.role IN ('user', 'assistant') AND TRIM(m.content) <> ''
+             AND NOT EXISTS (
+               SELECT 1 FROM memory_embeddings e WHERE e.model = ?1 AND e.message_id = m.id
+             ))
+          + (SELECT COUNT(*) FROM memory_notes n
+             WHERE NOT EXISTS (
+               SELECT 1 FROM memory_embeddings e WHERE e.model = ?1 AND e.note_id = n.id
+             ))
+      "#,
+        )
+        .bind(model)
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    /// Embeds everything not yet indexed for the client's model, in batches. `progress`
+    /// receives the running total after each batch. Returns the number of items embedded.
+    pub async fn index<F, Fut>(
+        &self,
+        client: &EmbeddingClient,
+        mut progress: F,
+    ) -> Result<usize, MemoryError>
+    where
+        F: FnMut(usize, usize) -> Fut,
+        Fut: std::future::Future<Output = ()>,
+    {
+        let model = client.model_key();
+        let total = self.pending_count(&model).await? as usize;
+        let mut indexed = 0;
+        loop {
+            let batch = self.pending(&model, INDEX_BATCH).await?;
+            if batch.is_empty() {
+                break;
+            }
+            let inputs: Vec<String> = batch.iter().map(|item| item.content.clone()).collect();
+            let vectors = client.embed(&inputs).await?;
+            let mut tx = self.pool.begin().await?;
+            for (item, vector) in batch.iter().zip(vectors) {
+                // Another worker may have indexed the same row meanwhile.
+                sqlx::query(
+                    r#"
+        INSERT OR IGNORE INTO memory_embeddings (message_id, note_id, model, dimensions, embedding)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+      "#,
+                )
+                .bind(item.message_id)
+                .bind(item.note_id)
+                .bind(&model)
+                .bind(vector.len() as i64)
+                .bind(encode_vector(&vector))
+                .execute(&mut *tx)
+                .await?;
+            }
+            tx.commit().await?;
+            indexed += batch.len();
+            progress(indexed, total.max(indexed)).await;
+        }
+        Ok(indexed)
+    }
+
+    /// The `limit` memories closest to `query`, skipping messages of `exclude_conversation`
+    /// (already part of the prompt when chatting).
+    pub async fn search(
+        &self,
+        client: &EmbeddingClient,
+        query: &str,
+        limit: usize,
+        exclude_conversation: Option<&str>,
+    ) -> Result<Vec<MemoryHit>, MemoryError> {
+        let Some(query_vector) = client.embed(&[query.to_string()]).await?.pop() else {
+            return Ok(Vec::new());
+        };
+        let candidates = sqlx::query_as::<_, Candidate>(
+            r#"
+        SELECT
+          e.message_id,
+          m.conversation_id,
+          e.note_id,
+          COALESCE(n.title, c.title) AS title,
+          COALESCE(m.content, n.content) AS content,
+          COALESCE(m.created_at, n.created_at) AS created_at,
+          e.embedding
+        FROM memory_embeddings e
+        LEFT JOIN messages m ON m.id = e.message_id
+        LEFT JOIN conversations c ON c.id = m.conversation_id
+        LEFT JOIN memory_notes n ON n.id = e.note_id
+        WHERE e.model = ?1 AND e.dimensions = ?2
+          AND (?3 IS NULL OR m.conversation_id IS NULL OR m.conversation_id <> ?3)
+      "#,
+        )
+        .bind(client.model_key())
+        .bind(query_vector.len() as i64)
+        .bind(exclude_conversation)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(top_k(
+            &query_vector,
+            candidates
+                .into_iter()
+                .map(|candidate| (candidate.hit, decode_vector(&candidate.embedding))),
+            limit,
+            MIN_SIMILARITY,
+        )
+        .into_iter()
+        .map(|(hit, score)| MemoryHit { score, ..hit })
+        .collect())
+    }
+}
+
+/// Recalls memories relevant to `prompt` for a chat turn, as a numbered block the model
+/// can cite, or `None` when nothing relevant is stored.
+pub async fn recall(
+    config: &ConfigService,
+    prompt: &str,
+    conversation_id: &str,
+) -> Result<Option<String>, MemoryError> {
+    let client = EmbeddingClient::from_settings(config).await?;
+    let limit: i64 = config.get_value("memory.top_k").await?.unwrap_or(5);
+    let hits = MemoryStore::new(config.pool().clone())
+        .search(
+            &client,
+            prompt,
+            limit.max(1) as usize,
+            Some(conversation_id),
+        )
+        .await?;
+    Ok(render_memories(&hits))
+}
+
+fn render_memories(hits: &[MemoryHit]) -> Option<String> {
+    if hits.is_empty() {
+        return None;
+    }
+    let mut block = String::from(
+        "Possibly relevant memories from earlier conversations and the user's notes. Use them \
+         only if they help, and cite them as [n] when you do.\n",
+    );
+    for (index, hit) in hits.iter().enumerate() {
+        let source = match (&hit.note_id, &hit.conversation_id) {
+            (Some(id), _) => format!("note {id}"),
+            (None, Some(conversation)) => format!("conversation {conversation}"),
+            (None, None) => "memory".into(),
+        };
+        let title = hit
+            .title
+            .as_deref()
+            .filter(|title| !title.is_empty())
+            .map(|title| format!(" \"{title}\""))
+            .unwrap_or_default();
+        let content: String = hit.content.chars().take(MAX_MEMORY_CHARS).collect();
+        block.push_str(&format!(
+            "\n[{}] {source}{title}, {}:\n{content}\n",
+            index + 1,
+            hit.created_at
+        ));
+    }
+    Some(block)
+}
+
+/// Queues an index run unless memory is off or one is already waiting.
+pub async fn schedule_indexing(
+    config: &ConfigService,
+    queue: &JobQueue,
+) -> Result<Option<i64>, MemoryError> {
+    if !config
+        .get_value::<bool>("memory.enabled")
+        .await?
+        .unwrap_or(false)
+    {
+        return Ok(None);
+    }
+    Ok(queue.enqueue_once(INDEX_MEMORY_JOB, Value::Null).await?)
+}
+
+pub fn register_memory_jobs(queue: &JobQueue, config: SharedConfigService) {
+    queue.register(INDEX_MEMORY_JOB, move |context| {
+        let config = config.clone();
+        async move { index_job(&config, context).await }
+    });
+}
+
+async fn index_job(config: &ConfigService, context: JobContext) -> JobResult {
+    let client = EmbeddingClient::from_settings(config)
+        .await
+        .map_err(|err| err.to_string())?;
+    let indexed = MemoryStore::new(config.pool().clone())
+        .index(&client, |done, total| {
+            let context = &context;
+            async move {
+                context
+                    .progress(
+                        done as f64 / total.max(1) as f64,
+                        format!("embedded {done} of {total}"),
+                    )
+                    .await
+            }
+        })
+        .await
+        .map_err(|err| err.to_string())?;
+    if indexed > 0 {
+        info!(
+            indexed,
+            model = client.model_key().as_str(),
+            "memory index updated"
+        );
+    }
+    Ok(json!({ "indexed": indexed }))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::config::paths::ConfigPaths;
+    use crate::services::conversations::{ConversationStore, NewMessage};
+    use crate::services::embeddings::{fake_embeddings_server, EmbeddingProvider};
+
+    #[tokio::test]
+    async fn indexes_and_recalls_messages_and_notes() {
+        let (_server, url) = fake_embeddings_server().await;
+        let temp_dir = tempdir().unwrap();
+        let config =
+            ConfigService::with_paths(ConfigPaths::from_base_dir(temp_dir.path()).unwrap())
+                .await
+                .unwrap();
+        let conversations = ConversationStore::new(config.pool().clone());
+        for (conversation, content) in [
+            ("rust", "How do I parse timestamps with chrono in rust"),
+            (
+                "rust",
+                "Use NaiveDateTime parse_from_str with a format string",
+            ),
+            ("cooking", "My sourdough starter smells of acetone"),
+        ] {
+            conversations.ensure(conversation).await.unwrap();
+            conversations
+                .append(
+                    conversation,
+                    NewMessage {
+                        role: "user",
+                        content,
+                        ..Default::default()
+                    },
+                )
+                .await
+                .unwrap();
+        }
+        let store = MemoryStore::new(config.pool().clone());
+        let note = store
+            .add_note("Sourdough", "Feed the sourdough starter twice a day")
+            .await
+            .unwrap();
+        let client =
+            EmbeddingClient::new(EmbeddingProvider::Ollama, None, None, Some(url)).unwrap();
+
+        let mut reports = Vec::new();
+        let indexed = store
+            .index(&client, |done, total| {
+                reports.push((done, total));
+                async {}
+            })
+            .await
+            .unwrap();
+        assert_eq!(indexed, 4);
+        assert_eq!(reports.last(), Some(&(4, 4)));
+        assert_eq!(store.index(&client, |_, _| async {}).await.unwrap(), 0);
+
+        let hits = store
+            .search(&client, "sourdough starter", 2, None)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().any(|hit| hit.note_id == Some(note.id)));
+        assert!(hits
+            .iter()
+            .all(|hit| hit.content.to_lowercase().contains("sourdough")));
+
+        let hits = store
+            .search(&client, "parse timestamps in rust", 5, Some("rust"))
+            .await
+            .unwrap();
+        assert!(hits
+            .iter()
+            .all(|hit| hit.conversation_id.as_deref() != Some("rust")));
+
+        let block = render_memories(
+            &store
+                .search(&client, "sourdough starter", 1, None)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(block.contains("[1] "));
+
+        store.delete_note(note.id).await.unwrap();
+        let hits = store
+            .search(&client, "feed sourdough twice a day", 5, None)
+            .await
+            .unwrap();
+        assert!(hits.iter().all(|hit| hit.note_id.is_none()));
+    }
+}
@@ -1 +1,19 @@
+pub mod attachments;
+pub mod calculator;
 pub mod chat;
+pub mod config_events;
+pub mod context_window;
+pub mod conversation_export;
+pub mod conversations;
+pub mod documents;
+pub mod embeddings;
+pub mod job_queue;
+pub mod mcp_context;
+pub mod memory;
+pub mod native_tools;
+pub mod scheduled_jobs;
+pub mod search;
+pub mod structured_output;
+pub mod titles;
+pub mod tool_policy;
+pub mod tools;
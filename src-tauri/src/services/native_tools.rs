@@ -0,0 +1,370 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+
+use crate::config::service::ConfigService;
+use crate::config::ConfigError;
+use crate::services::calculator;
+use crate::services::tools::ToolOutcome;
+
+/// Server name used for built-in tools in policies, the audit log and chat chunks. MCP
+/// servers cannot take this slug.
+pub const NATIVE_SERVER: &str = "builtin";
+const MAX_READ_BYTES: u64 = 256 * 1024;
+const MAX_LIST_ENTRIES: usize = 500;
+const MAX_COMMAND_OUTPUT: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NativeTool {
+    ReadFile,
+    ListDirectory,
+    Calculate,
+    RunCommand,
+}
+
+impl NativeTool {
+    const ALL: [Self; 4] = [
+        Self::ReadFile,
+        Self::ListDirectory,
+        Self::Calculate,
+        Self::RunCommand,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ReadFile => "read_file",
+            Self::ListDirectory => "list_directory",
+            Self::Calculate => "calculate",
+            Self::RunCommand => "run_command",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::ReadFile => "Reads a text file inside one of the user's approved directories.",
+            Self::ListDirectory => {
+                "Lists a directory inside one of the user's approved directories."
+            }
+            Self::Calculate => "Evaluates an arithmetic expression, e.g. `(2 + 3) * sqrt(16)`.",
+            Self::RunCommand => {
+                "Runs an allow-listed program with arguments; no shell is involved."
+            }
+        }
+    }
+
+    pub fn parameters(self) -> Value {
+        match self {
+            Self::ReadFile | Self::ListDirectory => json!({
+                "type": "object",
+                "properties": { "path": { "type": "string", "description": "Absolute path" } },
+                "required": ["path"],
+            }),
+            Self::Calculate => json!({
+                "type": "object",
+                "properties": { "expression": { "type": "string" } },
+                "required": ["expression"],
+            }),
+            Self::RunCommand => json!({
+                "type": "object",
+                "properties": {
+                    "command": { "type": "string" },
+                    "args": { "type": "array", "items": { "type": "string" } },
+                },
+                "required": ["command"],
+            }),
+        }
+    }
+}
+
+/// User configuration that bounds what the built-in tools may touch, read from the
+/// `tools.*` settings when a chat turn starts.
+#[derive(Debug, Clone, Default)]
+pub struct NativeToolContext {
+    roots: Vec<PathBuf>,
+    allowlist: Vec<String>,
+    timeout: Duration,
+}
+
+impl NativeToolContext {
+    pub async fn load(config: &ConfigService) -> Result<Self, ConfigError> {
+        let roots: Vec<String> = config
+            .get_value("tools.fs.roots")
+            .await?
+            .unwrap_or_default();
+        let allowlist = config
+            .get_value("tools.shell.allowlist")
+            .await?
+            .unwrap_or_default();
+        let timeout: i64 = config
+            .get_value("tools.shell.timeout_secs")
+            .await?
+            .unwrap_or(30);
+        Ok(Self {
+            // Roots that do not exist cannot contain anything; drop them.
+            roots: roots
+                .iter()
+                .filter_map(|root| std::fs::canonicalize(root).ok())
+                .collect(),
+            allowlist,
+            timeout: Duration::from_secs(timeout.max(1) as u64),
+        })
+    }
+
+    /// Tools worth offering: file tools need an approved directory and the command tool
+    /// needs an allow-listed program.
+    pub fn available(&self) -> Vec<NativeTool> {
+        NativeTool::ALL
+            .into_iter()
+            .filter(|tool| match tool {
+                NativeTool::ReadFile | NativeTool::ListDirectory => !self.roots.is_empty(),
+                NativeTool::Calculate => true,
+                NativeTool::RunCommand => !self.allowlist.is_empty(),
+            })
+            .collect()
+    }
+
+    pub async fn execute(&self, tool: NativeTool, arguments: Value) -> ToolOutcome {
+        let result = match tool {
+            NativeTool::ReadFile => self.read_file(arguments).await,
+            NativeTool::ListDirectory => self.list_directory(arguments).await,
+            NativeTool::Calculate => calculate(arguments),
+            NativeTool::RunCommand => self.run_command(arguments).await,
+        };
+        match result {
+            Ok(output) => ToolOutcome {
+                output,
+                is_error: false,
+            },
+            Err(message) => ToolOutcome::error(message),
+        }
+    }
+
+    async fn read_file(&self, arguments: Value) -> Result<String, String> {
+        let PathArgs { path } = parse_args(arguments)?;
+        let path = self.resolve(&path)?;
+        let metadata = tokio::fs::metadata(&path).await.map_err(io_error)?;
+        if !metadata.is_file() {
+            return Err(format!("{} is not a file", path.display()));
+        }
+        let mut bytes = Vec::new();
+        tokio::fs::File::open(&path)
+            .await
+            .map_err(io_error)?
+            .take(MAX_READ_BYTES)
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(io_error)?;
+        let mut text = String::from_utf8_lossy(&bytes).into_owned();
+        if metadata.len() > MAX_READ_BYTES {
+            text.push_str(&format!(
+                "\n[truncated: showing {MAX_READ_BYTES} of {} bytes]",
+                metadata.len()
+            ));
+        }
+        Ok(text)
+    }
+
+    async fn list_directory(&self, arguments: Value) -> Result<String, String> {
+        let PathArgs { path } = parse_args(arguments)?;
+        let path = self.resolve(&path)?;
+        let mut reader = tokio::fs::read_dir(&path).await.map_err(io_error)?;
+        let mut entries = Vec::new();
+        while let Some(entry) = reader.next_entry().await.map_err(io_error)? {
+            let mut name = entry.file_name().to_string_lossy().into_owned();
+            if entry.file_type().await.is_ok_and(|kind| kind.is_dir()) {
+                name.push('/');
+            }
+            entries.push(name);
+        }
+        entries.sort();
+        let total = entries.len();
+        entries.truncate(MAX_LIST_ENTRIES);
+        if total > MAX_LIST_ENTRIES {
+            entries.push(format!("[{} more entries]", total - MAX_LIST_ENTRIES));
+        }
+        Ok(entries.join("\n"))
+    }
+
+    async fn run_command(&self, arguments: Value) -> Result<String, String> {
+        let CommandArgs { command, args } = parse_args(arguments)?;
+        if !self.allowlist.contains(&command) {
+            return Err(format!("`{command}` is not in the command allow-list"));
+        }
+        let mut process = Command::new(&command);
+        process
+            .args(&args)
+            .env_clear()
+            .envs(std::env::var_os("PATH").map(|path| ("PATH", path)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(root) = self.roots.first() {
+            process.current_dir(root);
+        }
+        let child = process.spawn().map_err(io_error)?;
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
+            .await
+            .map_err(|_| format!("`{command}` timed out after {}s", self.timeout.as_secs()))?
+            .map_err(io_error)?;
+        let status = output.status.code().map_or_else(
+            || "killed by signal".to_string(),
+            |code| format!("exit code {code}"),
+        );
+        let report = format!(
+            "{status}\nstdout:\n{}\nstderr:\n{}",
+            capped(&output.stdout),
+            capped(&output.stderr)
+        );
+        if output.status.success() {
+            Ok(report)
+        } else {
+            Err(report)
+        }
+    }
+
+    /// Canonicalizes `path` (resolving `..` and symlinks) and checks it lies inside an
+    /// approved root.
+    fn resolve(&self, path: &str) -> Result<PathBuf, String> {
+        let path = Path::new(path);
+        if !path.is_absolute() {
+            return Err("path must be absolute".into());
+        }
+        let canonical = std::fs::canonicalize(path).map_err(io_error)?;
+        if self.roots.iter().any(|root| canonical.starts_with(root)) {
+            Ok(canonical)
+        } else {
+            Err(format!(
+                "{} is outside the approved directories",
+                path.display()
+            ))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PathArgs {
+    path: String,
+}
+
+#[derive(Deserialize)]
+struct CommandArgs {
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct CalculateArgs {
+    expression: String,
+}
+
+fn calculate(arguments: Value) -> Result<String, String> {
+    let CalculateArgs { expression } = parse_args(arguments)?;
+    calculator::evaluate(&expression).map(|value| value.to_string())
+}
+
+fn parse_args<T: for<'de> Deserialize<'de>>(arguments: Value) -> Result<T, String> {
+    serde_json::from_value(arguments).map_err(|err| format!("invalid arguments: {err}"))
+}
+
+fn io_error(err: std::io::Error) -> String {
+    err.to_string()
+}
+
+fn capped(bytes: &[u8]) -> String {
+    let mut text =
+        String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_COMMAND_OUTPUT)]).into_owned();
+    if bytes.len() > MAX_COMMAND_OUTPUT {
+        text.push_str("\n[truncated]");
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn context(root: &Path, allowlist: &[&str]) -> NativeToolContext {
+        NativeToolContext {
+            roots: vec![std::fs::canonicalize(root).unwrap()],
+            allowlist: allowlist.iter().map(|name| name.to_string()).collect(),
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn file_tools_stay_inside_approved_roots() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("approved");
+        std::fs::create_dir_all(root.join("nested")).unwrap();
+        std::fs::write(root.join("notes.txt"), "hello").unwrap();
+        std::fs::write(temp_dir.path().join("secret.txt"), "nope").unwrap();
+        let tools = context(&root, &[]);
+
+        let read = tools
+            .execute(
+                NativeTool::ReadFile,
+                json!({ "path": root.join("notes.txt") }),
+            )
+            .await;
+        assert_eq!(read.output, "hello");
+
+        let escaped = tools
+            .execute(
+                NativeTool::ReadFile,
+                json!({ "path": root.join("../secret.txt") }),
+            )
+            .await;
+        assert!(escaped.is_error);
+
+        let listing = tools
+            .execute(NativeTool::ListDirectory, json!({ "path": root }))
+            .await;
+        assert_eq!(listing.output, "nested/\nnotes.txt");
+    }
+
+    #[tokio::test]
+    async fn commands_need_the_allow_list_and_time_out() {
+        let temp_dir = tempdir().unwrap();
+        let tools = context(temp_dir.path(), &["echo", "sleep"]);
+
+        let echoed = tools
+            .execute(
+                NativeTool::RunCommand,
+                json!({ "command": "echo", "args": ["hi"] }),
+            )
+            .await;
+        assert!(!echoed.is_error);
+        assert!(echoed.output.contains("stdout:\nhi"));
+
+        let blocked = tools
+            .execute(
+                NativeTool::RunCommand,
+                json!({ "command": "rm", "args": ["-rf", "/"] }),
+            )
+            .await;
+        assert!(blocked.is_error);
+
+        let slow = tools
+            .execute(
+                NativeTool::RunCommand,
+                json!({ "command": "sleep", "args": ["5"] }),
+            )
+            .await;
+        assert!(slow.output.contains("timed out"));
+    }
+
+    #[test]
+    fn offers_only_configured_tools() {
+        let tools = NativeToolContext::default();
+        assert_eq!(tools.available(), vec![NativeTool::Calculate]);
+    }
+}
@@ -0,0 +1,661 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, Weak};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{FromRow, SqlitePool};
+use tauri::Emitter;
+use thiserror::Error;
+use tokio_cron_scheduler::{Job, JobScheduler, JobSchedulerError};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::service::SharedConfigService;
+use crate::mcp::manager::SharedMcpManager;
+use crate::services::chat::{send_chat, ChatRequest};
+use crate::services::conversations::ConversationStore;
+use crate::services::job_queue::{JobContext, JobQueueError, JobResult, QueuedJob, SharedJobQueue};
+use crate::services::mcp_context::PromptRef;
+use crate::services::memory::schedule_indexing;
+use crate::services::tool_policy::SharedToolPolicyService;
+
+pub const JOB_COMPLETED_EVENT: &str = "job:completed";
+/// Job queue kind that performs one run; payload is a [`RunRequest`].
+pub const RUN_SCHEDULED_JOB: &str = "scheduled_job.run";
+
+pub type SharedScheduledJobService = Arc<ScheduledJobService>;
+
+#[derive(Debug, Error)]
+pub enum ScheduledJobError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("scheduler error: {0}")]
+    Scheduler(#[from] JobSchedulerError),
+    #[error("serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("{0}")]
+    Queue(#[from] JobQueueError),
+    #[error("invalid schedule `{0}`: expected six cron fields (sec min hour day month weekday)")]
+    InvalidSchedule(String),
+    #[error("a scheduled job needs a prompt or an MCP prompt")]
+    EmptyPrompt,
+    #[error("scheduled job {0} not found")]
+    NotFound(i64),
+    #[error("scheduled job {0} is already running")]
+    AlreadyRunning(i64),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewScheduledJob {
+    pub name: String,
+    /// Six-field cron expression, seconds first: `0 0 9 * * Mon-Fri`.
+    pub schedule: String,
+    #[serde(default)]
+    pub prompt: String,
+    pub mcp_prompt: Option<PromptRef>,
+    /// Defaults to the default provider at run time.
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    /// Offer tools to the model. Calls still follow the tool policies, so `ask` rules wait
+    /// for the user to approve them.
+    #[serde(default)]
+    pub tools: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledJob {
+    pub id: i64,
+    pub name: String,
+    pub schedule: String,
+    pub prompt: String,
+    pub mcp_prompt: Option<PromptRef>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub tools: bool,
+    pub paused: bool,
+    pub last_run_at: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, FromRow)]
+struct JobRow {
+    id: i64,
+    name: String,
+    schedule: String,
+    prompt: String,
+    mcp_prompt: Option<String>,
+    provider: Option<String>,
+    model: Option<String>,
+    tools: bool,
+    paused: bool,
+    last_run_at: Option<String>,
+    created_at: String,
+}
+
+impl From<JobRow> for ScheduledJob {
+    fn from(row: JobRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            schedule: row.schedule,
+            prompt: row.prompt,
+            mcp_prompt: row
+                .mcp_prompt
+                .and_then(|raw| serde_json::from_str(&raw).ok()),
+            provider: row.provider,
+            model: row.model,
+            tools: row.tools,
+            paused: row.paused,
+            last_run_at: row.last_run_at,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RunTrigger {
+    Schedule,
+    Manual,
+}
+
+impl RunTrigger {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Schedule => "schedule",
+            Self::Manual => "manual",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RunRequest {
+    job_id: i64,
+    trigger: RunTrigger,
+}
+
+/// One execution of a job; also the payload of [`JOB_COMPLETED_EVENT`].
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledJobRun {
+    pub id: i64,
+    pub job_id: i64,
+    pub trigger: String,
+    /// `running`, `succeeded` or `failed`.
+    pub status: String,
+    pub conversation_id: Option<String>,
+    pub error: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+}
+
+const JOB_COLUMNS: &str = "id, name, schedule, prompt, mcp_prompt, provider, model, tools, \
+                           paused, last_run_at, created_at";
+const RUN_COLUMNS: &str =
+    "id, job_id, trigger, status, conversation_id, error, started_at, finished_at";
+
+/// Data access for the `scheduled_jobs` and `scheduled_job_runs` tables.
+#[derive(Clone)]
+pub struct ScheduledJobStore {
+    pool: SqlitePool,
+}
+
+impl ScheduledJobStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, job: &NewScheduledJob) -> Result<ScheduledJob, ScheduledJobError> {
+        validate_schedule(&job.schedule)?;
+        if job.prompt.trim().is_empty() && job.mcp_prompt.is_none() {
+            return Err(ScheduledJobError::EmptyPrompt);
+        }
+        let mcp_prompt = job
+            .mcp_prompt
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+        let id = sqlx::query(
+            r#"
+        INSERT INTO scheduled_jobs (name, schedule, prompt, mcp_prompt, provider, model, tools)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+      "#,
+        )
+        .bind(job.name.trim())
+        .bind(job.schedule.trim())
+        .bind(&job.prompt)
+        .bind(mcp_prompt)
+        .bind(&job.provider)
+        .bind(&job.model)
+        .bind(job.tools)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        self.get(id).await
+    }
+
+    pub async fn get(&self, id: i64) -> Result<ScheduledJob, ScheduledJobError> {
+        sqlx::query_as::<_, JobRow>(&format!(
+            "SELECT {JOB_COLUMNS} FROM scheduled_jobs WHERE id = ?1"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(Into::into)
+        .ok_or(ScheduledJobError::NotFound(id))
+    }
+
+    pub async fn list(&self) -> Result<Vec<ScheduledJob>, ScheduledJobError> {
+        let rows = sqlx::query_as::<_, JobRow>(&format!(
+            "SELECT {JOB_COLUMNS} FROM scheduled_jobs ORDER BY name, id"
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn set_paused(
+        &self,
+        id: i64,
+        paused: bool,
+    ) -> Result<ScheduledJob, ScheduledJobError> {
+        let updated = sqlx::query(
+            "UPDATE scheduled_jobs SET paused = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+        )
+        .bind(id)
+        .bind(paused)
+        .execute(&self.pool)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Err(ScheduledJobError::NotFound(id));
+        }
+        self.get(id).await
+    }
+
+    /// Removes the job and its run history; the conversations the runs produced are kept.
+    pub async fn delete(&self, id: i64) -> Result<(), ScheduledJobError> {
+        let deleted = sqlx::query("DELETE FROM scheduled_jobs WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if deleted.rows_affected() == 0 {
+            return Err(ScheduledJobError::NotFound(id));
+        }
+        Ok(())
+    }
+
+    pub async fn start_run(
+        &self,
+        job_id: i64,
+        trigger: RunTrigger,
+        conversation_id: &str,
+    ) -> Result<i64, ScheduledJobError> {
+        let mut tx = self.pool.begin().await?;
+        let id = sqlx::query(
+            r#"
+        INSERT INTO scheduled_job_runs (job_id, trigger, status, conversation_id)
+        VALUES (?1, ?2, 'running', ?3)
+      "#,
+        )
+        .bind(job_id)
+        .bind(trigger.as_str())
+        .bind(conversation_id)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+        sqlx::query("UPDATE scheduled_jobs SET last_run_at = CURRENT_TIMESTAMP WHERE id = ?1")
+            .bind(job_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    pub async fn finish_run(
+        &self,
+        run_id: i64,
+        error: Option<&str>,
+    ) -> Result<ScheduledJobRun, ScheduledJobError> {
+        sqlx::query(
+            r#"
+        UPDATE scheduled_job_runs
+        SET status = ?2, error = ?3, finished_at = CURRENT_TIMESTAMP
+        WHERE id = ?1
+      "#,
+        )
+        .bind(run_id)
+        .bind(if error.is_some() {
+            "failed"
+        } else {
+            "succeeded"
+        })
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+        Ok(sqlx::query_as::<_, ScheduledJobRun>(&format!(
+            "SELECT {RUN_COLUMNS} FROM scheduled_job_runs WHERE id = ?1"
+        ))
+        .bind(run_id)
+        .fetch_one(&self.pool)
+        .await?)
+    }
+
+    /// Runs left `running` by an app that quit mid-run will never finish.
+    pub async fn fail_interrupted_runs(&self) -> Result<u64, ScheduledJobError> {
+        let updated = sqlx::query(
+            r#"
+        UPDATE scheduled_job_runs
+        SET status = 'failed', error = 'interrupted', finished_at = CURRENT_TIMESTAMP
+        WHERE status = 'running'
+      "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(updated.rows_affected())
+    }
+
+    /// Most recent runs first.
+    pub async fn runs(
+        &self,
+        job_id: i64,
+        limit: i64,
+    ) -> Result<Vec<ScheduledJobRun>, ScheduledJobError> {
+        Ok(sqlx::query_as::<_, ScheduledJobRun>(&format!(
+            "SELECT {RUN_COLUMNS} FROM scheduled_job_runs WHERE job_id = ?1 ORDER BY id DESC LIMIT ?2"
+        ))
+        .bind(job_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+}
+
+/// Owns the cron scheduler for user jobs. Due jobs go through the job queue, which runs them
+/// through the regular chat pipeline and keeps them across restarts.
+pub struct ScheduledJobService {
+    store: ScheduledJobStore,
+    scheduler: JobScheduler,
+    /// Scheduler entries of active jobs, keyed by job id.
+    entries: Mutex<HashMap<i64, Uuid>>,
+    running: Mutex<HashSet<i64>>,
+    app: tauri::AppHandle,
+    config: SharedConfigService,
+    mcp: SharedMcpManager,
+    policies: SharedToolPolicyService,
+    queue: SharedJobQueue,
+}
+
+impl ScheduledJobService {
+    /// Registers the run handler with `queue`, schedules every unpaused job and starts the
+    /// scheduler.
+    pub async fn start(
+        app: tauri::AppHandle,
+        config: SharedConfigService,
+        mcp: SharedMcpManager,
+        policies: SharedToolPolicyService,
+        queue: SharedJobQueue,
+    ) -> Result<SharedScheduledJobService, ScheduledJobError> {
+        let store = ScheduledJobStore::new(config.pool().clone());
+        let interrupted = store.fail_interrupted_runs().await?;
+        if interrupted > 0 {
+            warn!(
+                interrupted,
+                "marked unfinished scheduled job runs as failed"
+            );
+        }
+        let service = Arc::new(Self {
+            store,
+            scheduler: JobScheduler::new().await?,
+            entries: Mutex::new(HashMap::new()),
+            running: Mutex::new(HashSet::new()),
+            app,
+            config,
+            mcp,
+            policies,
+            queue,
+        });
+        let handler: Weak<Self> = Arc::downgrade(&service);
+        service.queue.register(RUN_SCHEDULED_JOB, move |context| {
+            let service = handler.upgrade();
+            async move {
+                match service {
+                    Some(service) => service.handle_run(context).await,
+                    None => Err("scheduled jobs are shutting down".into()),
+                }
+            }
+        });
+        for job in service.store.list().await? {
+            if job.paused {
+                continue;
+            }
+            // One bad row should not keep the other jobs from running.
+            if let Err(err) = service.register(&job).await {
+                warn!(job = job.id, "unable to schedule job: {err}");
+            }
+        }
+        service.scheduler.start().await?;
+        Ok(service)
+    }
+
+    pub async fn list(&self) -> Result<Vec<ScheduledJob>, ScheduledJobError> {
+        self.store.list().await
+    }
+
+    pub async fn create(
+        self: &Arc<Self>,
+        job: NewScheduledJob,
+    ) -> Result<ScheduledJob, ScheduledJobError> {
+        let job = self.store.create(&job).await?;
+        self.register(&job).await?;
+        info!(
+            job = job.id,
+            schedule = job.schedule.as_str(),
+            "scheduled job created"
+        );
+        Ok(job)
+    }
+
+    pub async fn set_paused(
+        self: &Arc<Self>,
+        id: i64,
+        paused: bool,
+    ) -> Result<ScheduledJob, ScheduledJobError> {
+        let job = self.store.set_paused(id, paused).await?;
+        if paused {
+            self.unregister(id).await?;
+        } else {
+            self.register(&job).await?;
+        }
+        Ok(job)
+    }
+
+    pub async fn delete(&self, id: i64) -> Result<(), ScheduledJobError> {
+        self.unregister(id).await?;
+        self.store.delete(id).await
+    }
+
+    /// Queues a run right away, paused or not. Completion is reported through
+    /// [`JOB_COMPLETED_EVENT`].
+    pub async fn run_now(&self, id: i64) -> Result<QueuedJob, ScheduledJobError> {
+        self.store.get(id).await?;
+        let queued = self.enqueue(id, RunTrigger::Manual).await?;
+        Ok(self.queue.get(queued).await?)
+    }
+
+    pub async fn history(
+        &self,
+        id: i64,
+        limit: i64,
+    ) -> Result<Vec<ScheduledJobRun>, ScheduledJobError> {
+        self.store.get(id).await?;
+        self.store.runs(id, limit).await
+    }
+
+    async fn register(self: &Arc<Self>, job: &ScheduledJob) -> Result<(), ScheduledJobError> {
+        self.unregister(job.id).await?;
+        let service: Weak<Self> = Arc::downgrade(self);
+        let job_id = job.id;
+        let entry = Job::new_async(job.schedule.as_str(), move |_id, _scheduler| {
+            let service = service.clone();
+            Box::pin(async move {
+                let Some(service) = service.upgrade() else {
+                    return;
+                };
+                if let Err(err) = service.enqueue(job_id, RunTrigger::Schedule).await {
+                    warn!(job = job_id, "unable to queue scheduled job: {err}");
+                }
+            })
+        })
+        .map_err(|_| ScheduledJobError::InvalidSchedule(job.schedule.clone()))?;
+        let entry = self.scheduler.add(entry).await?;
+        lock(&self.entries).insert(job.id, entry);
+        Ok(())
+    }
+
+    async fn unregister(&self, id: i64) -> Result<(), ScheduledJobError> {
+        let entry = lock(&self.entries).remove(&id);
+        if let Some(entry) = entry {
+            self.scheduler.remove(&entry).await?;
+        }
+        Ok(())
+    }
+
+    async fn enqueue(&self, job_id: i64, trigger: RunTrigger) -> Result<i64, ScheduledJobError> {
+        let payload = serde_json::to_value(RunRequest { job_id, trigger })?;
+        Ok(self.queue.enqueue(RUN_SCHEDULED_JOB, payload).await?)
+    }
+
+    /// Queue handler. A failed chat is final: it is recorded on the run, and retrying would
+    /// only leave another conversation behind. Jobs deleted while queued are dropped, and a
+    /// trigger that arrives while the job is still running is skipped. Only a run that
+    /// could not be recorded fails the attempt.
+    async fn handle_run(&self, context: JobContext) -> JobResult {
+        let request: RunRequest =
+            serde_json::from_value(context.payload.clone()).map_err(|err| err.to_string())?;
+        context.progress(0.0, "waiting for the model").await;
+        let run = match self.run(request.job_id, request.trigger).await {
+            Ok(run) => run,
+            Err(ScheduledJobError::NotFound(_)) => return Ok(serde_json::Value::Null),
+            Err(ScheduledJobError::AlreadyRunning(id)) => {
+                info!(job = id, "skipping trigger while the job is still running");
+                return Ok(json!({ "skipped": "already running" }));
+            }
+            Err(err) => return Err(err.to_string()),
+        };
+        Ok(json!({
+            "runId": run.id,
+            "conversationId": run.conversation_id,
+            "error": run.error,
+        }))
+    }
+
+    /// Runs one job as a fresh conversation. Failures of the chat itself are recorded on
+    /// the run; `Err` is only returned when the run could not be recorded at all.
+    async fn run(
+        &self,
+        id: i64,
+        trigger: RunTrigger,
+    ) -> Result<ScheduledJobRun, ScheduledJobError> {
+        let job = self.store.get(id).await?;
+        if !lock(&self.running).insert(id) {
+            return Err(ScheduledJobError::AlreadyRunning(id));
+        }
+        let result = self.execute(&job, trigger).await;
+        lock(&self.running).remove(&id);
+        let run = result?;
+        if let Err(err) = self.app.emit(JOB_COMPLETED_EVENT, run.clone()) {
+            warn!("failed to emit job completion: {err}");
+        }
+        Ok(run)
+    }
+
+    async fn execute(
+        &self,
+        job: &ScheduledJob,
+        trigger: RunTrigger,
+    ) -> Result<ScheduledJobRun, ScheduledJobError> {
+        let conversation_id = format!("job-{}-{}", job.id, Uuid::new_v4());
+        let conversations = ConversationStore::new(self.config.pool().clone());
+        conversations.ensure(&conversation_id).await?;
+        conversations.set_title(&conversation_id, &job.name).await?;
+        let run_id = self
+            .store
+            .start_run(job.id, trigger, &conversation_id)
+            .await?;
+
+        let request = ChatRequest {
+            prompt: job.prompt.clone(),
+            model: job.model.clone(),
+            conversation_id: Some(conversation_id),
+            resources: Vec::new(),
+            mcp_prompt: job.mcp_prompt.clone(),
+            provider: job.provider.clone(),
+            tools: job.tools,
+            memory: None,
+            collection_id: None,
+            attachments: Vec::new(),
+            response_schema: None,
+            regenerate: false,
+        };
+        let error = send_chat(&self.app, &self.config, &self.mcp, &self.policies, request)
+            .await
+            .err()
+            .map(|err| err.to_string());
+        if let Some(error) = &error {
+            warn!(job = job.id, "scheduled job run failed: {error}");
+        } else if let Err(err) = schedule_indexing(&self.config, &self.queue).await {
+            warn!(job = job.id, "unable to queue memory indexing: {err}");
+        }
+        self.store.finish_run(run_id, error.as_deref()).await
+    }
+}
+
+/// Cron parsing lives in the scheduler crate; building a throwaway job is the cheapest way
+/// to ask it.
+fn validate_schedule(schedule: &str) -> Result<(), ScheduledJobError> {
+    Job::new_async(schedule.trim(), |_id, _scheduler| Box::pin(async {}))
+        .map(|_| ())
+        .map_err(|_| ScheduledJobError::InvalidSchedule(schedule.to_string()))
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::config::{paths::ConfigPaths, service::ConfigService};
+
+    fn new_job(schedule: &str, prompt: &str) -> NewScheduledJob {
+        NewScheduledJob {
+            name: "Morning digest".into(),
+            schedule: schedule.into(),
+            prompt: prompt.into(),
+            mcp_prompt: None,
+            provider: None,
+            model: None,
+            tools: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn stores_jobs_and_run_history() {
+        let temp_dir = tempdir().unwrap();
+        let config =
+            ConfigService::with_paths(ConfigPaths::from_base_dir(temp_dir.path()).unwrap())
+                .await
+                .unwrap();
+        let store = ScheduledJobStore::new(config.pool().clone());
+
+        assert!(matches!(
+            store.create(&new_job("every morning", "hi")).await,
+            Err(ScheduledJobError::InvalidSchedule(_))
+        ));
+        assert!(matches!(
+            store.create(&new_job("0 0 9 * * *", "  ")).await,
+            Err(ScheduledJobError::EmptyPrompt)
+        ));
+
+        let job = store
+            .create(&new_job("0 0 9 * * Mon-Fri", "Summarize the news"))
+            .await
+            .unwrap();
+        assert!(!job.paused);
+        assert!(store.set_paused(job.id, true).await.unwrap().paused);
+
+        let succeeded = store
+            .start_run(job.id, RunTrigger::Manual, "job-1-a")
+            .await
+            .unwrap();
+        store.finish_run(succeeded, None).await.unwrap();
+        let interrupted = store
+            .start_run(job.id, RunTrigger::Schedule, "job-1-b")
+            .await
+            .unwrap();
+        assert_eq!(store.fail_interrupted_runs().await.unwrap(), 1);
+
+        let runs = store.runs(job.id, 10).await.unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].id, interrupted);
+        assert_eq!(runs[0].status, "failed");
+        assert_eq!(runs[0].error.as_deref(), Some("interrupted"));
+        assert_eq!(runs[1].status, "succeeded");
+        assert_eq!(runs[1].trigger, "manual");
+        assert!(store.get(job.id).await.unwrap().last_run_at.is_some());
+
+        store.delete(job.id).await.unwrap();
+        assert!(store.runs(job.id, 10).await.unwrap().is_empty());
+        assert!(matches!(
+            store.get(job.id).await,
+            Err(ScheduledJobError::NotFound(_))
+        ));
+    }
+}
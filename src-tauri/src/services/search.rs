@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use thiserror::Error;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 200;
+/// Tokens of context `snippet()` keeps around each match.
+const SNIPPET_TOKENS: i64 = 16;
+// Control characters cannot appear in FTS tokens, so they are safe match delimiters until
+// the snippet is escaped and turned into `<mark>` tags.
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+
+#[derive(Debug, Error)]
+pub enum SearchError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("search query is empty")]
+    EmptyQuery,
+    #[error("invalid date `{0}`: expected YYYY-MM-DD")]
+    InvalidDate(String),
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageSearchQuery {
+    pub query: String,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    /// Inclusive lower bound, `YYYY-MM-DD`.
+    pub from: Option<String>,
+    /// Inclusive upper bound, `YYYY-MM-DD`.
+    pub to: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageHit {
+    pub message_id: i64,
+    pub conversation_id: String,
+    pub conversation_title: Option<String>,
+    pub role: String,
+    /// HTML-escaped excerpt with matches wrapped in `<mark>`.
+    pub snippet: String,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub created_at: String,
+    /// BM25 score; lower is more relevant.
+    pub rank: f64,
+}
+
+/// Searches message content through the `messages_fts` index. Every word must match; the
+/// last one also matches as a prefix so results show up while the user is still typing.
+pub async fn search_messages(
+    pool: &SqlitePool,
+    query: &MessageSearchQuery,
+) -> Result<Vec<MessageHit>, SearchError> {
+    let expression = match_expression(&query.query).ok_or(SearchError::EmptyQuery)?;
+    for date in [&query.from, &query.to].into_iter().flatten() {
+        validate_date(date)?;
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let mut hits = sqlx::query_as::<_, MessageHit>(
+        r#"
+        SELECT
+          m.id AS message_id,
+          m.conversation_id,
+          c.title AS conversation_title,
+          m.role,
+          snippet(messages_fts, 0, ?2, ?3, '…', ?4) AS snippet,
+          m.provider,
+          m.model,
+          m.created_at,
+          bm25(messages_fts) AS rank
+        FROM messages_fts
+        JOIN messages m ON m.id = messages_fts.rowid
+        JOIN conversations c ON c.id = m.conversation_id
+        WHERE messages_fts MATCH ?1
+          AND (?5 IS NULL OR m.provider = ?5)
+          AND (?6 IS NULL OR m.model = ?6)
+          AND (?7 IS NULL OR date(m.created_at) >= date(?7))
+          AND (?8 IS NULL OR date(m.created_at) <= date(?8))
+        ORDER BY rank, m.created_at DESC
+        LIMIT ?9
+      "#,
+    )
+    .bind(expression)
+    .bind(MATCH_START.to_string())
+    .bind(MATCH_END.to_string())
+    .bind(SNIPPET_TOKENS)
+    .bind(&query.provider)
+    .bind(&query.model)
+    .bind(&query.from)
+    .bind(&query.to)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    for hit in &mut hits {
+        hit.snippet = highlight(&hit.snippet);
+    }
+    Ok(hits)
+}
+
+/// Turns free text into an FTS5 expression. Each word is quoted so punctuation and FTS
+/// operators in the input are matched literally instead of failing to parse.
+fn match_expression(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+    let last = terms.len().checked_sub(1)?;
+    Some(
+        terms
+            .iter()
+            .enumerate()
+            .map(|(index, term)| {
+                if index == last {
+                    format!("{term}*")
+                } else {
+                    term.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+fn validate_date(date: &str) -> Result<(), SearchError> {
+    let valid = date.len() == 10
+        && date.char_indices().all(|(index, c)| match index {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(SearchError::InvalidDate(date.to_string()))
+    }
+}
+
+fn highlight(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len() + 16);
+    for c in snippet.chars() {
+        match c {
+            MATCH_START => html.push_str("<mark>"),
+            MATCH_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::config::{paths::ConfigPaths, service::ConfigService};
+    use crate::services::conversations::{ConversationStore, NewMessage};
+
+    #[tokio::test]
+    async fn finds_ranks_and_filters_messages() {
+        let temp_dir = tempdir().unwrap();
+        let config =
+            ConfigService::with_paths(ConfigPaths::from_base_dir(temp_dir.path()).unwrap())
+                .await
+                .unwrap();
+        let pool = config.pool();
+        let store = ConversationStore::new(pool.clone());
+        store.ensure("regex").await.unwrap();
+        store.set_title("regex", "Parsing logs").await.unwrap();
+        store.ensure("other").await.unwrap();
+        for (conversation, content, model) in [
+            ("regex", "Use the regex `^\\d+<x>` to match ids", "gpt-4o"),
+            (
+                "regex",
+                "A regex per line; another regex for headers",
+                "gpt-4o",
+            ),
+            ("other", "Regexes are overkill for this", "gpt-4o-mini"),
+            ("other", "Unrelated message about lunch", "gpt-4o-mini"),
+        ] {
+            store
+                .append(
+                    conversation,
+                    NewMessage {
+                        role: "assistant",
+                        content,
+                        provider: Some("openai"),
+                        model: Some(model),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .unwrap();
+        }
+
+        let search = |query: &str| MessageSearchQuery {
+            query: query.into(),
+            ..Default::default()
+        };
+        let hits = search_messages(pool, &search("regex")).await.unwrap();
+        assert_eq!(hits.len(), 3, "prefix match includes `Regexes`");
+        assert_eq!(hits[0].snippet.matches("<mark>").count(), 2);
+        assert_eq!(hits[0].conversation_title.as_deref(), Some("Parsing logs"));
+        assert!(hits
+            .iter()
+            .any(|hit| hit.snippet.contains("&lt;x&gt;") && !hit.snippet.contains("<x>")));
+
+        let filtered = search_messages(
+            pool,
+            &MessageSearchQuery {
+                model: Some("gpt-4o-mini".into()),
+                ..search("regex")
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].conversation_id, "other");
+
+        let future = search_messages(
+            pool,
+            &MessageSearchQuery {
+                from: Some("2999-01-01".into()),
+                ..search("regex")
+            },
+        )
+        .await
+        .unwrap();
+        assert!(future.is_empty());
+
+        // FTS syntax in user input is matched literally rather than rejected.
+        assert!(search_messages(pool, &search("regex \"AND ("))
+            .await
+            .is_ok());
+        assert!(matches!(
+            search_messages(pool, &search("   ")).await,
+            Err(SearchError::EmptyQuery)
+        ));
+        assert!(matches!(
+            search_messages(
+                pool,
+                &MessageSearchQuery {
+                    to: Some("last week".into()),
+                    ..search("regex")
+                }
+            )
+            .await,
+            Err(SearchError::InvalidDate(_))
+        ));
+
+        sqlx::query("DELETE FROM conversations WHERE id = 'regex'")
+            .execute(pool)
+            .await
+            .unwrap();
+        let hits = search_messages(pool, &search("regex")).await.unwrap();
+        assert_eq!(hits.len(), 1);
+    }
+}
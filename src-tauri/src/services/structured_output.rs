@@ -0,0 +1,489 @@
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::services::attachments::PartFormat;
+
+/// Problems reported back to the model on a retry; more rarely help it.
+const MAX_REPORTED_ERRORS: usize = 8;
+const DEFAULT_SCHEMA_NAME: &str = "response";
+const GEMINI_UNSUPPORTED: &[&str] = &["$schema", "$id", "additionalProperties"];
+
+/// A JSON schema the model's final answer has to satisfy.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseSchema {
+    /// Shown to the model by some providers; defaults to `response`.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub schema: Value,
+    /// OpenAI strict mode, which needs every property listed in `required` and
+    /// `additionalProperties: false` on every object.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl ResponseSchema {
+    /// Providers accept `[A-Za-z0-9_-]{1,64}` only.
+    fn name(&self) -> String {
+        let name: String = self
+            .name
+            .as_deref()
+            .unwrap_or(DEFAULT_SCHEMA_NAME)
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .take(64)
+            .collect();
+        if name.is_empty() {
+            DEFAULT_SCHEMA_NAME.into()
+        } else {
+            name
+        }
+    }
+
+    /// Top-level request fields asking the provider for a reply in this shape. Anthropic
+    /// has no JSON mode, so the model is forced to call a single tool whose `input` is the
+    /// answer; Gemini's schema dialect has no `$schema`, `$id` or `additionalProperties`.
+    pub fn request_fields(&self, format: PartFormat) -> Map<String, Value> {
+        let name = self.name();
+        let fields = match format {
+            PartFormat::OpenAi => json!({
+                "response_format": {
+                    "type": "json_schema",
+                    "json_schema": { "name": name, "schema": self.schema, "strict": self.strict },
+                },
+            }),
+            PartFormat::Gemini => json!({
+                "generationConfig": {
+                    "responseMimeType": "application/json",
+                    "responseSchema": strip_keys(&self.schema, GEMINI_UNSUPPORTED),
+                },
+            }),
+            PartFormat::Anthropic => json!({
+                "tools": [{
+                    "name": name,
+                    "description": "Give the final answer by calling this tool.",
+                    "input_schema": self.schema,
+                }],
+                "tool_choice": { "type": "tool", "name": name },
+            }),
+        };
+        match fields {
+            Value::Object(fields) => fields,
+            _ => Map::new(),
+        }
+    }
+
+    /// Parses a reply (optionally fenced as a ```json block) and validates it. The error
+    /// is written to be sent back to the model.
+    pub fn parse_reply(&self, reply: &str) -> Result<Value, String> {
+        let text = unfence(reply);
+        let value: Value = serde_json::from_str(text)
+            .map_err(|err| format!("the reply is not valid JSON: {err}"))?;
+        self.validate(&value)?;
+        Ok(value)
+    }
+
+    pub fn validate(&self, value: &Value) -> Result<(), String> {
+        let mut errors = Vec::new();
+        check(&self.schema, value, &self.schema, "$", &[], &mut errors);
+        if errors.is_empty() {
+            return Ok(());
+        }
+        let more = errors.len().saturating_sub(MAX_REPORTED_ERRORS);
+        errors.truncate(MAX_REPORTED_ERRORS);
+        if more > 0 {
+            errors.push(format!("and {more} more"));
+        }
+        Err(errors.join("; "))
+    }
+}
+
+fn unfence(reply: &str) -> &str {
+    let text = reply.trim();
+    let Some(body) = text.strip_prefix("```") else {
+        return text;
+    };
+    // Skip the info string (`json`) on the opening line.
+    let body = body.split_once('\n').map_or("", |(_, rest)| rest);
+    body.trim_end().strip_suffix("```").unwrap_or(body).trim()
+}
+
+fn strip_keys(schema: &Value, keys: &[&str]) -> Value {
+    match schema {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(key, _)| !keys.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), strip_keys(value, keys)))
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|item| strip_keys(item, keys)).collect())
+        }
+        other => other.clone(),
+    }
+}
+
+/// Checks `value` against the commonly used part of JSON Schema: `type`, `enum`, `const`,
+/// object, array, string and number bounds, the combinators and local `$ref`s. Keywords
+/// it does not know, such as `pattern` and `format`, are accepted as satisfied. `refs` are
+/// the `$ref`s already followed for this value, which catches schemas that refer to
+/// themselves without descending into the value.
+fn check(
+    schema: &Value,
+    value: &Value,
+    root: &Value,
+    path: &str,
+    refs: &[&str],
+    errors: &mut Vec<String>,
+) {
+    let schema = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => {
+            errors.push(format!("{path}: no value is allowed here"));
+            return;
+        }
+        Value::Object(schema) => schema,
+        _ => return,
+    };
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        if refs.contains(&reference) {
+            errors.push(format!("{path}: circular $ref `{reference}` in the schema"));
+            return;
+        }
+        let refs = [refs, &[reference]].concat();
+        match reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer))
+        {
+            Some(target) => check(target, value, root, path, &refs, errors),
+            None => errors.push(format!("{path}: unresolvable $ref `{reference}`")),
+        }
+    }
+
+    if let Some(expected) = schema.get("type") {
+        let names: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !names.is_empty() && !names.iter().any(|name| has_type(value, name)) {
+            errors.push(format!(
+                "{path}: expected {}, got {}",
+                names.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            errors.push(format!(
+                "{path}: must be one of {}",
+                Value::Array(options.clone())
+            ));
+        }
+    }
+    if let Some(constant) = schema.get("const") {
+        if constant != value {
+            errors.push(format!("{path}: must be {constant}"));
+        }
+    }
+
+    match value {
+        Value::Object(object) => check_object(schema, object, root, path, errors),
+        Value::Array(items) => check_array(schema, items, root, path, errors),
+        Value::String(text) => {
+            let length = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if length < min {
+                    errors.push(format!("{path}: shorter than {min} characters"));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if length > max {
+                    errors.push(format!("{path}: longer than {max} characters"));
+                }
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            let bound = |key: &str| schema.get(key).and_then(Value::as_f64);
+            if bound("minimum").is_some_and(|min| number < min) {
+                errors.push(format!("{path}: below the minimum {}", schema["minimum"]));
+            }
+            if bound("maximum").is_some_and(|max| number > max) {
+                errors.push(format!("{path}: above the maximum {}", schema["maximum"]));
+            }
+            if bound("exclusiveMinimum").is_some_and(|min| number <= min) {
+                errors.push(format!(
+                    "{path}: must exceed {}",
+                    schema["exclusiveMinimum"]
+                ));
+            }
+            if bound("exclusiveMaximum").is_some_and(|max| number >= max) {
+                errors.push(format!(
+                    "{path}: must be below {}",
+                    schema["exclusiveMaximum"]
+                ));
+            }
+        }
+        _ => {}
+    }
+
+    if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+        for sub in all {
+            check(sub, value, root, path, refs, errors);
+        }
+    }
+    if let Some(any) = schema.get("anyOf").and_then(Value::as_array) {
+        if !any.iter().any(|sub| passes(sub, value, root, path, refs)) {
+            errors.push(format!(
+                "{path}: matches none of the allowed shapes (anyOf)"
+            ));
+        }
+    }
+    if let Some(one) = schema.get("oneOf").and_then(Value::as_array) {
+        let matching = one
+            .iter()
+            .filter(|sub| passes(sub, value, root, path, refs))
+            .count();
+        if matching != 1 {
+            errors.push(format!(
+                "{path}: must match exactly one allowed shape (oneOf), matches {matching}"
+            ));
+        }
+    }
+}
+
+fn check_object(
+    schema: &Map<String, Value>,
+    object: &Map<String, Value>,
+    root: &Value,
+    path: &str,
+    errors: &mut Vec<String>,
+) {
+    for key in schema
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+    {
+        if !object.contains_key(key) {
+            errors.push(format!("{path}: missing required property `{key}`"));
+        }
+    }
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (key, item) in object {
+        let item_path = format!("{path}.{key}");
+        match properties.and_then(|properties| properties.get(key)) {
+            Some(property) => check(property, item, root, &item_path, &[], errors),
+            None => match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    errors.push(format!("{path}: unexpected property `{key}`"))
+                }
+                Some(additional) => check(additional, item, root, &item_path, &[], errors),
+                None => {}
+            },
+        }
+    }
+}
+
+fn check_array(
+    schema: &Map<String, Value>,
+    items: &[Value],
+    root: &Value,
+    path: &str,
+    errors: &mut Vec<String>,
+) {
+    let count = items.len() as u64;
+    if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+        if count < min {
+            errors.push(format!("{path}: fewer than {min} items"));
+        }
+    }
+    if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+        if count > max {
+            errors.push(format!("{path}: more than {max} items"));
+        }
+    }
+    if schema.get("uniqueItems") == Some(&Value::Bool(true))
+        && items
+            .iter()
+            .enumerate()
+            .any(|(index, item)| items[..index].contains(item))
+    {
+        errors.push(format!("{path}: items must be unique"));
+    }
+    if let Some(item_schema) = schema.get("items") {
+        for (index, item) in items.iter().enumerate() {
+            check(
+                item_schema,
+                item,
+                root,
+                &format!("{path}[{index}]"),
+                &[],
+                errors,
+            );
+        }
+    }
+}
+
+fn passes(schema: &Value, value: &Value, root: &Value, path: &str, refs: &[&str]) -> bool {
+    let mut errors = Vec::new();
+    check(schema, value, root, path, refs, &mut errors);
+    errors.is_empty()
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invoice_schema() -> ResponseSchema {
+        serde_json::from_value(json!({
+            "name": "invoice lines",
+            "schema": {
+                "type": "object",
+                "required": ["customer", "lines"],
+                "additionalProperties": false,
+                "properties": {
+                    "customer": { "type": "string", "minLength": 1 },
+                    "currency": { "enum": ["EUR", "USD"] },
+                    "lines": { "type": "array", "minItems": 1, "items": { "$ref": "#/$defs/line" } },
+                },
+                "$defs": {
+                    "line": {
+                        "type": "object",
+                        "required": ["sku", "quantity"],
+                        "properties": {
+                            "sku": { "type": "string" },
+                            "quantity": { "type": "integer", "minimum": 1 },
+                        },
+                    },
+                },
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn validates_replies_against_the_schema() {
+        let schema = invoice_schema();
+        let valid = schema
+            .parse_reply(
+                "```json\n{\"customer\": \"ACME\", \"lines\": [{\"sku\": \"A1\", \"quantity\": 2}]}\n```",
+            )
+            .unwrap();
+        assert_eq!(valid["lines"][0]["quantity"], 2);
+
+        let problems = schema
+            .parse_reply(
+                r#"{"customer": "", "currency": "GBP", "notes": 1,
+                    "lines": [{"sku": "A1", "quantity": 0.5}, {"quantity": 3}]}"#,
+            )
+            .unwrap_err();
+        for expected in [
+            "$.customer: shorter than 1 characters",
+            "$.currency: must be one of",
+            "$: unexpected property `notes`",
+            "$.lines[0].quantity: expected integer, got number",
+            "$.lines[1]: missing required property `sku`",
+        ] {
+            assert!(problems.contains(expected), "{expected} not in {problems}");
+        }
+        assert!(schema
+            .parse_reply("Sure! Here it is")
+            .unwrap_err()
+            .starts_with("the reply is not valid JSON"));
+    }
+
+    #[test]
+    fn reports_circular_refs_instead_of_recursing() {
+        for schema in [
+            json!({ "$ref": "#" }),
+            json!({ "$defs": { "a": { "$ref": "#/$defs/a" } }, "$ref": "#/$defs/a" }),
+        ] {
+            let schema = ResponseSchema {
+                name: None,
+                schema,
+                strict: false,
+            };
+            assert!(schema
+                .validate(&json!(1))
+                .unwrap_err()
+                .contains("circular $ref"));
+        }
+        let nested = ResponseSchema {
+            name: None,
+            schema: json!({ "anyOf": [{ "$ref": "#" }] }),
+            strict: false,
+        };
+        assert!(nested.validate(&json!(1)).is_err());
+
+        // Recursion through the value itself is fine.
+        let tree = ResponseSchema {
+            name: None,
+            schema: json!({
+                "type": "object",
+                "properties": { "children": { "type": "array", "items": { "$ref": "#" } } },
+            }),
+            strict: false,
+        };
+        tree.validate(&json!({ "children": [{ "children": [] }] }))
+            .unwrap();
+    }
+
+    #[test]
+    fn maps_the_schema_per_provider() {
+        let schema = invoice_schema();
+        let openai = schema.request_fields(PartFormat::OpenAi);
+        assert_eq!(openai["response_format"]["type"], "json_schema");
+        assert_eq!(
+            openai["response_format"]["json_schema"]["name"],
+            "invoice_lines"
+        );
+
+        let gemini = &schema.request_fields(PartFormat::Gemini)["generationConfig"];
+        assert_eq!(gemini["responseMimeType"], "application/json");
+        assert!(gemini["responseSchema"]
+            .get("additionalProperties")
+            .is_none());
+        assert_eq!(gemini["responseSchema"]["required"][0], "customer");
+
+        let anthropic = schema.request_fields(PartFormat::Anthropic);
+        assert_eq!(anthropic["tool_choice"]["name"], "invoice_lines");
+        assert_eq!(anthropic["tools"][0]["input_schema"], schema.schema);
+    }
+}
@@ -0,0 +1,232 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::config::service::{ConfigService, SharedConfigService};
+use crate::config::ConfigError;
+use crate::services::chat::{complete_once, ChatError};
+use crate::services::conversations::ConversationStore;
+use crate::services::job_queue::{JobContext, JobQueue, JobQueueError, JobResult};
+
+pub const TITLE_CONVERSATION_JOB: &str = "chat.title";
+pub const CONVERSATION_UPDATED_EVENT: &str = "conversation:updated";
+const TITLE_MAX_CHARS: usize = 60;
+/// Only the start of the exchange is sent; it is plenty to name the topic.
+const EXCERPT_CHARS: usize = 2_000;
+const TITLE_INSTRUCTIONS: &str = "You name chat conversations. Reply with a title of at most \
+     six words for the exchange below, in the language of the user's message. No quotes, no \
+     trailing punctuation, nothing else.";
+
+#[derive(Debug, Error)]
+pub enum TitleError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("{0}")]
+    Config(#[from] ConfigError),
+    #[error("{0}")]
+    Chat(#[from] ChatError),
+    #[error("{0}")]
+    Queue(#[from] JobQueueError),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationUpdate {
+    pub conversation_id: String,
+    pub title: String,
+}
+
+/// Queues titling for `conversation_id` unless it already has a title or titling is off.
+pub async fn schedule_titling(
+    config: &ConfigService,
+    queue: &JobQueue,
+    conversation_id: &str,
+) -> Result<Option<i64>, TitleError> {
+    if !config
+        .get_value::<bool>("chat.auto_title")
+        .await?
+        .unwrap_or(true)
+    {
+        return Ok(None);
+    }
+    let store = ConversationStore::new(config.pool().clone());
+    if has_title(store.title(conversation_id).await?.as_deref()) {
+        return Ok(None);
+    }
+    Ok(Some(
+        queue
+            .enqueue(
+                TITLE_CONVERSATION_JOB,
+                json!({ "conversationId": conversation_id }),
+            )
+            .await?,
+    ))
+}
+
+/// Titles an untitled conversation from its first answered prompt. Returns the new title,
+/// or `None` when the conversation is titled already or has no answer yet.
+pub async fn generate_title(
+    config: &ConfigService,
+    conversation_id: &str,
+) -> Result<Option<String>, TitleError> {
+    let store = ConversationStore::new(config.pool().clone());
+    if has_title(store.title(conversation_id).await?.as_deref()) {
+        return Ok(None);
+    }
+    let history = store.history(conversation_id).await?;
+    let Some(prompt) = history
+        .iter()
+        .position(|message| message.role == "user" && !message.content.trim().is_empty())
+    else {
+        return Ok(None);
+    };
+    let Some(answer) = history[prompt..]
+        .iter()
+        .find(|message| message.role == "assistant" && !message.content.trim().is_empty())
+    else {
+        return Ok(None);
+    };
+    let excerpt = |text: &str| text.chars().take(EXCERPT_CHARS).collect::<String>();
+    let input = format!(
+        "User: {}\n\nAssistant: {}",
+        excerpt(&history[prompt].content),
+        excerpt(&answer.content)
+    );
+
+    let credential = config.default_provider_credentials().await?;
+    let model = config
+        .get_value::<String>("chat.title_model")
+        .await?
+        .filter(|model| !model.trim().is_empty())
+        .or(credential.default_model.clone())
+        .unwrap_or_else(|| "gpt-4o-mini".to_string());
+    let reply = complete_once(&credential, &model, TITLE_INSTRUCTIONS, input).await?;
+    let Some(title) = clean_title(&reply) else {
+        return Err(ChatError::EmptyResponse.into());
+    };
+    // The user may have named the conversation while the model was thinking.
+    if has_title(store.title(conversation_id).await?.as_deref()) {
+        return Ok(None);
+    }
+    store.set_title(conversation_id, &title).await?;
+    Ok(Some(title))
+}
+
+pub fn register_title_jobs(queue: &JobQueue, config: SharedConfigService, app: AppHandle) {
+    queue.register(TITLE_CONVERSATION_JOB, move |context| {
+        let config = config.clone();
+        let app = app.clone();
+        async move { title_job(&config, &app, context).await }
+    });
+}
+
+async fn title_job(config: &ConfigService, app: &AppHandle, context: JobContext) -> JobResult {
+    let conversation_id = context
+        .payload
+        .get("conversationId")
+        .and_then(Value::as_str)
+        .ok_or("missing conversationId")?
+        .to_string();
+    let Some(title) = generate_title(config, &conversation_id)
+        .await
+        .map_err(|err| err.to_string())?
+    else {
+        return Ok(Value::Null);
+    };
+    info!(
+        conversation_id = conversation_id.as_str(),
+        "conversation titled"
+    );
+    let update = ConversationUpdate {
+        conversation_id,
+        title,
+    };
+    if let Err(err) = app.emit(CONVERSATION_UPDATED_EVENT, update.clone()) {
+        warn!("unable to emit conversation update: {err}");
+    }
+    Ok(json!(update))
+}
+
+fn has_title(title: Option<&str>) -> bool {
+    title.is_some_and(|title| !title.trim().is_empty())
+}
+
+/// First line of the model's reply without quotes, markup, a `Title:` label or trailing
+/// punctuation, cut to [`TITLE_MAX_CHARS`].
+fn clean_title(reply: &str) -> Option<String> {
+    let markup = |c: char| c.is_whitespace() || "\"'`*#“”‘’".contains(c);
+    let line = reply.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let line = line.trim_matches(markup);
+    let line = line
+        .strip_prefix("Title:")
+        .or_else(|| line.strip_prefix("title:"))
+        .unwrap_or(line)
+        .trim_matches(markup)
+        .trim_end_matches(['.', '!', ':', ';', ','])
+        .trim();
+    if line.is_empty() {
+        return None;
+    }
+    let mut title: String = line.chars().take(TITLE_MAX_CHARS).collect();
+    if title.chars().count() < line.chars().count() {
+        title = format!("{}…", title.trim_end());
+    }
+    Some(title)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::config::{paths::ConfigPaths, service::ConfigService};
+
+    #[test]
+    fn cleans_model_replies_into_titles() {
+        assert_eq!(
+            clean_title("\n\"Planning a Trip to Lisbon.\"\n").as_deref(),
+            Some("Planning a Trip to Lisbon")
+        );
+        assert_eq!(
+            clean_title("**Title:** Rust lifetimes").as_deref(),
+            Some("Rust lifetimes")
+        );
+        assert_eq!(clean_title("  \n\"\""), None);
+        let long = clean_title(&"word ".repeat(30)).unwrap();
+        assert!(long.ends_with('…'));
+        assert!(long.chars().count() <= TITLE_MAX_CHARS + 1);
+    }
+
+    #[tokio::test]
+    async fn schedules_only_untitled_conversations() {
+        let temp_dir = tempdir().unwrap();
+        let config =
+            ConfigService::with_paths(ConfigPaths::from_base_dir(temp_dir.path()).unwrap())
+                .await
+                .unwrap();
+        let queue = JobQueue::new(config.pool().clone()).await;
+        let store = ConversationStore::new(config.pool().clone());
+        store.ensure("fresh").await.unwrap();
+        store.ensure("named").await.unwrap();
+        store.set_title("named", "Already named").await.unwrap();
+
+        assert!(schedule_titling(&config, &queue, "fresh")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(schedule_titling(&config, &queue, "named")
+            .await
+            .unwrap()
+            .is_none());
+        // Without an answered prompt there is nothing to name, and no model is asked.
+        assert_eq!(generate_title(&config, "fresh").await.unwrap(), None);
+
+        config.set_value("chat.auto_title", &false).await.unwrap();
+        assert!(schedule_titling(&config, &queue, "fresh")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use tauri::Emitter;
+use thiserror::Error;
+use tokio::sync::oneshot;
+use tracing::warn;
+
+pub const TOOL_APPROVAL_EVENT: &str = "chat:tool_approval";
+/// Unanswered approval requests count as rejections after this long.
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+/// Policy rows with this tool name apply to every tool of the server.
+const ANY_TOOL: &str = "*";
+
+pub type SharedToolPolicyService = Arc<ToolPolicyService>;
+
+#[derive(Debug, Error)]
+pub enum ToolPolicyError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("unknown or expired approval `{0}`")]
+    UnknownApproval(String),
+    #[error("failed to emit approval request: {0}")]
+    Emit(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ToolPolicy {
+    Allow,
+    Ask,
+    Deny,
+}
+
+impl ToolPolicy {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Ask => "ask",
+            Self::Deny => "deny",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "allow" => Self::Allow,
+            "deny" => Self::Deny,
+            _ => Self::Ask,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolPolicyRule {
+    pub server: String,
+    /// `None` for a server-wide rule.
+    pub tool: Option<String>,
+    pub policy: ToolPolicy,
+}
+
+/// Payload of [`TOOL_APPROVAL_EVENT`]; answered through `approve_tool_call`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolApprovalRequest {
+    pub approval_id: String,
+    pub conversation_id: String,
+    pub call_id: String,
+    pub server: String,
+    pub tool: String,
+    pub arguments: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditDecision {
+    /// Ran under an `allow` policy.
+    Allowed,
+    /// Ran after the user approved it.
+    Approved,
+    /// Blocked by a `deny` policy.
+    Denied,
+    /// The user rejected it or did not answer in time.
+    Rejected,
+    /// The model named a tool that does not exist.
+    Unknown,
+}
+
+impl AuditDecision {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Allowed => "allowed",
+            Self::Approved => "approved",
+            Self::Denied => "denied",
+            Self::Rejected => "rejected",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+pub struct AuditEntry<'a> {
+    pub conversation_id: &'a str,
+    pub call_id: &'a str,
+    pub server: Option<&'a str>,
+    pub tool: &'a str,
+    pub arguments: &'a str,
+    pub decision: AuditDecision,
+    pub is_error: bool,
+    pub result_bytes: usize,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    pub id: i64,
+    pub conversation_id: String,
+    pub call_id: String,
+    pub server: Option<String>,
+    pub tool: String,
+    pub arguments: String,
+    pub decision: String,
+    pub is_error: bool,
+    pub result_bytes: i64,
+    pub duration_ms: i64,
+    pub created_at: String,
+}
+
+struct PendingApproval {
+    server: String,
+    tool: String,
+    reply: oneshot::Sender<bool>,
+}
+
+/// Decides whether model-requested tool calls may run, asks the user when the policy says
+/// so, and records every call in the append-only `tool_audit` table.
+pub struct ToolPolicyService {
+    pool: SqlitePool,
+    pending: Mutex<HashMap<String, PendingApproval>>,
+    next_approval: AtomicU64,
+}
+
+impl ToolPolicyService {
+    pub fn new(pool: SqlitePool) -> SharedToolPolicyService {
+        Arc::new(Self {
+            pool,
+            pending: Mutex::new(HashMap::new()),
+            next_approval: AtomicU64::new(1),
+        })
+    }
+
+    pub async fn list_policies(&self) -> Result<Vec<ToolPolicyRule>, ToolPolicyError> {
+        let rows: Vec<(String, String, String)> =
+            sqlx::query_as("SELECT server, tool, policy FROM tool_policies ORDER BY server, tool")
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(server, tool, policy)| ToolPolicyRule {
+                server,
+                tool: (tool != ANY_TOOL).then_some(tool),
+                policy: ToolPolicy::parse(&policy),
+            })
+            .collect())
+    }
+
+    pub async fn set_policy(
+        &self,
+        server: &str,
+        tool: Option<&str>,
+        policy: ToolPolicy,
+    ) -> Result<(), ToolPolicyError> {
+        sqlx::query(
+            r#"
+        INSERT INTO tool_policies (server, tool, policy)
+        VALUES (?1, ?2, ?3)
+        ON CONFLICT(server, tool) DO UPDATE SET
+          policy = excluded.policy,
+          updated_at = CURRENT_TIMESTAMP
+      "#,
+        )
+        .bind(server)
+        .bind(tool.unwrap_or(ANY_TOOL))
+        .bind(policy.as_str())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn clear_policy(
+        &self,
+        server: &str,
+        tool: Option<&str>,
+    ) -> Result<(), ToolPolicyError> {
+        sqlx::query("DELETE FROM tool_policies WHERE server = ?1 AND tool = ?2")
+            .bind(server)
+            .bind(tool.unwrap_or(ANY_TOOL))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// The tool's own rule, else the server-wide rule, else [`ToolPolicy::Ask`].
+    pub async fn policy_for(
+        &self,
+        server: &str,
+        tool: &str,
+    ) -> Result<ToolPolicy, ToolPolicyError> {
+        let policy: Option<String> = sqlx::query_scalar(
+            r#"
+        SELECT policy FROM tool_policies
+        WHERE server = ?1 AND tool IN (?2, ?3)
+        ORDER BY tool = ?3
+        LIMIT 1
+      "#,
+        )
+        .bind(server)
+        .bind(tool)
+        .bind(ANY_TOOL)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(policy.map_or(ToolPolicy::Ask, |policy| ToolPolicy::parse(&policy)))
+    }
+
+    /// Applies the policy for `request`, asking the user through an app event when needed.
+    pub async fn authorize(
+        &self,
+        app: &tauri::AppHandle,
+        mut request: ToolApprovalRequest,
+    ) -> Result<AuditDecision, ToolPolicyError> {
+        match self.policy_for(&request.server, &request.tool).await? {
+            ToolPolicy::Allow => return Ok(AuditDecision::Allowed),
+            ToolPolicy::Deny => return Ok(AuditDecision::Denied),
+            ToolPolicy::Ask => {}
+        }
+        let approval_id = format!(
+            "approval-{}",
+            self.next_approval.fetch_add(1, Ordering::Relaxed)
+        );
+        request.approval_id = approval_id.clone();
+        let (reply, answer) = oneshot::channel();
+        lock(&self.pending).insert(
+            approval_id.clone(),
+            PendingApproval {
+                server: request.server.clone(),
+                tool: request.tool.clone(),
+                reply,
+            },
+        );
+        if let Err(err) = app.emit(TOOL_APPROVAL_EVENT, request) {
+            lock(&self.pending).remove(&approval_id);
+            return Err(ToolPolicyError::Emit(err.to_string()));
+        }
+        let approved = match tokio::time::timeout(APPROVAL_TIMEOUT, answer).await {
+            Ok(Ok(approved)) => approved,
+            Ok(Err(_)) => false,
+            Err(_) => {
+                warn!(approval = approval_id.as_str(), "tool approval timed out");
+                lock(&self.pending).remove(&approval_id);
+                false
+            }
+        };
+        Ok(if approved {
+            AuditDecision::Approved
+        } else {
+            AuditDecision::Rejected
+        })
+    }
+
+    /// Answers a pending approval. With `remember`, the answer also becomes the tool's policy.
+    pub async fn resolve(
+        &self,
+        approval_id: &str,
+        approved: bool,
+        remember: bool,
+    ) -> Result<(), ToolPolicyError> {
+        let pending = lock(&self.pending)
+            .remove(approval_id)
+            .ok_or_else(|| ToolPolicyError::UnknownApproval(approval_id.to_string()))?;
+        if remember {
+            let policy = if approved {
+                ToolPolicy::Allow
+            } else {
+                ToolPolicy::Deny
+            };
+            self.set_policy(&pending.server, Some(&pending.tool), policy)
+                .await?;
+        }
+        pending
+            .reply
+            .send(approved)
+            .map_err(|_| ToolPolicyError::UnknownApproval(approval_id.to_string()))
+    }
+
+    pub async fn record(&self, entry: AuditEntry<'_>) -> Result<(), ToolPolicyError> {
+        sqlx::query(
+            r#"
+        INSERT INTO tool_audit
+          (conversation_id, call_id, server, tool, arguments, decision, is_error, result_bytes, duration_ms)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+      "#,
+        )
+        .bind(entry.conversation_id)
+        .bind(entry.call_id)
+        .bind(entry.server)
+        .bind(entry.tool)
+        .bind(entry.arguments)
+        .bind(entry.decision.as_str())
+        .bind(entry.is_error)
+        .bind(entry.result_bytes as i64)
+        .bind(entry.duration.as_millis() as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Most recent audit entries first.
+    pub async fn audit_log(&self, limit: i64) -> Result<Vec<AuditRecord>, ToolPolicyError> {
+        Ok(sqlx::query_as::<_, AuditRecord>(
+            r#"
+        SELECT id, conversation_id, call_id, server, tool, arguments, decision, is_error,
+               result_bytes, duration_ms, created_at
+        FROM tool_audit
+        ORDER BY id DESC
+        LIMIT ?1
+      "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::config::{paths::ConfigPaths, service::ConfigService};
+
+    async fn service(dir: &std::path::Path) -> SharedToolPolicyService {
+        let config = ConfigService::with_paths(ConfigPaths::from_base_dir(dir).unwrap())
+            .await
+            .unwrap();
+        ToolPolicyService::new(config.pool().clone())
+    }
+
+    #[tokio::test]
+    async fn tool_rules_override_server_rules() {
+        let temp_dir = tempdir().unwrap();
+        let policies = service(temp_dir.path()).await;
+        assert_eq!(
+            policies.policy_for("fs", "read").await.unwrap(),
+            ToolPolicy::Ask
+        );
+
+        policies
+            .set_policy("fs", None, ToolPolicy::Deny)
+            .await
+            .unwrap();
+        policies
+            .set_policy("fs", Some("read"), ToolPolicy::Allow)
+            .await
+            .unwrap();
+        assert_eq!(
+            policies.policy_for("fs", "read").await.unwrap(),
+            ToolPolicy::Allow
+        );
+        assert_eq!(
+            policies.policy_for("fs", "write").await.unwrap(),
+            ToolPolicy::Deny
+        );
+
+        policies.clear_policy("fs", Some("read")).await.unwrap();
+        assert_eq!(
+            policies.policy_for("fs", "read").await.unwrap(),
+            ToolPolicy::Deny
+        );
+        assert_eq!(policies.list_policies().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn audit_log_is_append_only() {
+        let temp_dir = tempdir().unwrap();
+        let policies = service(temp_dir.path()).await;
+        policies
+            .record(AuditEntry {
+                conversation_id: "c1",
+                call_id: "call_0",
+                server: Some("fake"),
+                tool: "echo",
+                arguments: "{}",
+                decision: AuditDecision::Allowed,
+                is_error: false,
+                result_bytes: 12,
+                duration: Duration::from_millis(5),
+            })
+            .await
+            .unwrap();
+        let log = policies.audit_log(10).await.unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].decision, "allowed");
+        assert_eq!(log[0].result_bytes, 12);
+
+        assert!(sqlx::query("DELETE FROM tool_audit")
+            .execute(&policies.pool)
+            .await
+            .is_err());
+        assert!(sqlx::query("UPDATE tool_audit SET decision = 'denied'")
+            .execute(&policies.pool)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,284 @@
+use serde_json::Value;
+use tracing::warn;
+
+use crate::config::service::ConfigService;
+use crate::config::ConfigError;
+use crate::mcp::manager::McpManager;
+use crate::services::conversations::ToolCall;
+use crate::services::native_tools::{NativeTool, NativeToolContext, NATIVE_SERVER};
+
+const MAX_TOOL_NAME: usize = 64;
+
+/// A tool as offered to the model.
+#[derive(Debug, Clone)]
+pub struct ToolDefinition {
+    /// Model-facing name, unique across servers.
+    pub name: String,
+    pub description: String,
+    pub parameters: Value,
+    pub source: ToolSource,
+}
+
+#[derive(Debug, Clone)]
+pub enum ToolSource {
+    Mcp { server: String, tool: String },
+    Native(NativeTool),
+}
+
+impl ToolSource {
+    pub fn server(&self) -> Option<&str> {
+        match self {
+            Self::Mcp { server, .. } => Some(server),
+            Self::Native(_) => Some(NATIVE_SERVER),
+        }
+    }
+
+    /// The tool's name at its source, as used by approval policies.
+    pub fn tool(&self) -> &str {
+        match self {
+            Self::Mcp { tool, .. } => tool,
+            Self::Native(tool) => tool.name(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ToolOutcome {
+    pub output: String,
+    pub is_error: bool,
+}
+
+/// Tools available for one chat turn, snapshotted when the turn starts.
+#[derive(Debug, Default)]
+pub struct ToolCatalog {
+    tools: Vec<ToolDefinition>,
+    native: NativeToolContext,
+}
+
+impl ToolCatalog {
+    /// Built-in tools keep their plain names; MCP tools are qualified by server so the two
+    /// can never collide.
+    pub async fn discover(config: &ConfigService, mcp: &McpManager) -> Result<Self, ConfigError> {
+        let native = NativeToolContext::load(config).await?;
+        let mut tools: Vec<ToolDefinition> = native
+            .available()
+            .into_iter()
+            .map(|tool| ToolDefinition {
+                name: tool.name().into(),
+                description: tool.description().into(),
+                parameters: tool.parameters(),
+                source: ToolSource::Native(tool),
+            })
+            .collect();
+        for (server, tool) in mcp.list_tools().await {
+            let name = unique_name(&tools, &server, &tool.name);
+            tools.push(ToolDefinition {
+                name,
+                description: tool.description.unwrap_or_default(),
+                parameters: tool.input_schema,
+                source: ToolSource::Mcp {
+                    server,
+                    tool: tool.name,
+                },
+            });
+        }
+        Ok(Self { tools, native })
+    }
+
+    pub fn definitions(&self) -> &[ToolDefinition] {
+        &self.tools
+    }
+
+    pub fn find(&self, name: &str) -> Option<&ToolDefinition> {
+        self.tools.iter().find(|tool| tool.name == name)
+    }
+
+    /// Runs a model-requested call. Failures are returned as error outcomes rather than
+    /// `Err` so the model can see what went wrong and recover.
+    pub async fn execute(&self, mcp: &McpManager, call: &ToolCall) -> ToolOutcome {
+        let Some(definition) = self.find(&call.name) else {
+            return ToolOutcome::error(format!("unknown tool `{}`", call.name));
+        };
+        let arguments: Value = if call.arguments.trim().is_empty() {
+            Value::Object(Default::default())
+        } else {
+            match serde_json::from_str(&call.arguments) {
+                Ok(arguments) => arguments,
+                Err(err) => return ToolOutcome::error(format!("invalid tool arguments: {err}")),
+            }
+        };
+        match &definition.source {
+            ToolSource::Mcp { server, tool } => {
+                let client = match mcp.client(server).await {
+                    Ok(client) => client,
+                    Err(err) => return ToolOutcome::error(err.to_string()),
+                };
+                match client.call_tool(tool, arguments).await {
+                    Ok(result) => ToolOutcome {
+                        output: result.to_text(),
+                        is_error: result.is_error,
+                    },
+                    Err(err) => {
+                        warn!(
+                            server = server.as_str(),
+                            tool = tool.as_str(),
+                            "tool call failed: {err}"
+                        );
+                        ToolOutcome::error(err.to_string())
+                    }
+                }
+            }
+            ToolSource::Native(tool) => self.native.execute(*tool, arguments).await,
+        }
+    }
+}
+
+impl ToolOutcome {
+    pub fn error(message: String) -> Self {
+        Self {
+            output: message,
+            is_error: true,
+        }
+    }
+}
+
+/// `<server>__<tool>`, restricted to the characters and length providers accept for
+/// function names.
+fn qualified_name(server: &str, tool: &str) -> String {
+    format!("{server}__{tool}")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(MAX_TOOL_NAME)
+        .collect()
+}
+
+/// Qualifies the tool and, on a collision, numbers it. The base is cut short enough that
+/// the number always survives the length limit.
+fn unique_name(taken: &[ToolDefinition], server: &str, tool: &str) -> String {
+    let base = qualified_name(server, tool);
+    let is_taken = |name: &str| taken.iter().any(|existing| existing.name == name);
+    if !is_taken(&base) {
+        return base;
+    }
+    (2..)
+        .map(|suffix| {
+            let suffix = format!("_{suffix}");
+            let prefix: String = base.chars().take(MAX_TOOL_NAME - suffix.len()).collect();
+            prefix + &suffix
+        })
+        .find(|name| !is_taken(name))
+        .unwrap_or(base)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::config::{paths::ConfigPaths, service::ConfigService};
+    use crate::mcp::manager::AddMcpServerPayload;
+    use crate::mcp::store::ServerLaunch;
+
+    #[test]
+    fn numbers_colliding_names_within_the_limit() {
+        let mut taken = Vec::new();
+        for tool in [
+            "a".repeat(69) + "1",
+            "a".repeat(69) + "2",
+            "a".repeat(69) + "3",
+        ] {
+            let name = unique_name(&taken, "server", &tool);
+            assert!(name.len() <= MAX_TOOL_NAME);
+            taken.push(ToolDefinition {
+                name,
+                description: String::new(),
+                parameters: Value::Null,
+                source: ToolSource::Mcp {
+                    server: "server".into(),
+                    tool,
+                },
+            });
+        }
+        assert!(taken[1].name.ends_with("_2"));
+        assert!(taken[2].name.ends_with("_3"));
+        assert_ne!(taken[0].name, taken[1].name);
+    }
+
+    #[tokio::test]
+    async fn discovers_and_executes_mcp_tools() {
+        let temp_dir = tempdir().unwrap();
+        let config =
+            ConfigService::with_paths(ConfigPaths::from_base_dir(temp_dir.path()).unwrap())
+                .await
+                .unwrap();
+        let mcp = McpManager::new(config.clone()).await;
+        mcp.add(AddMcpServerPayload {
+            slug: "fake".into(),
+            name: "Fake".into(),
+            launch: ServerLaunch::Stdio {
+                command: "python3".into(),
+                args: vec![concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/tests/fixtures/fake_mcp_server.py"
+                )
+                .into()],
+                env: Default::default(),
+            },
+            auto_start: false,
+            auth_header: None,
+        })
+        .await
+        .unwrap();
+        mcp.start("fake").await.unwrap();
+
+        let catalog = ToolCatalog::discover(&config, &mcp).await.unwrap();
+        let echo = catalog.find("fake__echo").unwrap();
+        assert_eq!(echo.source.server(), Some("fake"));
+        let calculate = catalog.find("calculate").unwrap();
+        assert_eq!(calculate.source.server(), Some(NATIVE_SERVER));
+        assert!(catalog.find("read_file").is_none());
+
+        let outcome = catalog
+            .execute(
+                &mcp,
+                &ToolCall {
+                    id: "call_0".into(),
+                    name: "fake__echo".into(),
+                    arguments: r#"{"text":"hi"}"#.into(),
+                },
+            )
+            .await;
+        assert!(!outcome.is_error);
+        assert_eq!(outcome.output, "echo: hi");
+
+        let outcome = catalog
+            .execute(
+                &mcp,
+                &ToolCall {
+                    id: "call_1".into(),
+                    name: "missing".into(),
+                    arguments: String::new(),
+                },
+            )
+            .await;
+        assert!(outcome.is_error);
+
+        let outcome = catalog
+            .execute(
+                &mcp,
+                &ToolCall {
+                    id: "call_2".into(),
+                    name: "calculate".into(),
+                    arguments: r#"{"expression":"6 * 7"}"#.into(),
+                },
+            )
+            .await;
+        assert_eq!(outcome.output, "42");
+    }
+}
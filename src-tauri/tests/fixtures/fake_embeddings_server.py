@@ -0,0 +1,53 @@
+#!/usr/bin/env python3
+"""Stand-in for the Ollama (/api/embed) and OpenAI (/embeddings) embeddings endpoints.
+
+Prints the bound port on the first line of stdout. Vectors are bags of words hashed into
+DIMENSIONS buckets, so texts that share words are close and unrelated texts are not.
+"""
+import json
+import re
+import sys
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+
+DIMENSIONS = 64
+
+
+def embed(text):
+    vector = [0.0] * DIMENSIONS
+    for word in re.findall(r"[a-z0-9]+", text.lower()):
+        vector[sum(word.encode()) % DIMENSIONS] += 1.0
+    return vector
+
+
+class Handler(BaseHTTPRequestHandler):
+    def do_POST(self):
+        body = json.loads(self.rfile.read(int(self.headers["Content-Length"])))
+        inputs = body["input"] if isinstance(body["input"], list) else [body["input"]]
+        if self.path == "/api/embed":
+            reply = {"model": body["model"], "embeddings": [embed(text) for text in inputs]}
+        elif self.path == "/embeddings":
+            reply = {
+                "data": [
+                    {"index": index, "embedding": embed(text)}
+                    for index, text in enumerate(inputs)
+                ]
+            }
+        else:
+            self.send_error(404)
+            return
+        payload = json.dumps(reply).encode()
+        self.send_response(200)
+        self.send_header("Content-Type", "application/json")
+        self.send_header("Content-Length", str(len(payload)))
+        self.end_headers()
+        self.wfile.write(payload)
+
+    def log_message(self, *args):
+        pass
+
+
+if __name__ == "__main__":
+    server = ThreadingHTTPServer(("127.0.0.1", 0), Handler)
+    print(server.server_address[1], flush=True)
+    sys.stdout.close()
+    server.serve_forever()
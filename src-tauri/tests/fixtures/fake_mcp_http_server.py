@@ -0,0 +1,165 @@
+#!/usr/bin/env python3
+"""HTTP flavour of fake_mcp_server.py: serves Streamable HTTP on /mcp and legacy SSE on /sse.
+
+Prints the bound port on the first line of stdout. Pass `--token VALUE` to require
+`Authorization: Bearer VALUE`. The `reset` method drops every session after replying.
+"""
+import json
+import queue
+import sys
+import threading
+import uuid
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+from urllib.parse import parse_qs, urlparse
+
+from fake_mcp_server import handle
+
+TOKEN = sys.argv[sys.argv.index("--token") + 1] if "--token" in sys.argv else None
+SESSIONS = set()
+SSE_QUEUES = {}
+LOCK = threading.Lock()
+
+
+def respond(message):
+    """Returns the JSON-RPC reply for a request, or None for notifications and responses."""
+    if "id" not in message or "method" not in message:
+        return None
+    method = message["method"]
+    try:
+        if method == "reset":
+            result = {}
+        else:
+            result = handle(method, message.get("params") or {})
+        return {"jsonrpc": "2.0", "id": message["id"], "result": result}
+    except LookupError:
+        return {
+            "jsonrpc": "2.0",
+            "id": message["id"],
+            "error": {"code": -32601, "message": f"unknown method {method}"},
+        }
+
+
+class Handler(BaseHTTPRequestHandler):
+    protocol_version = "HTTP/1.1"
+
+    def log_message(self, *args):
+        pass
+
+    def authorized(self):
+        if TOKEN is None or self.headers.get("Authorization") == f"Bearer {TOKEN}":
+            return True
+        self.send_empty(401)
+        return False
+
+    def send_empty(self, status, headers=None):
+        self.send_response(status)
+        for name, value in (headers or {}).items():
+            self.send_header(name, value)
+        self.send_header("Content-Length", "0")
+        self.end_headers()
+
+    def read_json(self):
+        length = int(self.headers.get("Content-Length", 0))
+        return json.loads(self.rfile.read(length))
+
+    def do_POST(self):
+        if not self.authorized():
+            return
+        url = urlparse(self.path)
+        if url.path == "/mcp":
+            self.streamable_post()
+        elif url.path == "/messages":
+            self.sse_post(parse_qs(url.query).get("session", [""])[0])
+        else:
+            self.send_empty(404)
+
+    def do_GET(self):
+        if not self.authorized():
+            return
+        if urlparse(self.path).path != "/sse":
+            self.send_empty(405)
+            return
+        session = uuid.uuid4().hex
+        outbox = queue.Queue()
+        with LOCK:
+            SSE_QUEUES[session] = outbox
+        self.send_response(200)
+        self.send_header("Content-Type", "text/event-stream")
+        self.send_header("Cache-Control", "no-cache")
+        self.end_headers()
+        self.write_event("endpoint", f"/messages?session={session}")
+        while True:
+            message = outbox.get()
+            if message is None:
+                break
+            self.write_event("message", json.dumps(message))
+        self.close_connection = True
+
+    def do_DELETE(self):
+        with LOCK:
+            SESSIONS.discard(self.headers.get("Mcp-Session-Id"))
+        self.send_empty(200)
+
+    def streamable_post(self):
+        message = self.read_json()
+        session = self.headers.get("Mcp-Session-Id")
+        headers = {}
+        if message.get("method") == "initialize":
+            session = uuid.uuid4().hex
+            with LOCK:
+                SESSIONS.add(session)
+            headers["Mcp-Session-Id"] = session
+        elif session not in SESSIONS:
+            self.send_empty(404)
+            return
+
+        reply = respond(message)
+        if message.get("method") == "reset":
+            with LOCK:
+                SESSIONS.clear()
+        if reply is None:
+            self.send_empty(202)
+        elif message.get("method") == "tools/call":
+            # Exercise the event-stream response path.
+            body = f"event: message\ndata: {json.dumps(reply)}\n\n".encode()
+            self.send_body(body, "text/event-stream", headers)
+        else:
+            self.send_body(json.dumps(reply).encode(), "application/json", headers)
+
+    def sse_post(self, session):
+        with LOCK:
+            outbox = SSE_QUEUES.get(session)
+        if outbox is None:
+            self.send_empty(404)
+            return
+        message = self.read_json()
+        reply = respond(message)
+        self.send_empty(202)
+        if reply is not None:
+            outbox.put(reply)
+        if message.get("method") == "reset":
+            outbox.put(None)
+
+    def send_body(self, body, content_type, headers):
+        self.send_response(200)
+        self.send_header("Content-Type", content_type)
+        self.send_header("Content-Length", str(len(body)))
+        for name, value in headers.items():
+            self.send_header(name, value)
+        self.end_headers()
+        self.wfile.write(body)
+
+    def write_event(self, event, data):
+        self.wfile.write(f"event: {event}\ndata: {data}\n\n".encode())
+        self.wfile.flush()
+
+
+def main():
+    server = ThreadingHTTPServer(("127.0.0.1", 0), Handler)
+    server.daemon_threads = True
+    print(server.server_address[1], flush=True)
+    server.serve_forever()
+
+
+if __name__ == "__main__":
+    main()
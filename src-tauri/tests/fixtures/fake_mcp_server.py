@@ -0,0 +1,108 @@
+#!/usr/bin/env python3
+"""Minimal stdio MCP server used by the Rust test suite."""
+import json
+import os
+import sys
+
+
+GREETING_URI = "memo://greeting"
+GREETING = "Hello from fake-mcp"
+RESOURCES = [
+    {"uri": GREETING_URI, "name": "greeting", "mimeType": "text/plain"},
+    {"uri": "memo://logo", "name": "logo", "mimeType": "image/png"},
+]
+
+
+def reply(message_id, result=None, error=None):
+    message = {"jsonrpc": "2.0", "id": message_id}
+    if error is not None:
+        message["error"] = error
+    else:
+        message["result"] = result
+    sys.stdout.write(json.dumps(message) + "\n")
+    sys.stdout.flush()
+
+
+def handle(method, params):
+    if method == "initialize":
+        return {
+            "protocolVersion": params.get("protocolVersion", "2024-11-05"),
+            "capabilities": {"tools": {}, "resources": {}, "prompts": {}},
+            "serverInfo": {"name": "fake-mcp", "version": "0.1.0"},
+            "instructions": os.environ.get("FAKE_MCP_INSTRUCTIONS"),
+        }
+    if method == "ping":
+        return {}
+    if method == "tools/list":
+        return {
+            "tools": [
+                {
+                    "name": "echo",
+                    "description": "Echoes the given text back.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {"text": {"type": "string"}},
+                        "required": ["text"],
+                    },
+                }
+            ]
+        }
+    if method == "tools/call":
+        if params.get("name") != "echo":
+            return {"content": [{"type": "text", "text": "unknown tool"}], "isError": True}
+        text = params.get("arguments", {}).get("text", "")
+        return {"content": [{"type": "text", "text": f"echo: {text}"}]}
+    if method == "resources/list":
+        # Two pages, to exercise cursor handling.
+        if params.get("cursor") == "2":
+            return {"resources": [RESOURCES[1]]}
+        return {"resources": [RESOURCES[0]], "nextCursor": "2"}
+    if method == "resources/read":
+        if params.get("uri") != GREETING_URI:
+            raise LookupError(params.get("uri"))
+        return {"contents": [{"uri": GREETING_URI, "mimeType": "text/plain", "text": GREETING}]}
+    if method == "prompts/list":
+        return {
+            "prompts": [
+                {
+                    "name": "summarize",
+                    "description": "Asks for a summary of a topic.",
+                    "arguments": [{"name": "topic", "required": True}],
+                }
+            ]
+        }
+    if method == "prompts/get":
+        topic = (params.get("arguments") or {}).get("topic", "nothing")
+        return {
+            "messages": [
+                {"role": "user", "content": {"type": "text", "text": f"Summarize {topic}."}},
+                {
+                    "role": "user",
+                    "content": {
+                        "type": "resource",
+                        "resource": {"uri": GREETING_URI, "text": GREETING},
+                    },
+                },
+            ]
+        }
+    if method == "exit":
+        sys.exit(0)
+    raise LookupError(method)
+
+
+def main():
+    for line in sys.stdin:
+        line = line.strip()
+        if not line:
+            continue
+        message = json.loads(line)
+        if "id" not in message:
+            continue
+        try:
+            reply(message["id"], handle(message.get("method"), message.get("params") or {}))
+        except LookupError as missing:
+            reply(message["id"], error={"code": -32601, "message": f"unknown method {missing}"})
+
+
+if __name__ == "__main__":
+    main()
@@ -4,16 +4,29 @@ export interface ChatMessage {
   id: string
   role: 'user' | 'assistant' | 'system'
   content: string
+  /** The model's thinking, shown collapsed. */
+  reasoning?: string
   createdAt: number
 }
 
 interface InvokeChatResponse {
   reply: string
   model: string
+  /** The parsed reply when a response schema was requested. */
+  structured?: unknown
 }
 
+export type ChatChunkKind =
+  | 'content'
+  | 'reasoning'
+  | 'tool_call'
+  | 'tool_result'
+  | 'usage'
+  | 'error'
+
 export interface ChatStreamChunk {
   conversationId: string
+  kind: ChatChunkKind
   delta: string
   done: boolean
   model?: string
@@ -28,12 +28,20 @@ export function ChatScreen() {
                 } ${message.status === 'error' ? 'border-destructive text-destructive' : ''}`}
               >
                 {message.role === 'assistant' ? (
-                  <ReactMarkdown
-                    remarkPlugins={[remarkGfm]}
-                    className="prose prose-invert max-w-none text-sm"
-                  >
-                    {message.content || '...'}
-                  </ReactMarkdown>
+                  <>
+                    {message.reasoning ? (
+                      <details className="mb-2 text-xs text-muted-foreground">
+                        <summary className="cursor-pointer select-none">Reasoning</summary>
+                        <p className="mt-1 whitespace-pre-wrap">{message.reasoning}</p>
+                      </details>
+                    ) : null}
+                    <ReactMarkdown
+                      remarkPlugins={[remarkGfm]}
+                      className="prose prose-invert max-w-none text-sm"
+                    >
+                      {message.content || '...'}
+                    </ReactMarkdown>
+                  </>
                 ) : (
                   message.content
                 )}
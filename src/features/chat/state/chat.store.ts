@@ -65,7 +65,14 @@ export const useChatStore = create<ChatState>((set, get) => ({
             if (msg.id !== assistantId) {
               return msg
             }
-            const nextContent = payload.done ? msg.content : `${msg.content}${payload.delta}`
+            if (payload.kind === 'error') {
+              return { ...msg, status: 'error' }
+            }
+            if (payload.kind === 'reasoning') {
+              return { ...msg, reasoning: `${msg.reasoning ?? ''}${payload.delta}` }
+            }
+            const appends = !payload.done && payload.kind === 'content'
+            const nextContent = appends ? `${msg.content}${payload.delta}` : msg.content
             return {
               ...msg,
               content: nextContent,